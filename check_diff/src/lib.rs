@@ -233,6 +233,25 @@ where
     }
 }
 
+impl<S, T> DiffChecker<S, T>
+where
+    T: CodeFormatter,
+{
+    /// Creates a diff between running the target formatter on a file path, and running it again
+    /// on its own output. The diff is empty if the target formatter is idempotent on the file.
+    pub fn create_idempotence_diff<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<Diff, FormatCodeError> {
+        let first_pass = self.target.format_code_from_path(path)?;
+        let second_pass = self.target.format_code(&first_pass)?;
+        Ok(Diff {
+            source: first_pass,
+            target: second_pass,
+        })
+    }
+}
+
 impl RustfmtRunner {
     fn get_binary_version(&self) -> Result<String, CheckDiffError> {
         let Ok(command) = Command::new(&self.binary_path)
@@ -839,6 +858,46 @@ pub fn check_diff_for_file<'repo, P: AsRef<Path>, F: AsRef<Path>>(
     }
 }
 
+/// Checks that formatting a file a second time with the target binary doesn't change it.
+pub fn check_idempotence_for_file<'repo, P: AsRef<Path>, F: AsRef<Path>>(
+    diff_checker: &DiffChecker<impl CodeFormatter, impl CodeFormatter>,
+    repo: &'repo Repository<P>,
+    file: F,
+) -> Result<(), (Diff, F, &'repo Repository<P>)> {
+    let relative_path = repo.relative_path(&file);
+    let repo_name = repo.name();
+
+    trace!(
+        "Formatting '{0}' file {0}/{1} twice",
+        repo_name,
+        relative_path.display()
+    );
+
+    match diff_checker.create_idempotence_diff(file.as_ref()) {
+        Ok(diff) => {
+            if !diff.is_empty() {
+                Err((diff, file, repo))
+            } else {
+                trace!(
+                    "Formatting is idempotent in '{0}' for {0}/{1}",
+                    repo_name,
+                    relative_path.display(),
+                );
+                Ok(())
+            }
+        }
+        Err(e) => {
+            debug!(
+                "`target` rustfmt failed to format {}/{}\n{:?}",
+                repo_name,
+                relative_path.display(),
+                e,
+            );
+            Ok(())
+        }
+    }
+}
+
 /// parse out the repository name from a GitHub Repository name.
 pub fn get_repo_name(git_url: &str) -> &str {
     let strip_git_prefix = git_url.strip_suffix(".git").unwrap_or(git_url);
@@ -857,6 +916,39 @@ where
     P: AsRef<Path> + Sync + Send,
     S: CodeFormatter + Sync,
     T: CodeFormatter + Sync,
+{
+    check_files(repositories, worker_threads, |file, repo| {
+        check_diff_for_file(diff_checker, repo, file)
+    })
+}
+
+/// Finds the files in `repositories` which the target binary formats differently when formatting
+/// them a second time.
+pub fn check_idempotence<'repo, P, S, T>(
+    diff_checker: &DiffChecker<S, T>,
+    repositories: &'repo [Repository<P>],
+    worker_threads: std::num::NonZeroU8,
+) -> Vec<(Diff, PathBuf, &'repo Repository<P>)>
+where
+    P: AsRef<Path> + Sync + Send,
+    S: CodeFormatter + Sync,
+    T: CodeFormatter + Sync,
+{
+    check_files(repositories, worker_threads, |file, repo| {
+        check_idempotence_for_file(diff_checker, repo, file)
+    })
+}
+
+/// Runs `check` on every file in `repositories`, and returns the errors it produced.
+fn check_files<'repo, P, C>(
+    repositories: &'repo [Repository<P>],
+    worker_threads: std::num::NonZeroU8,
+    check: C,
+) -> Vec<(Diff, PathBuf, &'repo Repository<P>)>
+where
+    P: AsRef<Path> + Sync + Send,
+    C: Fn(PathBuf, &'repo Repository<P>) -> Result<(), (Diff, PathBuf, &'repo Repository<P>)>
+        + Sync,
 {
     let (tx, rx) = crossbeam_channel::unbounded();
 
@@ -885,9 +977,10 @@ where
         for _ in 0..u8::from(worker_threads) {
             let errors = Arc::clone(&errors);
             let rx = rx.clone();
+            let check = &check;
             s.spawn(move || {
                 while let Ok((file, repo)) = rx.recv() {
-                    if let Err(e) = check_diff_for_file(diff_checker, repo, file) {
+                    if let Err(e) = check(file, repo) {
                         // Push errors to report on later
                         errors.lock().unwrap().push(e);
                     }
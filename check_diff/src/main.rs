@@ -2,7 +2,8 @@ use std::io::Error;
 use std::process::ExitCode;
 
 use check_diff::{
-    Edition, StyleEdition, check_diff, clone_repositories_for_diff_check, compile_rustfmt,
+    Edition, StyleEdition, check_diff, check_idempotence, clone_repositories_for_diff_check,
+    compile_rustfmt,
 };
 use clap::Parser;
 use tempfile::tempdir;
@@ -67,6 +68,10 @@ struct CliInputs {
     // Choosing 16 as the default since that's a common multiple of available CPU cores.
     #[arg(short, long, default_value_t = std::num::NonZeroU8::new(16).unwrap())]
     worker_threads: std::num::NonZeroU8,
+    /// Also check that the feature branch rustfmt doesn't change its own output when formatting
+    /// it a second time
+    #[arg(long)]
+    verify_idempotent: bool,
 }
 
 fn main() -> Result<ExitCode, Error> {
@@ -101,7 +106,14 @@ fn main() -> Result<ExitCode, Error> {
     info!("Starting the Diff Check");
     let errors = check_diff(&diff_checker, &repositories, args.worker_threads);
 
-    if errors.is_empty() {
+    let idempotence_errors = if args.verify_idempotent {
+        info!("Starting the Idempotence Check");
+        check_idempotence(&diff_checker, &repositories, args.worker_threads)
+    } else {
+        Vec::new()
+    };
+
+    if errors.is_empty() && idempotence_errors.is_empty() {
         info!("No diff found 😊");
         return Ok(ExitCode::SUCCESS);
     }
@@ -118,6 +130,26 @@ fn main() -> Result<ExitCode, Error> {
         );
     }
 
-    error!("{} formatting diffs found 💔", errors.len());
+    for (diff, file, repo) in idempotence_errors.iter() {
+        let repo_name = repo.name();
+        let relative_path = repo.relative_path(&file);
+
+        error!(
+            "Formatting is not idempotent in '{0}' when formatting {0}/{1} twice\n{2}",
+            repo_name,
+            relative_path.display(),
+            diff,
+        );
+    }
+
+    if !errors.is_empty() {
+        error!("{} formatting diffs found 💔", errors.len());
+    }
+    if !idempotence_errors.is_empty() {
+        error!(
+            "{} files not formatted idempotently 💔",
+            idempotence_errors.len()
+        );
+    }
     Ok(ExitCode::FAILURE)
 }
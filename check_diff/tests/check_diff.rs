@@ -1,6 +1,6 @@
 use check_diff::{
     CheckDiffError, DiffChecker, CodeFormatter, FormatCodeError, Repository,
-    RustFmtFileFinder, check_diff,
+    RustFmtFileFinder, check_diff, check_idempotence,
 };
 use std::fs::File;
 use tempfile::Builder;
@@ -95,3 +95,33 @@ fn check_diff_test_formatting_difference() -> Result<(), CheckDiffError> {
     assert_ne!(errors.len(), 0);
     Ok(())
 }
+
+#[test]
+fn check_idempotence_test_idempotent_formatter() -> Result<(), CheckDiffError> {
+    let diff_checker = DiffChecker::new(AddWhiteSpaceFormatter, DoNothingFormatter);
+    let dir = Builder::new().tempdir_in("").unwrap();
+    let file_path = dir.path().join("test.rs");
+    let _tmp_file = File::create(file_path)?;
+    let repo = Repository::new("https://github.com/rust-lang/rustfmt.git", dir);
+    let repos = [repo];
+    let workers = std::num::NonZeroU8::new(1).unwrap();
+
+    let errors = check_idempotence(&diff_checker, &repos, workers);
+    assert_eq!(errors.len(), 0);
+    Ok(())
+}
+
+#[test]
+fn check_idempotence_test_non_idempotent_formatter() -> Result<(), CheckDiffError> {
+    let diff_checker = DiffChecker::new(DoNothingFormatter, AddWhiteSpaceFormatter);
+    let dir = Builder::new().tempdir_in("").unwrap();
+    let file_path = dir.path().join("test.rs");
+    let _tmp_file = File::create(file_path)?;
+    let repo = Repository::new("https://github.com/rust-lang/rustfmt.git", dir);
+    let repos = [repo];
+    let workers = std::num::NonZeroU8::new(1).unwrap();
+
+    let errors = check_idempotence(&diff_checker, &repos, workers);
+    assert_eq!(errors.len(), 1);
+    Ok(())
+}
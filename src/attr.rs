@@ -1,17 +1,23 @@
 //! Format attributes and meta items.
 
+use std::collections::HashSet;
+
 use rustc_ast::HasAttrs;
 use rustc_ast::ast;
+use rustc_ast::token::Delimiter;
 use rustc_span::{Span, symbol::sym};
 use tracing::debug;
 
-use self::doc_comment::DocCommentFormatter;
+use self::doc_comment::{DocCommentFormatter, format_doc_attr_literal};
 use crate::comment::{CommentStyle, contains_comment, rewrite_doc_comment};
-use crate::config::IndentStyle;
 use crate::config::lists::*;
-use crate::expr::rewrite_literal;
-use crate::lists::{ListFormatting, Separator, definitive_tactic, itemize_list, write_list};
-use crate::overflow;
+use crate::config::{DeriveOrder, IndentStyle, StyleEdition};
+use crate::expr::{rewrite_literal, span_ends_with_comma};
+use crate::lists::{
+    ListFormatting, ListItem, Separator, definitive_tactic, itemize_list, write_list,
+};
+use crate::overflow::{self, IntoOverflowableItem};
+use crate::parse::macros::attr::parse_attr_args;
 use crate::rewrite::{Rewrite, RewriteContext, RewriteError, RewriteErrorExt, RewriteResult};
 use crate::shape::Shape;
 use crate::source_map::SpanUtils;
@@ -80,7 +86,7 @@ fn format_derive(
     context: &RewriteContext<'_>,
 ) -> Option<String> {
     // Collect all items from all attributes
-    let all_items = derives
+    let mut all_items = derives
         .iter()
         .map(|attr| {
             // Parse the derive items and extract the span for each item; if any
@@ -115,6 +121,7 @@ fn format_derive(
         .into_iter()
         .flatten()
         .collect::<Vec<_>>();
+    order_derives(&mut all_items, &context.config.derive_order());
 
     // Collect formatting parameters.
     let prefix = attr_prefix(&derives[0]);
@@ -180,6 +187,40 @@ fn format_derive(
     Some(result)
 }
 
+/// Orders the traits of a derive according to `derive_order`, and removes the duplicate ones
+/// unless the order is preserved. The traits are left as they are if any of them has a comment.
+fn order_derives(items: &mut Vec<ListItem>, derive_order: &DeriveOrder) {
+    if *derive_order == DeriveOrder::Preserve || items.iter().any(ListItem::has_comment) {
+        return;
+    }
+    let mut paths = HashSet::new();
+    items.retain(|item| paths.insert(derive_path(item)));
+    match derive_order {
+        DeriveOrder::Preserve => {}
+        DeriveOrder::Alphabetical => items.sort_by(|a, b| {
+            let (a, b) = (derive_path(a), derive_path(b));
+            derive_name(&a).cmp(derive_name(&b)).then(a.cmp(&b))
+        }),
+        DeriveOrder::Custom(names) => items.sort_by_key(|item| {
+            let path = derive_path(item);
+            names
+                .iter()
+                .position(|name| path == *name || derive_name(&path) == name)
+                .unwrap_or(names.len())
+        }),
+    }
+}
+
+/// The path of a trait of a derive, without whitespace.
+fn derive_path(item: &ListItem) -> String {
+    item.inner_as_ref().split_whitespace().collect()
+}
+
+/// The name of the trait at the end of `path`.
+fn derive_name(path: &str) -> &str {
+    path.rsplit("::").next().unwrap_or(path)
+}
+
 /// Returns the first group of attributes that fills the given predicate.
 /// We consider two doc comments are in different group if they are separated by normal comments.
 fn take_while_with_pred<'a, P>(
@@ -288,20 +329,13 @@ impl Rewrite for ast::MetaItem {
             }
             ast::MetaItemKind::List(ref list) => {
                 let path = rewrite_path(context, PathContext::Type, &None, &self.path, shape)?;
-                let has_trailing_comma = crate::expr::span_ends_with_comma(context, self.span);
-                overflow::rewrite_with_parens(
+                rewrite_attr_list(
                     context,
                     &path,
-                    list.iter(),
+                    list,
                     // 1 = "]"
                     shape.sub_width(1, self.span)?,
                     self.span,
-                    context.config.attr_fn_like_width(),
-                    Some(if has_trailing_comma {
-                        SeparatorTactic::Always
-                    } else {
-                        SeparatorTactic::Never
-                    }),
                 )?
             }
             ast::MetaItemKind::NameValue(ref lit) => {
@@ -363,19 +397,30 @@ impl Rewrite for ast::Attribute {
                     }
                 }
 
+                // Format the code blocks of a doc attribute that is kept as an attribute
+                if context.config.format_code_in_doc_comments() && meta.has_name(sym::doc) {
+                    if let ast::MetaItemKind::NameValue(ref lit) = meta.kind {
+                        let literal_snippet = context.snippet(lit.span);
+                        if let Some(literal) =
+                            format_doc_attr_literal(lit, literal_snippet, context.config)
+                        {
+                            return Ok(format!("{prefix}[doc = {literal}]"));
+                        }
+                    }
+                }
+
                 // 1 = `[`
                 let shape = shape.offset_left(prefix.len() + 1, self.span)?;
                 Ok(meta.rewrite_result(context, shape).map_or_else(
                     |_| snippet.to_owned(),
-                    |rw| match &self.kind {
-                        ast::AttrKind::Normal(normal_attr) => match normal_attr.item.unsafety {
-                            // For #![feature(unsafe_attributes)]
-                            // See https://github.com/rust-lang/rust/issues/123757
-                            ast::Safety::Unsafe(_) => format!("{}[unsafe({})]", prefix, rw),
-                            _ => format!("{}[{}]", prefix, rw),
-                        },
-                        _ => format!("{}[{}]", prefix, rw),
-                    },
+                    |rw| wrap_attr_item(self, prefix, &rw),
+                ))
+            } else if context.config.style_edition() >= StyleEdition::Edition2027 {
+                // 1 = `[`
+                let shape = shape.offset_left(prefix.len() + 1, self.span)?;
+                Ok(rewrite_attr_args(context, self, shape).map_or_else(
+                    || snippet.to_owned(),
+                    |rw| wrap_attr_item(self, prefix, &rw),
                 ))
             } else {
                 Ok(snippet.to_owned())
@@ -384,6 +429,109 @@ impl Rewrite for ast::Attribute {
     }
 }
 
+fn wrap_attr_item(attr: &ast::Attribute, prefix: &str, item: &str) -> String {
+    match &attr.kind {
+        ast::AttrKind::Normal(normal_attr) => match normal_attr.item.unsafety {
+            // For #![feature(unsafe_attributes)]
+            // See https://github.com/rust-lang/rust/issues/123757
+            ast::Safety::Unsafe(_) => format!("{}[unsafe({})]", prefix, item),
+            _ => format!("{}[{}]", prefix, item),
+        },
+        _ => format!("{}[{}]", prefix, item),
+    }
+}
+
+/// An argument of an attribute whose arguments aren't meta items, e.g. of
+/// `#[tracing::instrument(skip(self), fields(id = %req.id))]`. Values may be preceded by the `%`
+/// or `?` sigils of `tracing`, which are kept in the `&'static str`.
+#[derive(Debug)]
+pub(crate) enum AttrArg {
+    /// `path(args)`.
+    List(String, Vec<AttrArg>, Span),
+    /// `path = value`.
+    KeyValue(String, &'static str, Box<ast::Expr>, Span),
+    /// A plain value.
+    Value(&'static str, Box<ast::Expr>, Span),
+}
+
+impl Rewrite for AttrArg {
+    fn rewrite(&self, context: &RewriteContext<'_>, shape: Shape) -> Option<String> {
+        self.rewrite_result(context, shape).ok()
+    }
+
+    fn rewrite_result(&self, context: &RewriteContext<'_>, shape: Shape) -> RewriteResult {
+        match *self {
+            AttrArg::List(ref path, ref args, span) => {
+                rewrite_attr_list(context, path, args, shape, span)
+            }
+            AttrArg::KeyValue(ref key, sigil, ref value, span) => {
+                let lhs = format!("{key} = {sigil}");
+                let value_shape = shape.shrink_left(lhs.len(), span)?;
+                Ok(lhs + &value.rewrite_result(context, value_shape)?)
+            }
+            AttrArg::Value(sigil, ref value, span) => {
+                let value_shape = shape.shrink_left(sigil.len(), span)?;
+                Ok(format!(
+                    "{sigil}{}",
+                    value.rewrite_result(context, value_shape)?
+                ))
+            }
+        }
+    }
+}
+
+/// Rewrites the arguments of `path(...)` in an attribute like those of a function call, keeping
+/// the trailing comma as it is.
+fn rewrite_attr_list<'a, T: 'a + IntoOverflowableItem<'a>>(
+    context: &'a RewriteContext<'_>,
+    path: &'a str,
+    args: &'a [T],
+    shape: Shape,
+    span: Span,
+) -> RewriteResult {
+    let has_trailing_comma = span_ends_with_comma(context, span);
+    overflow::rewrite_with_parens(
+        context,
+        path,
+        args.iter(),
+        shape,
+        span,
+        context.config.attr_fn_like_width(),
+        Some(if has_trailing_comma {
+            SeparatorTactic::Always
+        } else {
+            SeparatorTactic::Never
+        }),
+    )
+}
+
+/// Rewrites the path and arguments of an attribute which can't be parsed as a meta item, if its
+/// arguments can be parsed as `AttrArg`s.
+fn rewrite_attr_args(
+    context: &RewriteContext<'_>,
+    attr: &ast::Attribute,
+    shape: Shape,
+) -> Option<String> {
+    let ast::AttrKind::Normal(ref normal_attr) = attr.kind else {
+        return None;
+    };
+    let ast::AttrArgs::Delimited(ref args) = normal_attr.item.args else {
+        return None;
+    };
+    if args.delim != Delimiter::Parenthesis {
+        return None;
+    }
+    let attr_args = parse_attr_args(context, args.tokens.clone())?;
+    // The arguments are tokens passed to an attribute macro, which are formatted like the
+    // arguments of a macro call.
+    let _guard = context.enter_macro();
+    let item = &normal_attr.item;
+    let path = rewrite_path(context, PathContext::Type, &None, &item.path, shape).ok()?;
+    let span = mk_sp(item.path.span.lo(), args.dspan.close.hi());
+    // 1 = "]"
+    rewrite_attr_list(context, &path, &attr_args, shape.sub_width_opt(1)?, span).ok()
+}
+
 impl Rewrite for [ast::Attribute] {
     fn rewrite(&self, context: &RewriteContext<'_>, shape: Shape) -> Option<String> {
         self.rewrite_result(context, shape).ok()
@@ -443,9 +591,15 @@ impl Rewrite for [ast::Attribute] {
                 continue;
             }
 
-            // Handle derives if we will merge them.
-            if !skip_derives && context.config.merge_derives() && is_derive(&attrs[0]) {
-                let derives = take_while_with_pred(context, attrs, is_derive);
+            // Handle derives if we will merge or order them.
+            let merge_derives = context.config.merge_derives();
+            let reorder_derives = context.config.derive_order() != DeriveOrder::Preserve;
+            if !skip_derives && (merge_derives || reorder_derives) && is_derive(&attrs[0]) {
+                let derives = if merge_derives {
+                    take_while_with_pred(context, attrs, is_derive)
+                } else {
+                    &attrs[..1]
+                };
                 let derive_str = format_derive(derives, shape, context).unknown_error()?;
                 result.push_str(&derive_str);
 
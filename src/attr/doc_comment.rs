@@ -1,5 +1,8 @@
-use crate::comment::CommentStyle;
-use std::fmt::{self, Display};
+use crate::comment::{CommentStyle, format_code_blocks_in_doc_text};
+use crate::config::Config;
+use rustc_ast::ast;
+use std::fmt::{self, Display, Write as _};
+use std::ops::Range;
 
 /// Formats a string as a doc comment using the given [`CommentStyle`].
 pub(super) struct DocCommentFormatter<'a> {
@@ -35,6 +38,122 @@ impl Display for DocCommentFormatter<'_> {
     }
 }
 
+/// Formats the Rust code blocks in the string literal of a doc attribute, e.g. `#[doc = "..."]`.
+/// `snippet` is the literal as written in the source. Only the code of the formatted code blocks
+/// is replaced in it, so the rest, e.g. escapes and escaped line breaks, is kept as it is.
+/// Returns `None` if nothing needs to change.
+pub(super) fn format_doc_attr_literal(
+    lit: &ast::MetaItemLit,
+    snippet: &str,
+    config: &Config,
+) -> Option<String> {
+    let ast::LitKind::Str(symbol, style) = lit.kind else {
+        return None;
+    };
+    let text = symbol.as_str();
+    let code_blocks = format_code_blocks_in_doc_text(text, config);
+    if code_blocks.is_empty() {
+        return None;
+    }
+    let (opener, closer) = match style {
+        ast::StrStyle::Raw(n) => {
+            let hashes = "#".repeat(usize::from(n));
+            (format!("r{hashes}\""), format!("\"{hashes}"))
+        }
+        ast::StrStyle::Cooked => ("\"".to_owned(), "\"".to_owned()),
+    };
+    let is_raw = matches!(style, ast::StrStyle::Raw(_));
+    let contents = snippet.strip_prefix(&opener)?.strip_suffix(&closer)?;
+    let lines = source_lines(contents, is_raw)?;
+    if lines.len() != text.split('\n').count() {
+        return None;
+    }
+
+    let mut result = opener;
+    let mut pos = 0;
+    for code_block in &code_blocks {
+        // The opening fence is always on the line before the code.
+        let fence_line = lines.get(code_block.lines.start.checked_sub(1)?)?;
+        let first_line = lines.get(code_block.lines.start)?;
+        let last_line = lines.get(code_block.lines.end - 1)?;
+        // The code lines are separated like the opening fence and the code in the source.
+        let newline = &contents[fence_line.end..first_line.start];
+        let mut code = Vec::with_capacity(code_block.formatted.len());
+        for line in &code_block.formatted {
+            if is_raw {
+                if line.contains(closer.as_str()) {
+                    return None;
+                }
+                code.push(line.clone());
+            } else {
+                code.push(escape_doc_str(line));
+            }
+        }
+        result.push_str(&contents[pos..first_line.start]);
+        result.push_str(&code.join(newline));
+        pos = last_line.end;
+    }
+    result.push_str(&contents[pos..]);
+    result.push_str(&closer);
+    Some(result)
+}
+
+/// Returns the ranges of `contents`, the source of a string literal without its quotes, which
+/// hold the lines of its value. A range starts at the first character of the line, after any
+/// escaped line break, and ends before the newline or `\n` escape ending the line.
+fn source_lines(contents: &str, is_raw: bool) -> Option<Vec<Range<usize>>> {
+    let mut lines = Vec::new();
+    let mut line_start = None;
+    let mut chars = contents.char_indices().peekable();
+    while let Some((pos, c)) = chars.next() {
+        let is_newline = match c {
+            '\\' if !is_raw => match chars.next()?.1 {
+                'n' => true,
+                '\n' | '\r' => {
+                    // An escaped line break skips the whitespaces after it.
+                    while chars
+                        .next_if(|&(_, c)| matches!(c, ' ' | '\t' | '\n' | '\r'))
+                        .is_some()
+                    {}
+                    continue;
+                }
+                'x' => {
+                    chars.nth(1)?;
+                    false
+                }
+                'u' => {
+                    while chars.next()?.1 != '}' {}
+                    false
+                }
+                _ => false,
+            },
+            _ => c == '\n',
+        };
+        let start = *line_start.get_or_insert(pos);
+        if is_newline {
+            lines.push(start..pos);
+            line_start = None;
+        }
+    }
+    lines.push(line_start.unwrap_or(contents.len())..contents.len());
+    Some(lines)
+}
+
+/// Escapes `s`, a line without newlines, to be part of a (non-raw) string literal.
+fn escape_doc_str(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' | '"' | '\r' | '\t' | '\0' => result.extend(c.escape_default()),
+            c if c.is_control() => {
+                let _ = write!(result, "\\u{{{:x}}}", u32::from(c));
+            }
+            c => result.push(c),
+        }
+    }
+    result
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -75,6 +194,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn doc_str_escapes_quotes_and_backslashes() {
+        assert_eq!(
+            escape_doc_str("let s = \"a\\b\";"),
+            "let s = \\\"a\\\\b\\\";"
+        );
+        assert_eq!(escape_doc_str("a\tb\u{1}"), "a\\tb\\u{1}");
+    }
+
+    #[test]
+    fn source_lines_skip_escapes_and_escaped_line_breaks() {
+        let contents = "a\\u{e9}\\x41\\n\\\n    b\nc\\n";
+        let lines = source_lines(contents, false).unwrap();
+        let lines: Vec<_> = lines.into_iter().map(|range| &contents[range]).collect();
+        assert_eq!(lines, ["a\\u{e9}\\x41", "b", "c", ""]);
+        assert_eq!(source_lines("a\\nb", true).unwrap(), [0..4]);
+    }
+
     fn test_doc_comment_is_formatted_correctly(
         literal: &str,
         expected_comment: &str,
@@ -6,6 +6,7 @@ use io::Error as IoError;
 use thiserror::Error;
 
 use rustfmt_nightly as rustfmt;
+use serde::Serialize;
 use tracing_subscriber::EnvFilter;
 
 use std::collections::HashMap;
@@ -19,7 +20,8 @@ use getopts::{Matches, Options};
 
 use crate::rustfmt::{
     CliOptions, Color, Config, Edition, EmitMode, FileLines, FileName,
-    FormatReportFormatterBuilder, Input, Session, StyleEdition, Verbosity, Version, load_config,
+    FormatReportFormatterBuilder, FormatSummary, Input, Session, StyleEdition, Verbosity, Version,
+    load_config,
 };
 
 const BUG_REPORT_URL: &str = "https://github.com/rust-lang/rustfmt/issues/new?labels=bug";
@@ -83,6 +85,9 @@ pub enum OperationError {
     /// Attempt to generate a minimal config from standard input.
     #[error("The `--print-config=minimal` option doesn't work with standard input.")]
     MinimalPathWithStdin,
+    /// Attempt to print a summary when formatting standard input.
+    #[error("The `--summary-json` option doesn't work with standard input.")]
+    SummaryWithStdin,
     /// An io error during reading or writing.
     #[error("{0}")]
     IoError(IoError),
@@ -116,11 +121,36 @@ fn make_opts() -> Options {
     );
     let is_nightly = is_nightly();
     let emit_opts = if is_nightly {
-        "[files|stdout|coverage|checkstyle|json]"
+        "[files|stdout|coverage|checkstyle|json|unformatted-report]"
     } else {
         "[files|stdout]"
     };
     opts.optopt("", "emit", "What data to emit and how", emit_opts);
+    if is_nightly {
+        opts.optflag(
+            "",
+            "summary-json",
+            "Print a JSON summary of checked, reformatted and skipped files and errors \
+             instead of the usual output.",
+        );
+        opts.optflag(
+            "",
+            "verify-idempotent",
+            "Format each file a second time and report any changes made by the second pass.",
+        );
+        opts.optflag(
+            "",
+            "verify-tokens",
+            "Compare the tokens and comments of each formatted file with the original, and \
+             leave the file unchanged if they differ.",
+        );
+        opts.optflag(
+            "",
+            "markdown",
+            "Format the Rust code blocks of the given Markdown files, leaving the rest of \
+             them untouched.",
+        );
+    }
     opts.optflag("", "backup", "Backup any modified files.");
     opts.optopt(
         "",
@@ -276,6 +306,10 @@ fn execute(opts: &Options) -> Result<i32> {
 }
 
 fn format_string(input: String, options: GetOptsOptions) -> Result<i32> {
+    if options.summary_json {
+        return Err(OperationError::SummaryWithStdin.into());
+    }
+
     // try to read config from local directory
     let (mut config, _) = load_config(Some(Path::new(".")), Some(options.clone()))?;
 
@@ -290,7 +324,10 @@ fn format_string(input: String, options: GetOptsOptions) -> Result<i32> {
                     .set()
                     .emit_mode(options.emit_mode.unwrap_or(EmitMode::Stdout));
             }
-            Some(EmitMode::Stdout) | Some(EmitMode::Checkstyle) | Some(EmitMode::Json) => {
+            Some(EmitMode::Stdout)
+            | Some(EmitMode::Checkstyle)
+            | Some(EmitMode::Json)
+            | Some(EmitMode::UnformattedReport) => {
                 config
                     .set_cli()
                     .emit_mode(options.emit_mode.unwrap_or(EmitMode::Stdout));
@@ -342,8 +379,14 @@ fn format(
         }
     }
 
-    let out = &mut stdout();
-    let mut session = Session::new(config, Some(out));
+    // With `--summary-json`, the summary is the only thing written to stdout.
+    let mut out: Box<dyn Write> = if options.summary_json {
+        Box::new(io::sink())
+    } else {
+        Box::new(stdout())
+    };
+    let mut session = Session::new(config, Some(&mut out));
+    let mut summaries = vec![];
 
     for file in files {
         if !file.exists() {
@@ -367,15 +410,22 @@ fn format(
                     }
                 }
 
-                session.override_config(local_config, |sess| {
-                    format_and_emit_report(sess, Input::File(file))
+                let summary = session.override_config(local_config, |sess| {
+                    format_and_emit_report(sess, Input::File(file.clone()))
                 });
+                summaries.push(InputSummary { file, summary });
             } else {
-                format_and_emit_report(&mut session, Input::File(file));
+                let summary = format_and_emit_report(&mut session, Input::File(file.clone()));
+                summaries.push(InputSummary { file, summary });
             }
         }
     }
 
+    if options.summary_json {
+        serde_json::to_writer(stdout(), &summaries)?;
+        println!();
+    }
+
     // If we were given a path via dump-minimal-config, output any options
     // that were used during formatting as TOML.
     if let Some(path) = minimal_config_path {
@@ -395,7 +445,15 @@ fn format(
     Ok(exit_code)
 }
 
-fn format_and_emit_report<T: Write>(session: &mut Session<'_, T>, input: Input) {
+/// The `--summary-json` output for a single input file.
+#[derive(Serialize)]
+struct InputSummary {
+    file: PathBuf,
+    #[serde(flatten)]
+    summary: FormatSummary,
+}
+
+fn format_and_emit_report<T: Write>(session: &mut Session<'_, T>, input: Input) -> FormatSummary {
     match session.format(input) {
         Ok(report) => {
             if report.has_warnings() {
@@ -406,10 +464,14 @@ fn format_and_emit_report<T: Write>(session: &mut Session<'_, T>, input: Input)
                         .build()
                 );
             }
+            report.summary()
         }
         Err(msg) => {
             eprintln!("Error writing files: {msg}");
             session.add_operational_error();
+            let mut summary = FormatSummary::default();
+            summary.add_error(&msg);
+            summary
         }
     }
 }
@@ -548,6 +610,10 @@ struct GetOptsOptions {
     unstable_features: bool,
     error_on_unformatted: Option<bool>,
     print_misformatted_file_names: bool,
+    summary_json: bool,
+    verify_idempotent: bool,
+    verify_tokens: bool,
+    markdown: bool,
 }
 
 impl GetOptsOptions {
@@ -659,6 +725,25 @@ impl GetOptsOptions {
             options.print_misformatted_file_names = true;
         }
 
+        if rust_nightly && matches.opt_present("summary-json") {
+            options.summary_json = true;
+            // Only list misformatted files in `--check` mode, so that no diff is printed
+            // alongside the summary.
+            options.print_misformatted_file_names = true;
+        }
+
+        if rust_nightly && matches.opt_present("verify-idempotent") {
+            options.verify_idempotent = true;
+        }
+
+        if rust_nightly && matches.opt_present("verify-tokens") {
+            options.verify_tokens = true;
+        }
+
+        if rust_nightly && matches.opt_present("markdown") {
+            options.markdown = true;
+        }
+
         if !rust_nightly {
             if let Some(ref emit_mode) = options.emit_mode {
                 if !STABLE_EMIT_MODES.contains(emit_mode) {
@@ -740,6 +825,15 @@ impl CliOptions for GetOptsOptions {
         if self.print_misformatted_file_names {
             config.set_cli().print_misformatted_file_names(true);
         }
+        if self.verify_idempotent {
+            config.set_cli().verify_idempotent(true);
+        }
+        if self.verify_tokens {
+            config.set_cli().verify_tokens(true);
+        }
+        if self.markdown {
+            config.set_cli().markdown(true);
+        }
 
         for (key, val) in self.inline_config {
             config.override_value(&key, &val);
@@ -798,6 +892,7 @@ fn emit_mode_from_emit_str(emit_str: &str) -> Result<EmitMode> {
         "coverage" => Ok(EmitMode::Coverage),
         "checkstyle" => Ok(EmitMode::Checkstyle),
         "json" => Ok(EmitMode::Json),
+        "unformatted-report" => Ok(EmitMode::UnformattedReport),
         _ => Err(format_err!("Invalid value for `--emit`")),
     }
 }
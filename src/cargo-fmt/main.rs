@@ -10,11 +10,12 @@ use std::fs;
 use std::hash::{Hash, Hasher};
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Stdio};
 use std::str;
 
 use cargo_metadata::Edition;
 use clap::{CommandFactory, Parser};
+use serde::{Deserialize, Serialize};
 
 #[path = "test/mod.rs"]
 #[cfg(test)]
@@ -28,11 +29,17 @@ const fn is_nightly() -> bool {
 }
 
 const MESSAGE_FORMATS: &str = if is_nightly() {
-    "short|json|human"
+    "short|json|json-summary|human"
 } else {
     "short|human"
 };
 
+/// The rustfmt flag used to request a machine-readable summary.
+const SUMMARY_JSON_FLAG: &str = "--summary-json";
+
+/// The rustfmt flag used to format the code blocks of Markdown files.
+const MARKDOWN_FLAG: &str = "--markdown";
+
 #[derive(Parser)]
 #[command(
     disable_version_flag = true,
@@ -86,6 +93,14 @@ pub struct Opts {
     /// Run rustfmt in check mode
     #[arg(long = "check")]
     check: bool,
+
+    /// Report files whose formatting changes when they are formatted a second time
+    #[arg(long = "verify-idempotent")]
+    verify_idempotent: bool,
+
+    /// Also format the Rust code blocks of the Markdown files of the packages
+    #[arg(long = "markdown")]
+    markdown: bool,
 }
 
 fn main() {
@@ -140,6 +155,20 @@ fn execute() -> i32 {
             rustfmt_args.push(check_flag.to_owned());
         }
     }
+    if opts.verify_idempotent {
+        if !is_nightly() {
+            print_usage_to_stderr("--verify-idempotent is only supported in nightly builds");
+            return FAILURE;
+        }
+        let verify_flag = "--verify-idempotent";
+        if !rustfmt_args.iter().any(|o| o == verify_flag) {
+            rustfmt_args.push(verify_flag.to_owned());
+        }
+    }
+    if opts.markdown && !is_nightly() {
+        print_usage_to_stderr("--markdown is only supported in nightly builds");
+        return FAILURE;
+    }
     if let Some(message_format) = opts.message_format {
         if let Err(msg) = convert_message_format_to_rustfmt_args(&message_format, &mut rustfmt_args)
         {
@@ -159,9 +188,16 @@ fn execute() -> i32 {
             &strategy,
             rustfmt_args,
             Some(&manifest_path),
+            opts.markdown,
         ))
     } else {
-        handle_command_status(format_crate(verbosity, &strategy, rustfmt_args, None))
+        handle_command_status(format_crate(
+            verbosity,
+            &strategy,
+            rustfmt_args,
+            None,
+            opts.markdown,
+        ))
     }
 }
 
@@ -221,6 +257,22 @@ fn convert_message_format_to_rustfmt_args(
             rustfmt_args.push(String::from("json"));
             Ok(())
         }
+        "json-summary" => {
+            if !is_nightly() {
+                return Err(String::from(
+                    "--message-format json-summary is only supported in nightly builds",
+                ));
+            }
+            if contains_emit_mode {
+                return Err(String::from(
+                    "cannot include --emit arg when --message-format is set to json-summary",
+                ));
+            }
+            if !rustfmt_args.iter().any(|arg| arg == SUMMARY_JSON_FLAG) {
+                rustfmt_args.push(String::from(SUMMARY_JSON_FLAG));
+            }
+            Ok(())
+        }
         "human" => Ok(()),
         _ => Err(format!(
             "invalid --message-format value: {message_format}. Allowed values are: \
@@ -278,10 +330,19 @@ fn format_crate(
     strategy: &CargoFmtStrategy,
     rustfmt_args: Vec<String>,
     manifest_path: Option<&Path>,
+    markdown: bool,
 ) -> Result<i32, io::Error> {
-    let targets = get_targets(strategy, manifest_path)?;
+    let mut targets = get_targets(strategy, manifest_path)?;
+    if markdown {
+        let markdown_targets = get_markdown_targets(&targets);
+        targets.extend(markdown_targets);
+    }
 
-    // Currently only bin and lib files get formatted.
+    if rustfmt_args.iter().any(|arg| arg == SUMMARY_JSON_FLAG) {
+        return run_rustfmt_with_summary(&targets, &rustfmt_args, verbosity);
+    }
+
+    // Currently only bin and lib files, and Markdown files with `--markdown`, get formatted.
     run_rustfmt(&targets, &rustfmt_args, verbosity)
 }
 
@@ -294,10 +355,14 @@ pub struct Target {
     kind: String,
     /// Rust edition for this target.
     edition: Edition,
+    /// The name of the target.
+    name: String,
+    /// The name of the package the target belongs to.
+    package: String,
 }
 
 impl Target {
-    pub fn from_target(target: &cargo_metadata::Target) -> Self {
+    pub fn from_target(target: &cargo_metadata::Target, package: &str) -> Self {
         let path = PathBuf::from(&target.src_path);
         let canonicalized = fs::canonicalize(&path).unwrap_or(path);
 
@@ -305,10 +370,18 @@ impl Target {
             path: canonicalized,
             kind: target.kind[0].to_string(),
             edition: target.edition,
+            name: target.name.clone(),
+            package: package.to_owned(),
         }
     }
 }
 
+impl Target {
+    fn is_markdown(&self) -> bool {
+        self.kind == "markdown"
+    }
+}
+
 impl PartialEq for Target {
     fn eq(&self, other: &Target) -> bool {
         self.path == other.path
@@ -406,24 +479,18 @@ fn get_targets_root_only(
         )
     };
 
-    let package_targets = match metadata.packages.len() {
-        1 => metadata.packages.into_iter().next().unwrap().targets,
-        _ => metadata
-            .packages
-            .into_iter()
-            .filter(|p| {
-                in_workspace_root
-                    || PathBuf::from(&p.manifest_path)
-                        .canonicalize()
-                        .unwrap_or_default()
-                        == current_dir_manifest
-            })
-            .flat_map(|p| p.targets)
-            .collect(),
-    };
+    let is_single_package = metadata.packages.len() == 1;
+    let packages = metadata.packages.iter().filter(|p| {
+        is_single_package
+            || in_workspace_root
+            || PathBuf::from(&p.manifest_path)
+                .canonicalize()
+                .unwrap_or_default()
+                == current_dir_manifest
+    });
 
-    for target in package_targets {
-        targets.insert(Target::from_target(&target));
+    for package in packages {
+        add_targets(package, targets);
     }
 
     Ok(())
@@ -436,7 +503,7 @@ fn get_targets_recursive(
 ) -> Result<(), io::Error> {
     let metadata = get_cargo_metadata(manifest_path)?;
     for package in &metadata.packages {
-        add_targets(&package.targets, targets);
+        add_targets(package, targets);
 
         // Look for local dependencies using information available since cargo v1.51
         // It's theoretically possible someone could use a newer version of rustfmt with
@@ -476,9 +543,7 @@ fn get_targets_with_hitlist(
 
     for package in metadata.packages {
         if workspace_hitlist.remove(package.name.as_ref()) {
-            for target in package.targets {
-                targets.insert(Target::from_target(&target));
-            }
+            add_targets(&package, targets);
         }
     }
 
@@ -493,10 +558,49 @@ fn get_targets_with_hitlist(
     }
 }
 
-fn add_targets(target_paths: &[cargo_metadata::Target], targets: &mut BTreeSet<Target>) {
-    for target in target_paths {
-        targets.insert(Target::from_target(target));
+fn add_targets(package: &cargo_metadata::Package, targets: &mut BTreeSet<Target>) {
+    for target in &package.targets {
+        targets.insert(Target::from_target(target, package.name.as_ref()));
+    }
+}
+
+/// Returns the Markdown files, e.g. READMEs and mdBook chapters, found in the package directories
+/// of the given targets. Files ignored by git and `target` directories are skipped.
+fn get_markdown_targets(targets: &BTreeSet<Target>) -> BTreeSet<Target> {
+    let mut package_dirs = BTreeSet::new();
+    let mut markdown_targets = BTreeSet::new();
+    for target in targets {
+        let Some(package_dir) = target
+            .path
+            .ancestors()
+            .skip(1)
+            .find(|dir| dir.join("Cargo.toml").is_file())
+        else {
+            continue;
+        };
+        if !package_dirs.insert(package_dir) {
+            continue;
+        }
+
+        let walker = ignore::WalkBuilder::new(package_dir)
+            .filter_entry(|entry| entry.file_name() != "target")
+            .build();
+        for entry in walker.flatten() {
+            let path = entry.path();
+            if entry.file_type().is_some_and(|t| t.is_file())
+                && path.extension().is_some_and(|ext| ext == "md")
+            {
+                markdown_targets.insert(Target {
+                    path: path.to_path_buf(),
+                    kind: String::from("markdown"),
+                    edition: target.edition,
+                    name: entry.file_name().to_string_lossy().into_owned(),
+                    package: target.package.clone(),
+                });
+            }
+        }
     }
+    markdown_targets
 }
 
 fn run_rustfmt(
@@ -512,12 +616,14 @@ fn run_rustfmt(
             }
         })
         .fold(BTreeMap::new(), |mut h, t| {
-            h.entry(&t.edition).or_insert_with(Vec::new).push(&t.path);
+            h.entry((&t.edition, t.is_markdown()))
+                .or_insert_with(Vec::new)
+                .push(&t.path);
             h
         });
 
     let mut status = vec![];
-    for (edition, files) in by_edition {
+    for ((edition, is_markdown), files) in by_edition {
         let stdout = if verbosity == Verbosity::Quiet {
             std::process::Stdio::null()
         } else {
@@ -527,6 +633,9 @@ fn run_rustfmt(
         if verbosity == Verbosity::Verbose {
             print!("rustfmt");
             print!(" --edition {edition}");
+            if is_markdown {
+                print!(" {MARKDOWN_FLAG}");
+            }
             fmt_args.iter().for_each(|f| print!(" {}", f));
             files.iter().for_each(|f| print!(" {}", f.display()));
             println!();
@@ -536,6 +645,7 @@ fn run_rustfmt(
             .stdout(stdout)
             .args(files)
             .args(["--edition", edition.as_str()])
+            .args(is_markdown.then_some(MARKDOWN_FLAG))
             .args(fmt_args)
             .spawn()
             .map_err(|e| match e.kind() {
@@ -556,6 +666,155 @@ fn run_rustfmt(
         .unwrap_or(SUCCESS))
 }
 
+/// Counts reported by `rustfmt --summary-json`, either for a single input file or
+/// accumulated over several of them.
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct FileCounts {
+    files_checked: usize,
+    files_reformatted: usize,
+    skipped: SkippedCounts,
+    errors: BTreeMap<String, usize>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct SkippedCounts {
+    ignored: usize,
+    skip_attribute: usize,
+    generated: usize,
+}
+
+impl FileCounts {
+    fn add(&mut self, other: &FileCounts) {
+        self.files_checked += other.files_checked;
+        self.files_reformatted += other.files_reformatted;
+        self.skipped.ignored += other.skipped.ignored;
+        self.skipped.skip_attribute += other.skipped.skip_attribute;
+        self.skipped.generated += other.skipped.generated;
+        for (kind, count) in &other.errors {
+            *self.errors.entry(kind.clone()).or_default() += count;
+        }
+    }
+}
+
+/// A single entry of the `rustfmt --summary-json` output.
+#[derive(Debug, Deserialize)]
+struct InputSummary {
+    file: PathBuf,
+    #[serde(flatten)]
+    counts: FileCounts,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct TargetSummary {
+    name: String,
+    kind: String,
+    edition: String,
+    path: PathBuf,
+    #[serde(flatten)]
+    counts: FileCounts,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct PackageSummary {
+    name: String,
+    targets: Vec<TargetSummary>,
+}
+
+/// The document printed by `--message-format json-summary`.
+#[derive(Debug, Default, Serialize)]
+struct SummaryReport {
+    packages: Vec<PackageSummary>,
+    totals: FileCounts,
+}
+
+impl SummaryReport {
+    fn add(&mut self, target: &Target, counts: FileCounts) {
+        self.totals.add(&counts);
+        let package = match self.packages.iter().position(|p| p.name == target.package) {
+            Some(idx) => &mut self.packages[idx],
+            None => {
+                self.packages.push(PackageSummary {
+                    name: target.package.clone(),
+                    targets: vec![],
+                });
+                self.packages.last_mut().unwrap()
+            }
+        };
+        package.targets.push(TargetSummary {
+            name: target.name.clone(),
+            kind: target.kind.clone(),
+            edition: target.edition.as_str().to_owned(),
+            path: target.path.clone(),
+            counts,
+        });
+    }
+}
+
+/// Like `run_rustfmt`, but collects the `--summary-json` output of each rustfmt invocation
+/// and prints a single summary of all packages and targets.
+fn run_rustfmt_with_summary(
+    targets: &BTreeSet<Target>,
+    fmt_args: &[String],
+    verbosity: Verbosity,
+) -> Result<i32, io::Error> {
+    let by_edition = targets.iter().fold(BTreeMap::new(), |mut h, t| {
+        h.entry((&t.edition, t.is_markdown()))
+            .or_insert_with(Vec::new)
+            .push(t);
+        h
+    });
+
+    let mut report = SummaryReport::default();
+    let mut status = vec![];
+    for ((edition, is_markdown), edition_targets) in by_edition {
+        let files = edition_targets.iter().map(|t| &t.path);
+        if verbosity == Verbosity::Verbose {
+            eprint!("rustfmt");
+            eprint!(" --edition {edition}");
+            if is_markdown {
+                eprint!(" {MARKDOWN_FLAG}");
+            }
+            fmt_args.iter().for_each(|f| eprint!(" {}", f));
+            files.clone().for_each(|f| eprint!(" {}", f.display()));
+            eprintln!();
+        }
+
+        let output = rustfmt_command()
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .args(files)
+            .args(["--edition", edition.as_str()])
+            .args(is_markdown.then_some(MARKDOWN_FLAG))
+            .args(fmt_args)
+            .output()
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::NotFound => io::Error::new(
+                    io::ErrorKind::Other,
+                    "Could not run rustfmt, please make sure it is in your PATH.",
+                ),
+                _ => e,
+            })?;
+        status.push(output.status);
+
+        let summaries: Vec<InputSummary> = serde_json::from_slice(&output.stdout)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        for summary in summaries {
+            if let Some(target) = edition_targets.iter().find(|t| t.path == summary.file) {
+                report.add(target, summary.counts);
+            }
+        }
+    }
+
+    serde_json::to_writer(io::stdout(), &report)?;
+    println!();
+
+    Ok(status
+        .iter()
+        .filter_map(|s| if s.success() { None } else { s.code() })
+        .next()
+        .unwrap_or(SUCCESS))
+}
+
 fn get_cargo_metadata(manifest_path: Option<&Path>) -> Result<cargo_metadata::Metadata, io::Error> {
     let mut cmd = cargo_metadata::MetadataCommand::new();
     cmd.no_deps();
@@ -8,7 +8,8 @@ fn invalid_message_format_nightly() {
     assert_eq!(
         convert_message_format_to_rustfmt_args("awesome", &mut vec![]),
         Err(String::from(
-            "invalid --message-format value: awesome. Allowed values are: short|json|human"
+            "invalid --message-format value: awesome. Allowed values are: \
+             short|json|json-summary|human"
         )),
     );
 }
@@ -75,6 +76,40 @@ fn json_message_format() {
     );
 }
 
+#[nightly_only_test]
+#[test]
+fn json_summary_message_format_and_emit_arg() {
+    let mut args = vec![String::from("--emit"), String::from("files")];
+    assert_eq!(
+        convert_message_format_to_rustfmt_args("json-summary", &mut args),
+        Err(String::from(
+            "cannot include --emit arg when --message-format is set to json-summary"
+        )),
+    );
+}
+
+#[stable_only_test]
+#[test]
+fn json_summary_message_format_non_nightly() {
+    assert_eq!(
+        convert_message_format_to_rustfmt_args("json-summary", &mut vec![]),
+        Err(String::from(
+            "--message-format json-summary is only supported in nightly builds"
+        )),
+    );
+}
+
+#[nightly_only_test]
+#[test]
+fn json_summary_message_format_with_check() {
+    let mut args = vec![String::from("--check")];
+    assert!(convert_message_format_to_rustfmt_args("json-summary", &mut args).is_ok());
+    assert_eq!(
+        args,
+        vec![String::from("--check"), String::from("--summary-json")]
+    );
+}
+
 #[test]
 fn human_message_format() {
     let exp_args = vec![String::from("--emit"), String::from("json")];
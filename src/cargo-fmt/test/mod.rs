@@ -11,6 +11,8 @@ fn default_options() {
     assert_eq!(false, o.verbose);
     assert_eq!(false, o.version);
     assert_eq!(false, o.check);
+    assert_eq!(false, o.verify_idempotent);
+    assert_eq!(false, o.markdown);
     assert_eq!(empty, o.packages);
     assert_eq!(empty, o.rustfmt_options);
     assert_eq!(false, o.format_all);
@@ -30,6 +32,8 @@ fn good_options() {
         "--message-format",
         "short",
         "--check",
+        "--verify-idempotent",
+        "--markdown",
         "--",
         "--edition",
         "2018",
@@ -38,6 +42,8 @@ fn good_options() {
     assert_eq!(false, o.verbose);
     assert_eq!(false, o.version);
     assert_eq!(true, o.check);
+    assert_eq!(true, o.verify_idempotent);
+    assert_eq!(true, o.markdown);
     assert_eq!(vec!["p1", "p2"], o.packages);
     assert_eq!(vec!["--edition", "2018"], o.rustfmt_options);
     assert_eq!(false, o.format_all);
@@ -29,6 +29,8 @@ mod all_targets {
                 path: get_path(target.path),
                 edition: target.edition,
                 kind: target.kind.to_owned(),
+                name: String::new(),
+                package: String::new(),
             }));
         }
     }
@@ -163,3 +165,28 @@ mod all_targets {
         }
     }
 }
+
+mod markdown_targets {
+    use super::*;
+
+    #[test]
+    fn finds_markdown_files_of_packages() {
+        let root_path = Path::new("tests/cargo-fmt/source/markdown");
+        let get_path = |exp: &str| root_path.join(exp).canonicalize().unwrap();
+        let manifest_path = root_path.join("Cargo.toml");
+        let targets = get_targets(&CargoFmtStrategy::Root, Some(manifest_path.as_path()))
+            .expect("Targets should have been loaded");
+
+        let markdown_targets = get_markdown_targets(&targets);
+        let paths: Vec<_> = markdown_targets.iter().map(|t| t.path.clone()).collect();
+        assert_eq!(
+            paths,
+            vec![get_path("README.md"), get_path("book/src/chapter.md")]
+        );
+        assert!(
+            markdown_targets
+                .iter()
+                .all(|t| t.is_markdown() && t.edition == Edition::E2021 && t.package == "markdown")
+        );
+    }
+}
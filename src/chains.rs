@@ -62,7 +62,10 @@ use rustc_ast::ast;
 use rustc_span::{BytePos, Span, symbol};
 use tracing::debug;
 
-use crate::comment::{CharClasses, FullCodeCharKind, RichChar, rewrite_comment};
+use crate::comment::{
+    CharClasses, FullCodeCharKind, RichChar, combine_strs_with_missing_comments, contains_comment,
+    rewrite_comment,
+};
 use crate::config::{IndentStyle, StyleEdition};
 use crate::expr::rewrite_call;
 use crate::lists::extract_pre_comment;
@@ -72,6 +75,7 @@ use crate::rewrite::{
 };
 use crate::shape::Shape;
 use crate::source_map::SpanUtils;
+use crate::types::rewrite_generic_args;
 use crate::utils::{
     self, filtered_str_fits, first_line_width, last_line_extendable, last_line_width, mk_sp,
     rewrite_ident, trimmed_last_line_width, wrap_str,
@@ -306,17 +310,19 @@ impl Rewrite for ChainItem {
                 parens: false,
             } => expr.rewrite_result(context, shape)?,
             ChainItemKind::MethodCall(ref segment, ref types, ref exprs) => {
-                Self::rewrite_method_call(segment.ident, types, exprs, self.span, context, shape)?
+                Self::rewrite_method_call(segment, types, exprs, self.span, context, shape)?
+            }
+            ChainItemKind::StructField(ident) => {
+                Self::rewrite_dot_ident(self.span.lo(), ident, context, shape)?
             }
-            ChainItemKind::StructField(ident) => format!(".{}", rewrite_ident(context, ident)),
             ChainItemKind::TupleField(ident, nested) => format!(
-                "{}.{}",
+                "{}{}",
                 if nested && context.config.style_edition() <= StyleEdition::Edition2021 {
                     " "
                 } else {
                     ""
                 },
-                rewrite_ident(context, ident)
+                Self::rewrite_dot_ident(self.span.lo(), ident, context, shape)?
             ),
             ChainItemKind::Await => ".await".to_owned(),
             ChainItemKind::Use => ".use".to_owned(),
@@ -348,26 +354,54 @@ impl ChainItem {
     }
 
     fn rewrite_method_call(
-        method_name: symbol::Ident,
+        segment: &ast::PathSegment,
         types: &[ast::GenericArg],
         args: &[Box<ast::Expr>],
         span: Span,
         context: &RewriteContext<'_>,
         shape: Shape,
     ) -> RewriteResult {
-        let type_str = if types.is_empty() {
-            String::new()
-        } else {
-            let type_list = types
-                .iter()
-                .map(|ty| ty.rewrite_result(context, shape))
-                .collect::<Result<Vec<_>, RewriteError>>()?;
+        let method_str = Self::rewrite_dot_ident(span.lo(), segment.ident, context, shape)?;
+        let type_str = match segment.args.as_deref() {
+            _ if types.is_empty() => String::new(),
+            // Generic arguments with comments are rewritten as a list to keep the comments.
+            Some(generic_args @ ast::GenericArgs::AngleBracketed(data))
+                if contains_comment(context.snippet(data.span)) =>
+            {
+                // 2 = `::`
+                let shape = shape.offset_left(last_line_width(&method_str) + 2, span)?;
+                let generics_str = rewrite_generic_args(generic_args, context, shape, data.span)?;
+                format!("::{generics_str}")
+            }
+            _ => {
+                let type_list = types
+                    .iter()
+                    .map(|ty| ty.rewrite_result(context, shape))
+                    .collect::<Result<Vec<_>, RewriteError>>()?;
 
-            format!("::<{}>", type_list.join(", "))
+                format!("::<{}>", type_list.join(", "))
+            }
         };
-        let callee_str = format!(".{}{}", rewrite_ident(context, method_name), type_str);
+        let callee_str = format!("{method_str}{type_str}");
         rewrite_call(context, &callee_str, &args, span, shape)
     }
+
+    /// Rewrites `.ident`, keeping the comments between the dot at `dot_lo` and the identifier.
+    fn rewrite_dot_ident(
+        dot_lo: BytePos,
+        ident: symbol::Ident,
+        context: &RewriteContext<'_>,
+        shape: Shape,
+    ) -> RewriteResult {
+        let ident_str = rewrite_ident(context, ident);
+        let comment_lo = dot_lo + BytePos(1);
+        let comment_span = mk_sp(comment_lo, ident.span.lo().max(comment_lo));
+        if contains_comment(context.snippet(comment_span)) {
+            combine_strs_with_missing_comments(context, ".", ident_str, comment_span, shape, true)
+        } else {
+            Ok(format!(".{ident_str}"))
+        }
+    }
 }
 
 #[derive(Debug)]
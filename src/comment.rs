@@ -1,12 +1,13 @@
 // Formatting and tools for comments.
 
-use std::{borrow::Cow, iter};
+use std::{borrow::Cow, iter, ops::Range};
 
 use itertools::{Itertools as _, MultiPeek, multipeek};
 use rustc_span::Span;
 use tracing::{debug, trace};
 
-use crate::config::Config;
+use crate::config::{Config, Edition};
+use crate::formatting::UnformattedReason;
 use crate::rewrite::{RewriteContext, RewriteErrorExt, RewriteResult};
 use crate::shape::{Indent, Shape};
 use crate::string::{StringFormat, rewrite_string};
@@ -16,6 +17,8 @@ use crate::utils::{
 };
 use crate::{ErrorKind, FormattingError};
 
+mod markdown;
+
 fn is_custom_comment(comment: &str) -> bool {
     if !comment.starts_with("//") {
         false
@@ -237,9 +240,163 @@ pub(crate) fn combine_strs_with_missing_comments(
 }
 
 pub(crate) fn rewrite_doc_comment(orig: &str, shape: Shape, config: &Config) -> RewriteResult {
+    if config.normalize_doc_comments() {
+        if let Some(normalized) = normalize_doc_comment(orig) {
+            return identify_comment(&normalized, false, shape, config, true);
+        }
+    }
     identify_comment(orig, false, shape, config, true)
 }
 
+/// Normalizes the conventions of a group of doc comments:
+///
+/// * `/** */` and `/*! */` blocks become `///` and `//!` lines,
+/// * the text is separated from the comment markers by a single space, keeping the
+///   indentation of lines indented further,
+/// * consecutive blank lines outside of code blocks are collapsed and trailing ones removed,
+/// * links whose text is their own path, like ``[`crate::Foo`](crate::Foo)``, are shortened to
+///   intra-doc links.
+///
+/// Returns `None` if `orig` contains anything but doc comments of the same kind, or if the
+/// group is separated by blank lines or would be left empty.
+fn normalize_doc_comment(orig: &str) -> Option<String> {
+    let mut opener = None;
+    let mut lines: Vec<&str> = vec![];
+    let mut rest = orig.trim_start();
+    while !rest.is_empty() {
+        let (style, is_block) = if rest.starts_with("//!") {
+            ("//!", false)
+        } else if rest.starts_with("///") && !rest.starts_with("////") {
+            ("///", false)
+        } else if rest.starts_with("/*!") {
+            ("//!", true)
+        } else if rest.starts_with("/**") && !rest.starts_with("/***") && !rest.starts_with("/**/")
+        {
+            ("///", true)
+        } else {
+            return None;
+        };
+        if *opener.get_or_insert(style) != style {
+            return None;
+        }
+        let end = if is_block {
+            let end = block_comment_end(rest)?;
+            lines.extend(block_doc_comment_lines(&rest[3..end - 2]));
+            end
+        } else {
+            let end = rest.find('\n').unwrap_or(rest.len());
+            lines.push(rest[3..end].trim_end_matches('\r'));
+            end
+        };
+        let after = &rest[end..];
+        rest = after.trim_start();
+        let separator = &after[..after.len() - rest.len()];
+        if separator.matches('\n').count() > 1 || (!rest.is_empty() && !separator.contains('\n')) {
+            return None;
+        }
+    }
+
+    let opener = opener?;
+    // Rustdoc removes the indentation common to all lines, so the indentation beyond it is kept
+    // but for lines that lack a space after the marker.
+    let indent = lines
+        .iter()
+        .filter(|line| !line.trim_start_matches([' ', '\t']).is_empty())
+        .map(|line| line.len() - line.trim_start_matches([' ', '\t']).len())
+        .min()?;
+
+    let mut result: Vec<String> = vec![];
+    let mut in_code_block = false;
+    for line in lines {
+        let text = if line.trim_start_matches([' ', '\t']).is_empty() {
+            ""
+        } else {
+            let text = &line[indent.saturating_sub(1)..];
+            text.strip_prefix([' ', '\t']).unwrap_or(text)
+        };
+        let is_fence = text.trim_start().starts_with("```") || text.trim_start().starts_with("~~~");
+        if text.is_empty() {
+            if !in_code_block && result.last().is_some_and(|last| last == opener) {
+                continue;
+            }
+            result.push(opener.to_owned());
+        } else if in_code_block || is_fence {
+            result.push(format!("{opener} {text}"));
+        } else {
+            result.push(format!("{opener} {}", shorten_intra_doc_links(text)));
+        }
+        in_code_block ^= is_fence;
+    }
+    while result.last().is_some_and(|last| last == opener) {
+        result.pop();
+    }
+    (!result.is_empty()).then(|| result.join("\n"))
+}
+
+/// Returns the offset right after the `*/` that closes the block comment `s` starts with.
+fn block_comment_end(s: &str) -> Option<usize> {
+    let bytes = s.as_bytes();
+    let mut depth = 0;
+    let mut i = 0;
+    while i + 1 < bytes.len() {
+        match &bytes[i..i + 2] {
+            b"/*" => {
+                depth += 1;
+                i += 2;
+            }
+            b"*/" => {
+                depth -= 1;
+                i += 2;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Returns the lines of the text of a block doc comment, without the leading `*` of each line
+/// if all of them have one.
+fn block_doc_comment_lines(body: &str) -> impl Iterator<Item = &str> {
+    let mut lines = body.lines().map(str::trim_end).collect::<Vec<_>>();
+    let bulleted = lines
+        .iter()
+        .skip(1)
+        .all(|line| line.is_empty() || line.trim_start().starts_with('*'));
+    if bulleted {
+        for line in lines.iter_mut().skip(1) {
+            *line = line.trim_start().strip_prefix('*').unwrap_or(line);
+        }
+    }
+    // Drop the lines of the opening and closing markers.
+    if lines.first().is_some_and(|line| line.trim().is_empty()) {
+        lines.remove(0);
+    }
+    if lines.len() > 1 && lines.last().is_some_and(|line| line.trim().is_empty()) {
+        lines.pop();
+    }
+    lines.into_iter()
+}
+
+/// Shortens the links of `line` whose text is the path they link to, like
+/// ``[`crate::Foo`](crate::Foo)``, to intra-doc links like ``[`crate::Foo`]``.
+pub(crate) fn shorten_intra_doc_links(line: &str) -> Cow<'_, str> {
+    let link = static_regex!(r"\[(`[^`\[\]]+`|[^`\[\]]+)\]\(([A-Za-z_][\w:]*)\)");
+    link.replace_all(line, |captures: &regex::Captures<'_>| {
+        let (text, path) = (&captures[1], &captures[2]);
+        let start = captures.get(0).map_or(0, |m| m.start());
+        // Leave links in code spans alone.
+        let in_code_span = line[..start].matches('`').count() % 2 == 1;
+        if !in_code_span && text.trim_matches('`') == path {
+            format!("[{text}]")
+        } else {
+            captures[0].to_owned()
+        }
+    })
+}
+
 pub(crate) fn rewrite_comment(
     orig: &str,
     block_style: bool,
@@ -348,6 +505,11 @@ fn identify_comment(
     let rewritten_first_group =
         if !config.normalize_comments() && has_bare_lines && style.is_block_comment() {
             trim_left_preserve_layout(first_group, shape.indent, config).unknown_error()?
+        } else if is_doc_comment
+            && config.doc_comment_markdown()
+            && matches!(style, CommentStyle::TripleSlash | CommentStyle::Doc)
+        {
+            rewrite_markdown_doc_comment(first_group, style, shape, config)
         } else if !config.normalize_comments()
             && !config.wrap_comments()
             && !(
@@ -396,24 +558,61 @@ fn identify_comment(
 
 /// Enum indicating if the code block contains rust based on attributes
 enum CodeBlockAttribute {
-    Rust,
+    /// Rust code, with the edition given by an `editionXXXX` attribute if any.
+    Rust(Option<Edition>),
     NotRust,
 }
 
 impl CodeBlockAttribute {
-    /// Parse comma separated attributes list. Return rust only if all
-    /// attributes are valid rust attributes
+    /// Parse comma or whitespace separated attributes list. Return rust only if all
+    /// attributes are valid rust attributes, or are listed in `doc_code_block_rust_tags`,
+    /// and none of them is listed in `doc_code_block_skip_tags`.
     /// See <https://doc.rust-lang.org/rustdoc/print.html#attributes>
-    fn new(attributes: &str) -> CodeBlockAttribute {
-        for attribute in attributes.split(',') {
-            match attribute.trim() {
-                "" | "rust" | "should_panic" | "no_run" | "edition2015" | "edition2018"
-                | "edition2021" => (),
+    fn new(attributes: &str, config: &Config) -> CodeBlockAttribute {
+        Self::parse(attributes, &[], config)
+    }
+
+    /// Parse the info string of a code block in a Markdown file, such as a README or an mdBook
+    /// chapter. Unlike in doc comments, only code blocks tagged `rust` contain rust, and the
+    /// mdBook attributes `editable`, `noplayground` and `mdbook-runnable` are valid as well.
+    /// See <https://rust-lang.github.io/mdBook/format/mdbook.html#rust-code-block-attributes>
+    fn from_markdown(info: &str, config: &Config) -> CodeBlockAttribute {
+        if info.trim_start().split(Self::is_separator).next() != Some("rust") {
+            return CodeBlockAttribute::NotRust;
+        }
+        Self::parse(
+            info,
+            &["editable", "noplayground", "mdbook-runnable"],
+            config,
+        )
+    }
+
+    fn is_separator(c: char) -> bool {
+        c == ',' || c.is_whitespace()
+    }
+
+    fn parse(attributes: &str, extra_rust_attributes: &[&str], config: &Config) -> Self {
+        let mut edition = None;
+        for attribute in attributes.split(Self::is_separator) {
+            if config.doc_code_block_skip_tags().matches(attribute) {
+                return CodeBlockAttribute::NotRust;
+            }
+            if let Some(attribute_edition) = attribute
+                .strip_prefix("edition")
+                .and_then(|year| year.parse().ok())
+            {
+                edition = Some(attribute_edition);
+                continue;
+            }
+            match attribute {
+                "" | "rust" | "should_panic" | "no_run" | "test_harness" => (),
+                _ if extra_rust_attributes.contains(&attribute) => (),
+                _ if config.doc_code_block_rust_tags().matches(attribute) => (),
                 "ignore" | "compile_fail" | "text" => return CodeBlockAttribute::NotRust,
                 _ => return CodeBlockAttribute::NotRust,
             }
         }
-        CodeBlockAttribute::Rust
+        CodeBlockAttribute::Rust(edition)
     }
 }
 
@@ -754,30 +953,17 @@ impl<'a> CommentRewrite<'a> {
         } else if self.code_block_attr.is_some() {
             if line.starts_with("```") {
                 let code_block = match self.code_block_attr.as_ref().unwrap() {
-                    CodeBlockAttribute::Rust
+                    CodeBlockAttribute::Rust(edition)
                         if self.fmt.config.format_code_in_doc_comments()
                             && !self.code_block_buffer.trim().is_empty() =>
                     {
-                        let mut config = self.fmt.config.clone();
-                        config.set().wrap_comments(false);
-                        let comment_max_width = config
-                            .doc_comment_code_block_width()
-                            .min(config.max_width());
-                        config.set().max_width(comment_max_width);
-                        if let Some(comment_use_small_heuristics) = config
-                            .doc_comment_code_block_small_heuristics()
-                            .to_heuristics()
-                        {
-                            config
-                                .set()
-                                .use_small_heuristics(comment_use_small_heuristics);
-                        }
-                        if let Some(s) =
-                            crate::format_code_block(&self.code_block_buffer, &config, false)
-                        {
-                            trim_custom_comment_prefix(&s.snippet)
-                        } else {
-                            trim_custom_comment_prefix(&self.code_block_buffer)
+                        match format_doc_code_block(
+                            &self.code_block_buffer,
+                            *edition,
+                            self.fmt.config,
+                        ) {
+                            Some(s) => trim_custom_comment_prefix(&s),
+                            None => trim_custom_comment_prefix(&self.code_block_buffer),
                         }
                     }
                     _ => trim_custom_comment_prefix(&self.code_block_buffer),
@@ -802,7 +988,7 @@ impl<'a> CommentRewrite<'a> {
         self.code_block_attr = None;
         self.item_block = None;
         if let Some(stripped) = line.strip_prefix("```") {
-            self.code_block_attr = Some(CodeBlockAttribute::new(stripped))
+            self.code_block_attr = Some(CodeBlockAttribute::new(stripped, self.fmt.config))
         } else if self.fmt.config.wrap_comments() {
             if let Some(ib) = ItemizedBlock::new(line) {
                 self.item_block = Some(ib);
@@ -949,6 +1135,214 @@ fn rewrite_comment_inner(
     Ok(rewriter.finish())
 }
 
+/// Rewrites a group of `///` or `//!` comments by reflowing them as Markdown.
+fn rewrite_markdown_doc_comment(
+    orig: &str,
+    style: CommentStyle<'_>,
+    shape: Shape,
+    config: &Config,
+) -> String {
+    let opener = style.opener();
+    let lines: Vec<&str> = orig
+        .lines()
+        .map(|line| left_trim_comment_line(line.trim_start(), &style).0)
+        .collect();
+    let mut format_code = |info: &str, code: &str| {
+        if !config.format_code_in_doc_comments() || code.trim().is_empty() {
+            return None;
+        }
+        let CodeBlockAttribute::Rust(edition) = CodeBlockAttribute::new(info, config) else {
+            return None;
+        };
+        let mut code_block = String::with_capacity(code.len() * 2);
+        for line in code.lines() {
+            code_block.push_str(&hide_sharp_behind_comment(line));
+            code_block.push('\n');
+        }
+        format_doc_code_block(&code_block, edition, config).map(|s| trim_custom_comment_prefix(&s))
+    };
+
+    let width = shape.width.saturating_sub(opener.len());
+    let separator = shape.indent.to_string_with_newline(config);
+    markdown::reflow_markdown(&lines, width, &mut format_code)
+        .iter()
+        .map(|line| {
+            if line.is_empty() {
+                opener.trim_end().to_owned()
+            } else {
+                format!("{opener}{line}")
+            }
+        })
+        .join(&separator)
+}
+
+/// The stack size of the threads formatting the code blocks of another edition, the same as
+/// that of the main thread on most platforms.
+const DOC_CODE_BLOCK_STACK_SIZE: usize = 8 * 1024 * 1024;
+
+/// Formats the Rust code of a code block in a doc comment, whose lines starting with `#` have been
+/// hidden by `hide_sharp_behind_comment`. The code is parsed with `edition` if given.
+fn format_doc_code_block(
+    code_block: &str,
+    edition: Option<Edition>,
+    config: &Config,
+) -> Option<String> {
+    let mut config = config.clone();
+    config.set().wrap_comments(false);
+    let comment_max_width = config
+        .doc_comment_code_block_width()
+        .min(config.max_width());
+    config.set().max_width(comment_max_width);
+    if let Some(comment_use_small_heuristics) = config
+        .doc_comment_code_block_small_heuristics()
+        .to_heuristics()
+    {
+        config
+            .set()
+            .use_small_heuristics(comment_use_small_heuristics);
+    }
+    match edition {
+        Some(edition) if edition != config.edition() => {
+            // The parser takes the edition from the session globals, which can't be replaced on
+            // a thread which already has them, so code of another edition is formatted in a
+            // session of its own on a new thread.
+            config.set().edition(edition);
+            std::thread::scope(|scope| {
+                let handle = std::thread::Builder::new()
+                    .stack_size(DOC_CODE_BLOCK_STACK_SIZE)
+                    .spawn_scoped(scope, move || {
+                        crate::format_code_block(code_block, &config, false)
+                    })
+                    .ok()?;
+                match handle.join() {
+                    Ok(formatted) => formatted.map(|s| s.snippet),
+                    Err(panic) => std::panic::resume_unwind(panic),
+                }
+            })
+        }
+        _ => crate::format_code_block(code_block, &config, false).map(|s| s.snippet),
+    }
+}
+
+/// Formats the Rust code blocks in the text of a doc attribute such as `#[doc = "..."]`. Returns
+/// the code blocks which changed, so that they can be spliced into the literal as written.
+pub(crate) fn format_code_blocks_in_doc_text(
+    text: &str,
+    config: &Config,
+) -> Vec<FormattedCodeBlock> {
+    format_fenced_code_blocks(text, config, CodeBlockAttribute::new)
+}
+
+/// Formats the Rust code blocks of a Markdown file, such as a README or an mdBook chapter, leaving
+/// the prose untouched. Returns `None` if no code block was changed.
+pub(crate) fn format_code_blocks_in_markdown(text: &str, config: &Config) -> Option<String> {
+    let code_blocks = format_fenced_code_blocks(text, config, CodeBlockAttribute::from_markdown);
+    if code_blocks.is_empty() {
+        return None;
+    }
+    let lines: Vec<_> = text.split('\n').collect();
+    let mut result = Vec::with_capacity(lines.len());
+    let mut next_line = 0;
+    for code_block in &code_blocks {
+        result.extend_from_slice(&lines[next_line..code_block.lines.start]);
+        result.extend(code_block.formatted.iter().map(String::as_str));
+        next_line = code_block.lines.end;
+    }
+    result.extend_from_slice(&lines[next_line..]);
+    Some(result.join("\n"))
+}
+
+/// A fenced code block whose code changed when formatted.
+pub(crate) struct FormattedCodeBlock {
+    /// The indices of the lines of code in the text, excluding the fences.
+    pub(crate) lines: Range<usize>,
+    /// The formatted lines of code, which keep the indentation of the opening fence.
+    pub(crate) formatted: Vec<String>,
+}
+
+/// Formats the fenced code blocks of `text` which contain rust according to `code_block_attr`,
+/// and returns those which changed.
+fn format_fenced_code_blocks(
+    text: &str,
+    config: &Config,
+    code_block_attr: fn(&str, &Config) -> CodeBlockAttribute,
+) -> Vec<FormattedCodeBlock> {
+    let mut code_blocks = Vec::new();
+    let lines: Vec<_> = text.split('\n').collect();
+    let mut next_line = 0;
+    while let Some(line) = lines.get(next_line) {
+        next_line += 1;
+        let trimmed = line.trim_start();
+        let Some(fence_char) = trimmed.chars().next().filter(|c| matches!(c, '`' | '~')) else {
+            continue;
+        };
+        let attributes = trimmed.trim_start_matches(fence_char);
+        let fence_len = trimmed.len() - attributes.len();
+        // An info string after backticks cannot contain backticks, e.g. "```code```" is inline.
+        if fence_len < 3 || (fence_char == '`' && attributes.contains('`')) {
+            continue;
+        }
+        let indent = &line[..line.len() - trimmed.len()];
+        let code_start = next_line;
+        let mut has_closing_fence = false;
+        while let Some(line) = lines.get(next_line) {
+            next_line += 1;
+            let rest = line.trim_start().trim_start_matches(fence_char);
+            if line.trim_start().len() - rest.len() >= fence_len && rest.trim().is_empty() {
+                has_closing_fence = true;
+                break;
+            }
+        }
+        let code_end = if has_closing_fence {
+            next_line - 1
+        } else {
+            next_line
+        };
+        let code_lines = &lines[code_start..code_end];
+
+        let is_indented = code_lines
+            .iter()
+            .all(|line| line.trim().is_empty() || line.starts_with(indent));
+        let formatted = match code_block_attr(attributes, config) {
+            CodeBlockAttribute::Rust(edition) if has_closing_fence && is_indented => {
+                let mut code_block = String::with_capacity(text.len());
+                for line in code_lines {
+                    let line = line.get(indent.len()..).unwrap_or_default();
+                    code_block.push_str(&hide_sharp_behind_comment(line));
+                    code_block.push('\n');
+                }
+                if code_block.trim().is_empty() {
+                    None
+                } else {
+                    format_doc_code_block(&code_block, edition, config)
+                        .map(|s| trim_custom_comment_prefix(&s))
+                }
+            }
+            _ => None,
+        };
+
+        if let Some(formatted) = formatted {
+            let formatted: Vec<_> = formatted
+                .lines()
+                .map(|line| {
+                    if line.is_empty() {
+                        String::new()
+                    } else {
+                        format!("{indent}{line}")
+                    }
+                })
+                .collect();
+            if formatted != code_lines {
+                code_blocks.push(FormattedCodeBlock {
+                    lines: code_start..code_end,
+                    formatted,
+                });
+            }
+        }
+    }
+    code_blocks
+}
+
 const RUSTFMT_CUSTOM_COMMENT_PREFIX: &str = "//#### ";
 
 fn hide_sharp_behind_comment(s: &str) -> Cow<'_, str> {
@@ -1722,6 +2116,9 @@ pub(crate) fn recover_comment_removed(
     let snippet = context.snippet(span);
     if snippet != new && changed_comment_content(snippet, &new) {
         // We missed some comments. Warn and keep the original text.
+        context
+            .report
+            .add_unformatted_region(context.psess, span, UnformattedReason::LostComment);
         if context.config.error_on_unformatted() {
             context.report.append(
                 context.psess.span_to_filename(span),
@@ -1850,6 +2247,7 @@ fn remove_comment_header(comment: &str) -> &str {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::config::CodeBlockTags;
 
     #[test]
     fn char_classes() {
@@ -1907,6 +2305,40 @@ mod test {
         assert_eq!(None, iter.next());
     }
 
+    #[test]
+    fn code_block_attribute_tags() {
+        fn is_rust(attributes: &str, config: &Config) -> bool {
+            matches!(
+                CodeBlockAttribute::new(attributes, config),
+                CodeBlockAttribute::Rust(_)
+            )
+        }
+
+        let mut config = Config::default();
+        assert!(is_rust("", &config));
+        assert!(is_rust("rust,no_run", &config));
+        assert!(is_rust("should_panic edition2024", &config));
+        assert!(!is_rust("rust,no_compile", &config));
+        assert!(!is_rust("ignore-wasm32", &config));
+        assert!(!is_rust("text", &config));
+        assert!(matches!(
+            CodeBlockAttribute::new("rust,edition2018", &config),
+            CodeBlockAttribute::Rust(Some(Edition::Edition2018))
+        ));
+
+        config.set().doc_code_block_rust_tags(CodeBlockTags(vec![
+            "no_compile".to_owned(),
+            "title=*".to_owned(),
+        ]));
+        assert!(is_rust("rust,no_compile", &config));
+        assert!(is_rust("rust title=example.rs", &config));
+
+        config
+            .set()
+            .doc_code_block_skip_tags(CodeBlockTags(vec!["no_run".to_owned()]));
+        assert!(!is_rust("rust,no_run", &config));
+    }
+
     #[test]
     #[rustfmt::skip]
     fn format_doc_comments() {
@@ -2146,4 +2578,39 @@ fn main() {
             );
         }
     }
+
+    #[test]
+    fn normalize_doc_comment_conventions() {
+        assert_eq!(
+            normalize_doc_comment(
+                "/**\n * Foo, see [`crate::Foo`](crate::Foo).\n *\n *\n * Bar\n */"
+            )
+            .as_deref(),
+            Some("/// Foo, see [`crate::Foo`].\n///\n/// Bar")
+        );
+        assert_eq!(
+            normalize_doc_comment("//!Foo\n    //!   bar\n    //!").as_deref(),
+            Some("//! Foo\n//!   bar")
+        );
+        assert_eq!(
+            normalize_doc_comment("/// ```\n///\n///\n/// [a](a)\n/// ```").as_deref(),
+            Some("/// ```\n///\n///\n/// [a](a)\n/// ```")
+        );
+        assert_eq!(normalize_doc_comment("/// Foo\n// Bar"), None);
+        assert_eq!(normalize_doc_comment("/// Foo\n\n/// Bar"), None);
+        assert_eq!(normalize_doc_comment("/// Foo\n//! Bar"), None);
+        assert_eq!(normalize_doc_comment("///\n///"), None);
+    }
+
+    #[test]
+    fn shorten_intra_doc_links_only_with_matching_text() {
+        assert_eq!(
+            shorten_intra_doc_links("[`Foo`](Foo), [Bar](Bar), [`Baz`](crate::Baz), `[a](a)`"),
+            "[`Foo`], [Bar], [`Baz`](crate::Baz), `[a](a)`"
+        );
+        assert_eq!(
+            shorten_intra_doc_links("[docs](https://docs.rs), [foo](foo.html)"),
+            "[docs](https://docs.rs), [foo](foo.html)"
+        );
+    }
 }
@@ -0,0 +1,833 @@
+//! Reflowing of doc comments as CommonMark, see `doc_comment_markdown`.
+//!
+//! Only the block structure of Markdown is parsed: paragraphs are reflowed, list markers and
+//! headings are normalized, and everything else (code blocks, tables, HTML, link reference
+//! definitions, ...) is kept as it is. Inline code spans and links are never broken.
+
+use crate::utils::unicode_str_width;
+
+/// Formats the code of a fenced code block, given the info string of the fence and the code.
+pub(super) type FormatCode<'a> = dyn FnMut(&str, &str) -> Option<String> + 'a;
+
+/// Reflows the Markdown in `lines` (the doc comment with its comment markers removed) to `width`.
+pub(super) fn reflow_markdown(
+    lines: &[&str],
+    width: usize,
+    format_code: &mut FormatCode<'_>,
+) -> Vec<String> {
+    let lines: Vec<String> = lines
+        .iter()
+        .map(|line| expand_indent_tabs(line.trim_end_matches('\r')))
+        .collect();
+    let trailing_blank = lines.len() > 1 && lines.last().is_some_and(|line| is_blank(line));
+
+    let mut result = vec![];
+    render_blocks(&parse_blocks(&lines), width, format_code, &mut result);
+    if trailing_blank {
+        result.push(String::new());
+    }
+    result
+}
+
+#[derive(Debug)]
+enum Block {
+    Paragraph(Vec<String>),
+    Heading(usize, String),
+    /// A fenced code block, with its opening fence, content and closing fence, if any.
+    Fenced(String, Vec<String>, Option<String>),
+    /// Lines that are kept as they are, e.g. indented code, tables or HTML.
+    Verbatim(Vec<String>),
+    Quote(Vec<Spaced>),
+    List(List),
+}
+
+/// A block, and whether it is preceded by a blank line.
+#[derive(Debug)]
+struct Spaced {
+    blank_before: bool,
+    block: Block,
+}
+
+#[derive(Debug)]
+struct List {
+    /// The number of the first item of an ordered list.
+    start: Option<u64>,
+    items: Vec<Item>,
+}
+
+#[derive(Debug)]
+struct Item {
+    blank_before: bool,
+    blocks: Vec<Spaced>,
+}
+
+fn is_blank(line: &str) -> bool {
+    line.trim().is_empty()
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+/// Removes up to `n` spaces from the start of `line`.
+fn dedent(line: &str, n: usize) -> String {
+    line[indent_of(line).min(n)..].to_owned()
+}
+
+fn expand_indent_tabs(line: &str) -> String {
+    let trimmed = line.trim_start_matches([' ', '\t']);
+    let mut indent = 0;
+    for c in line[..line.len() - trimmed.len()].chars() {
+        indent = if c == '\t' {
+            indent / 4 * 4 + 4
+        } else {
+            indent + 1
+        };
+    }
+    format!("{}{trimmed}", " ".repeat(indent))
+}
+
+/// Returns the fence character and length if `trimmed` opens a fenced code block.
+fn fence_of(trimmed: &str) -> Option<(char, usize)> {
+    let c = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = trimmed.len() - trimmed.trim_start_matches(c).len();
+    let info = &trimmed[len..];
+    (len >= 3 && !(c == '`' && info.contains('`'))).then_some((c, len))
+}
+
+fn is_closing_fence(line: &str, fence: (char, usize)) -> bool {
+    let trimmed = line.trim();
+    indent_of(line) < 4 && trimmed.len() >= fence.1 && trimmed.chars().all(|c| c == fence.0)
+}
+
+fn atx_heading(trimmed: &str) -> Option<(usize, String)> {
+    let level = trimmed.len() - trimmed.trim_start_matches('#').len();
+    let text = &trimmed[level..];
+    if !(1..=6).contains(&level) || !(text.is_empty() || text.starts_with([' ', '\t'])) {
+        return None;
+    }
+    let text = text.trim();
+    // Remove the optional closing sequence.
+    let without_closing = text.trim_end_matches('#');
+    let text = if without_closing.is_empty() || without_closing.ends_with([' ', '\t']) {
+        without_closing.trim_end()
+    } else {
+        text
+    };
+    Some((level, text.to_owned()))
+}
+
+fn is_thematic_break(trimmed: &str) -> bool {
+    let Some(c) = trimmed
+        .chars()
+        .next()
+        .filter(|c| matches!(c, '*' | '-' | '_'))
+    else {
+        return false;
+    };
+    trimmed.chars().all(|x| x == c || x == ' ' || x == '\t')
+        && trimmed.chars().filter(|x| *x == c).count() >= 3
+}
+
+/// Returns the level of the setext heading that `trimmed` underlines, if any.
+fn setext_underline(trimmed: &str) -> Option<usize> {
+    let c = trimmed.chars().next()?;
+    let level = match c {
+        '=' => 1,
+        '-' => 2,
+        _ => return None,
+    };
+    trimmed.trim_end().chars().all(|x| x == c).then_some(level)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Marker {
+    Bullet(char),
+    Ordered(u64, char),
+}
+
+/// Returns the list marker at the start of `trimmed`, and the width of the marker.
+fn list_marker(trimmed: &str) -> Option<(Marker, usize)> {
+    let (marker, len) = if let Some(c) = trimmed.chars().next().filter(|c| "-+*".contains(*c)) {
+        (Marker::Bullet(c), 1)
+    } else {
+        let digits = trimmed.len()
+            - trimmed
+                .trim_start_matches(|c: char| c.is_ascii_digit())
+                .len();
+        let delim = trimmed[digits..]
+            .chars()
+            .next()
+            .filter(|c| *c == '.' || *c == ')')?;
+        if !(1..=9).contains(&digits) {
+            return None;
+        }
+        (
+            Marker::Ordered(trimmed[..digits].parse().ok()?, delim),
+            digits + 1,
+        )
+    };
+    let rest = &trimmed[len..];
+    (rest.is_empty() || rest.starts_with([' ', '\t'])).then_some((marker, len))
+}
+
+fn same_list(a: Marker, b: Marker) -> bool {
+    match (a, b) {
+        (Marker::Bullet(a), Marker::Bullet(b)) => a == b,
+        (Marker::Ordered(_, a), Marker::Ordered(_, b)) => a == b,
+        _ => false,
+    }
+}
+
+fn is_html_start(trimmed: &str) -> bool {
+    let mut chars = trimmed.chars();
+    chars.next() == Some('<')
+        && chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || matches!(c, '/' | '!' | '?'))
+}
+
+fn is_table_delimiter_row(trimmed: &str) -> bool {
+    trimmed.contains('-')
+        && trimmed.contains(['|', ':'])
+        && trimmed
+            .chars()
+            .all(|c| matches!(c, '|' | '-' | ':' | ' ' | '\t'))
+}
+
+fn is_link_reference_definition(trimmed: &str) -> bool {
+    trimmed.starts_with('[') && !trimmed.starts_with("[^") && trimmed.contains("]:")
+}
+
+/// Whether `line` interrupts a paragraph.
+fn interrupts_paragraph(line: &str) -> bool {
+    if indent_of(line) >= 4 {
+        return false;
+    }
+    let trimmed = line.trim_start();
+    fence_of(trimmed).is_some()
+        || atx_heading(trimmed).is_some()
+        || is_thematic_break(trimmed)
+        || trimmed.starts_with('>')
+        || is_html_start(trimmed)
+        || trimmed.starts_with('|')
+        || list_marker(trimmed).is_some_and(|(marker, len)| {
+            !is_blank(&trimmed[len..])
+                && matches!(marker, Marker::Bullet(_) | Marker::Ordered(1, _))
+        })
+}
+
+fn parse_blocks(lines: &[String]) -> Vec<Spaced> {
+    let mut blocks = vec![];
+    let mut blank_before = false;
+    let mut i = 0;
+    while i < lines.len() {
+        if is_blank(&lines[i]) {
+            blank_before = true;
+            i += 1;
+            continue;
+        }
+        let (block, next) = parse_block(lines, i);
+        blocks.push(Spaced {
+            blank_before,
+            block,
+        });
+        blank_before = false;
+        i = next;
+    }
+    blocks
+}
+
+/// Parses the block starting at the non-blank line `lines[i]`, and returns it along with the index
+/// of the line following it.
+fn parse_block(lines: &[String], i: usize) -> (Block, usize) {
+    let line = &lines[i];
+    let indent = indent_of(line);
+    let trimmed = line.trim_start();
+
+    if indent >= 4 {
+        let mut end = i + 1;
+        let mut last_code = i;
+        while end < lines.len() && (is_blank(&lines[end]) || indent_of(&lines[end]) >= 4) {
+            if !is_blank(&lines[end]) {
+                last_code = end;
+            }
+            end += 1;
+        }
+        return (
+            Block::Verbatim(lines[i..=last_code].to_vec()),
+            last_code + 1,
+        );
+    }
+
+    if let Some(fence) = fence_of(trimmed) {
+        let mut end = i + 1;
+        while end < lines.len() {
+            if is_closing_fence(&lines[end], fence) {
+                let content = lines[i + 1..end]
+                    .iter()
+                    .map(|l| dedent(l, indent))
+                    .collect();
+                let closing = lines[end].trim().to_owned();
+                return (
+                    Block::Fenced(trimmed.trim_end().to_owned(), content, Some(closing)),
+                    end + 1,
+                );
+            }
+            end += 1;
+        }
+        let content = lines[i + 1..].iter().map(|l| dedent(l, indent)).collect();
+        return (
+            Block::Fenced(trimmed.trim_end().to_owned(), content, None),
+            lines.len(),
+        );
+    }
+
+    if let Some((level, text)) = atx_heading(trimmed) {
+        return (Block::Heading(level, text), i + 1);
+    }
+
+    if is_thematic_break(trimmed) {
+        return (Block::Verbatim(vec![trimmed.trim_end().to_owned()]), i + 1);
+    }
+
+    if trimmed.starts_with('>') {
+        return parse_quote(lines, i);
+    }
+
+    if let Some((marker, _)) = list_marker(trimmed) {
+        return parse_list(lines, i, marker);
+    }
+
+    let starts_table = trimmed.starts_with('|')
+        || lines
+            .get(i + 1)
+            .is_some_and(|next| trimmed.contains('|') && is_table_delimiter_row(next.trim()));
+    if is_html_start(trimmed) || starts_table {
+        let end = (i..lines.len())
+            .find(|&end| is_blank(&lines[end]))
+            .unwrap_or(lines.len());
+        return (Block::Verbatim(lines[i..end].to_vec()), end);
+    }
+
+    if is_link_reference_definition(trimmed) {
+        return (Block::Verbatim(vec![trimmed.trim_end().to_owned()]), i + 1);
+    }
+
+    let mut paragraph = vec![line.clone()];
+    let mut end = i + 1;
+    while end < lines.len() && !is_blank(&lines[end]) {
+        let next = &lines[end];
+        if indent_of(next) < 4 {
+            if let Some(level) = setext_underline(next.trim_start()) {
+                let text = paragraph.iter().map(|l| l.trim()).collect::<Vec<_>>();
+                return (Block::Heading(level, text.join(" ")), end + 1);
+            }
+        }
+        if interrupts_paragraph(next) {
+            break;
+        }
+        paragraph.push(next.clone());
+        end += 1;
+    }
+    (Block::Paragraph(paragraph), end)
+}
+
+fn parse_quote(lines: &[String], i: usize) -> (Block, usize) {
+    let mut inner = vec![];
+    let mut end = i;
+    while end < lines.len() && !is_blank(&lines[end]) {
+        let line = &lines[end];
+        let trimmed = line.trim_start();
+        if indent_of(line) < 4 && trimmed.starts_with('>') {
+            let content = &trimmed[1..];
+            inner.push(content.strip_prefix(' ').unwrap_or(content).to_owned());
+        } else if end > i
+            && !interrupts_paragraph(line)
+            && !inner.last().is_some_and(|l| is_blank(l))
+        {
+            // A lazy continuation line.
+            inner.push(line.clone());
+        } else {
+            break;
+        }
+        end += 1;
+    }
+    (Block::Quote(parse_blocks(&inner)), end)
+}
+
+fn parse_list(lines: &[String], i: usize, marker: Marker) -> (Block, usize) {
+    let start = match marker {
+        Marker::Ordered(start, _) => Some(start),
+        Marker::Bullet(_) => None,
+    };
+    let mut items = vec![];
+    let mut blank_before = false;
+    let mut i = i;
+    loop {
+        let (blocks, next) = parse_list_item(lines, i);
+        items.push(Item {
+            blank_before,
+            blocks,
+        });
+
+        let mut after_blank = next;
+        while after_blank < lines.len() && is_blank(&lines[after_blank]) {
+            after_blank += 1;
+        }
+        let Some(line) = lines.get(after_blank) else {
+            return (Block::List(List { start, items }), next);
+        };
+        let next_marker = (indent_of(line) < 4)
+            .then(|| list_marker(line.trim_start()))
+            .flatten();
+        if !next_marker.is_some_and(|(next_marker, _)| same_list(marker, next_marker))
+            || is_thematic_break(line.trim_start())
+        {
+            return (Block::List(List { start, items }), next);
+        }
+        blank_before = after_blank > next;
+        i = after_blank;
+    }
+}
+
+/// Parses the blocks of the list item starting at `lines[i]`, and returns them along with the index
+/// of the line following the item.
+fn parse_list_item(lines: &[String], i: usize) -> (Vec<Spaced>, usize) {
+    let line = &lines[i];
+    let indent = indent_of(line);
+    let trimmed = line.trim_start();
+    let (_, marker_len) = list_marker(trimmed).expect("a list item");
+    let after_marker = &trimmed[marker_len..];
+    let spaces = after_marker.len() - after_marker.trim_start().len();
+    let content_offset = if after_marker.trim().is_empty() || spaces > 4 {
+        indent + marker_len + 1
+    } else {
+        indent + marker_len + spaces
+    };
+
+    let mut content = vec![if after_marker.trim().is_empty() {
+        String::new()
+    } else if spaces > 4 {
+        after_marker[1..].to_owned()
+    } else {
+        after_marker.trim_start().to_owned()
+    }];
+    let mut fence = content
+        .first()
+        .and_then(|first| fence_of(first.trim_start()));
+    let mut end = i + 1;
+    while end < lines.len() {
+        let next = &lines[end];
+        if is_blank(next) {
+            content.push(String::new());
+        } else if indent_of(next) >= content_offset {
+            let next = dedent(next, content_offset);
+            match fence {
+                Some(open) if is_closing_fence(&next, open) => fence = None,
+                Some(_) => {}
+                None => fence = fence_of(next.trim_start()),
+            }
+            content.push(next);
+        } else if fence.is_none()
+            && content.last().is_some_and(|last| !is_blank(last))
+            && !interrupts_paragraph(next)
+            && list_marker(next.trim_start()).is_none()
+            && setext_underline(next.trim_start()).is_none()
+        {
+            // A lazy continuation line.
+            content.push(next.trim_start().to_owned());
+        } else {
+            break;
+        }
+        end += 1;
+    }
+    while content.len() > 1 && content.last().is_some_and(|last| is_blank(last)) {
+        content.pop();
+        end -= 1;
+    }
+    (parse_blocks(&content), end)
+}
+
+fn render_blocks(
+    blocks: &[Spaced],
+    width: usize,
+    format_code: &mut FormatCode<'_>,
+    result: &mut Vec<String>,
+) {
+    let mut previous_list: Option<(bool, usize)> = None;
+    for Spaced {
+        blank_before,
+        block,
+    } in blocks
+    {
+        if *blank_before {
+            result.push(String::new());
+        }
+        let list = match block {
+            Block::Paragraph(lines) => {
+                render_paragraph(lines, width, result);
+                None
+            }
+            Block::Heading(level, text) if text.is_empty() => {
+                result.push("#".repeat(*level));
+                None
+            }
+            Block::Heading(level, text) => {
+                result.push(format!("{} {text}", "#".repeat(*level)));
+                None
+            }
+            Block::Fenced(opening, content, closing) => {
+                result.push(opening.clone());
+                let info = opening.trim_start_matches(['`', '~']).trim();
+                let code = content.join("\n");
+                match closing.as_ref().and_then(|_| format_code(info, &code)) {
+                    Some(formatted) => result.extend(formatted.lines().map(str::to_owned)),
+                    None => result.extend(content.iter().cloned()),
+                }
+                result.extend(closing.iter().cloned());
+                None
+            }
+            Block::Verbatim(lines) => {
+                result.extend(lines.iter().cloned());
+                None
+            }
+            Block::Quote(blocks) => {
+                let mut inner = vec![];
+                render_blocks(blocks, width.saturating_sub(2), format_code, &mut inner);
+                result.extend(inner.into_iter().map(|line| {
+                    if line.is_empty() {
+                        ">".to_owned()
+                    } else {
+                        format!("> {line}")
+                    }
+                }));
+                None
+            }
+            Block::List(list) => {
+                // Adjacent lists need different markers to stay separate lists.
+                let ordered = list.start.is_some();
+                let style = match previous_list {
+                    Some((previous_ordered, style)) if previous_ordered == ordered => 1 - style,
+                    _ => 0,
+                };
+                render_list(list, style, width, format_code, result);
+                Some((ordered, style))
+            }
+        };
+        previous_list = list;
+    }
+}
+
+fn render_list(
+    list: &List,
+    style: usize,
+    width: usize,
+    format_code: &mut FormatCode<'_>,
+    result: &mut Vec<String>,
+) {
+    for (index, item) in list.items.iter().enumerate() {
+        if item.blank_before {
+            result.push(String::new());
+        }
+        let marker = match list.start {
+            Some(start) => format!("{}{}", start + index as u64, [".", ")"][style]),
+            None => ["-", "*"][style].to_owned(),
+        };
+        let content_indent = marker.len() + 1;
+        let mut inner = vec![];
+        render_blocks(
+            &item.blocks,
+            width.saturating_sub(content_indent),
+            format_code,
+            &mut inner,
+        );
+        if inner.is_empty() {
+            result.push(marker);
+            continue;
+        }
+        for (i, line) in inner.into_iter().enumerate() {
+            result.push(if i == 0 {
+                if line.is_empty() {
+                    marker.clone()
+                } else {
+                    format!("{marker} {line}")
+                }
+            } else if line.is_empty() {
+                line
+            } else {
+                format!("{}{line}", " ".repeat(content_indent))
+            });
+        }
+    }
+}
+
+/// Reflows a paragraph, keeping its hard line breaks.
+fn render_paragraph(lines: &[String], width: usize, result: &mut Vec<String>) {
+    let mut text = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        let is_last = i + 1 == lines.len();
+        let trimmed = line.trim_start();
+        let hard_break = if is_last {
+            None
+        } else if trimmed.ends_with("  ") {
+            Some("  ")
+        } else if trimmed.ends_with('\\') && !trimmed.ends_with("\\\\") {
+            Some("\\")
+        } else {
+            None
+        };
+        if !text.is_empty() {
+            text.push(' ');
+        }
+        text.push_str(trimmed.trim_end());
+        if let Some(hard_break) = hard_break {
+            if hard_break == "\\" {
+                text.pop();
+            }
+            let mut wrapped = fill(&atoms(text.trim_end()), width);
+            if let Some(last) = wrapped.last_mut() {
+                last.push_str(hard_break);
+            }
+            result.extend(wrapped);
+            text.clear();
+        }
+    }
+    if !text.is_empty() {
+        result.extend(fill(&atoms(&text), width));
+    }
+}
+
+/// Splits `text` at whitespace, except within inline code spans and links.
+fn atoms(text: &str) -> Vec<String> {
+    let mut atoms = vec![];
+    let mut atom = String::new();
+    let mut i = 0;
+    while let Some(c) = text[i..].chars().next() {
+        if c.is_whitespace() {
+            if !atom.is_empty() {
+                atoms.push(std::mem::take(&mut atom));
+            }
+            i += c.len_utf8();
+            continue;
+        }
+        let end = match c {
+            '`' => code_span_end(text, i),
+            '[' => link_end(text, i),
+            '\\' => text[i + 1..].chars().next().map(|c| i + 1 + c.len_utf8()),
+            _ => None,
+        }
+        .unwrap_or(i + c.len_utf8());
+        atom.push_str(&collapse_whitespace(&text[i..end]));
+        i = end;
+    }
+    if !atom.is_empty() {
+        atoms.push(atom);
+    }
+    atoms
+}
+
+fn collapse_whitespace(s: &str) -> String {
+    if !s.contains(char::is_whitespace) {
+        return s.to_owned();
+    }
+    let mut result = String::with_capacity(s.len());
+    let mut previous_whitespace = false;
+    for c in s.chars() {
+        if c.is_whitespace() {
+            if !previous_whitespace {
+                result.push(' ');
+            }
+            previous_whitespace = true;
+        } else {
+            result.push(c);
+            previous_whitespace = false;
+        }
+    }
+    result
+}
+
+/// Returns the end of the code span starting at `start`, if it is closed.
+fn code_span_end(text: &str, start: usize) -> Option<usize> {
+    let ticks = text[start..].len() - text[start..].trim_start_matches('`').len();
+    let mut i = start + ticks;
+    while let Some(offset) = text[i..].find('`') {
+        let run_start = i + offset;
+        let run = text[run_start..].len() - text[run_start..].trim_start_matches('`').len();
+        if run == ticks {
+            return Some(run_start + run);
+        }
+        i = run_start + run;
+    }
+    None
+}
+
+/// Returns the end of the link (or link text) starting at the `[` at `start`, if it is closed.
+fn link_end(text: &str, start: usize) -> Option<usize> {
+    let text_end = matching_bracket(text, start, '[', ']')?;
+    match text[text_end..].chars().next() {
+        Some('(') => matching_bracket(text, text_end, '(', ')').or(Some(text_end)),
+        Some('[') => matching_bracket(text, text_end, '[', ']').or(Some(text_end)),
+        _ => Some(text_end),
+    }
+}
+
+/// Returns the offset after the `close` matching the `open` at `start`.
+fn matching_bracket(text: &str, start: usize, open: char, close: char) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = start;
+    while let Some(c) = text[i..].chars().next() {
+        match c {
+            '\\' => {
+                i += 1;
+                if let Some(escaped) = text[i..].chars().next() {
+                    i += escaped.len_utf8();
+                }
+                continue;
+            }
+            '`' => {
+                if let Some(end) = code_span_end(text, i) {
+                    i = end;
+                    continue;
+                }
+            }
+            _ if c == open => depth += 1,
+            _ if c == close => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + c.len_utf8());
+                }
+            }
+            _ => {}
+        }
+        i += c.len_utf8();
+    }
+    None
+}
+
+/// Whether `atom` would start a block other than a paragraph at the start of a line.
+fn starts_block(atom: &str) -> bool {
+    matches!(atom, "-" | "+" | "*")
+        || (atom.starts_with('#') && atom.chars().all(|c| c == '#'))
+        || atom.starts_with(['>', '|', '<'])
+        || atom.starts_with("```")
+        || atom.starts_with("~~~")
+        || is_thematic_break(atom)
+        || setext_underline(atom).is_some()
+        || list_marker(atom).is_some()
+}
+
+/// Fills lines of at most `width` columns with `atoms`, where possible.
+fn fill(atoms: &[String], width: usize) -> Vec<String> {
+    let mut lines = vec![];
+    let mut line = String::new();
+    for atom in atoms {
+        if line.is_empty() {
+            line.push_str(atom);
+        } else if unicode_str_width(&line) + 1 + unicode_str_width(atom) <= width
+            || starts_block(atom)
+        {
+            line.push(' ');
+            line.push_str(atom);
+        } else {
+            lines.push(std::mem::take(&mut line));
+            line.push_str(atom);
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn reflow(text: &str, width: usize) -> String {
+        let lines: Vec<_> = text.lines().collect();
+        reflow_markdown(&lines, width, &mut |_, _| None).join("\n")
+    }
+
+    #[test]
+    fn reflows_paragraphs() {
+        assert_eq!(
+            reflow(
+                "Lorem ipsum dolor\nsit amet, consectetur adipiscing elit.",
+                20
+            ),
+            "Lorem ipsum dolor\nsit amet,\nconsectetur\nadipiscing elit."
+        );
+        assert_eq!(reflow("Lorem\nipsum\n\ndolor", 20), "Lorem ipsum\n\ndolor");
+    }
+
+    #[test]
+    fn keeps_code_spans_and_links_together() {
+        assert_eq!(
+            reflow(
+                "See `foo bar baz` and [the docs](https://example.com/docs).",
+                10
+            ),
+            "See\n`foo bar baz`\nand\n[the docs](https://example.com/docs)."
+        );
+    }
+
+    #[test]
+    fn keeps_hard_line_breaks() {
+        assert_eq!(
+            reflow("one\\\ntwo  \nthree\nfour", 80),
+            "one\\\ntwo  \nthree four"
+        );
+    }
+
+    #[test]
+    fn does_not_start_lines_with_block_markers() {
+        assert_eq!(reflow("aaaa - bbbb", 4), "aaaa -\nbbbb");
+        assert_eq!(reflow("aaaa 1. bbbb", 4), "aaaa 1.\nbbbb");
+    }
+
+    #[test]
+    fn normalizes_headings() {
+        assert_eq!(reflow("##   Title ##", 80), "## Title");
+        assert_eq!(
+            reflow("Title\n=====\n\nSub\ntitle\n---", 80),
+            "# Title\n\n## Sub title"
+        );
+    }
+
+    #[test]
+    fn normalizes_list_markers() {
+        assert_eq!(
+            reflow(
+                "* one\n* two\n  continued\n\n  second paragraph\n+ three",
+                80
+            ),
+            "- one\n- two continued\n\n  second paragraph\n* three"
+        );
+        assert_eq!(reflow("3) three\n3) four", 80), "3. three\n4. four");
+    }
+
+    #[test]
+    fn reflows_nested_lists_and_quotes() {
+        assert_eq!(
+            reflow("- one two three\n  * four five six", 10),
+            "- one two\n  three\n  - four\n    five\n    six"
+        );
+        assert_eq!(
+            reflow("> one two three\nfour", 10),
+            "> one two\n> three\n> four"
+        );
+    }
+
+    #[test]
+    fn keeps_code_blocks() {
+        let text = "- item\n\n  ```\n  let x   =  1;\n  ```\n\n      indented   code";
+        assert_eq!(reflow(text, 80), text);
+    }
+
+    #[test]
+    fn keeps_tables_and_link_definitions() {
+        let text = "| a | b |\n|---|---|\n| c | d |\n\n[foo bar]: https://example.com";
+        assert_eq!(reflow(text, 5), text);
+    }
+}
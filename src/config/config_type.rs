@@ -1,6 +1,8 @@
 use crate::config::file_lines::FileLines;
-use crate::config::macro_names::MacroSelectors;
-use crate::config::options::{IgnoreList, WidthHeuristics};
+use crate::config::macro_names::{MacroSelectors, MacroShapes};
+use crate::config::options::{
+    CodeBlockTags, DeriveOrder, GeneratedMarkers, IgnoreList, WidthHeuristics,
+};
 
 /// Trait for types that can be used in `Config`.
 pub(crate) trait ConfigType: Sized {
@@ -53,6 +55,30 @@ impl ConfigType for MacroSelectors {
     }
 }
 
+impl ConfigType for MacroShapes {
+    fn doc_hint() -> String {
+        String::from("{<string> = <shape>, ...}")
+    }
+}
+
+impl ConfigType for CodeBlockTags {
+    fn doc_hint() -> String {
+        String::from("[<string>, ...]")
+    }
+}
+
+impl ConfigType for GeneratedMarkers {
+    fn doc_hint() -> String {
+        String::from("[<string>, ...]")
+    }
+}
+
+impl ConfigType for DeriveOrder {
+    fn doc_hint() -> String {
+        String::from("[Preserve|Alphabetical|[<string>, ...]]")
+    }
+}
+
 impl ConfigType for WidthHeuristics {
     fn doc_hint() -> String {
         String::new()
@@ -522,6 +548,7 @@ macro_rules! create_config {
 
             fn set_ignore(&mut self, dir: &Path) {
                 self.ignore.2.add_prefix(dir);
+                self.generated_paths.2.add_prefix(dir);
             }
 
             fn set_merge_imports(&mut self) {
@@ -1,9 +1,10 @@
 //! This module contains types and functions to support formatting specific macros.
 
 use itertools::Itertools;
+use std::collections::BTreeMap;
 use std::{fmt, str};
 
-use serde::{Deserialize, Deserializer, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json as json;
 use thiserror::Error;
 
@@ -98,6 +99,140 @@ impl str::FromStr for MacroSelectors {
     }
 }
 
+/// How the invocations of a macro are formatted.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MacroShape {
+    /// Like a function call, e.g. `foo!(a, b)`. Parentheses are used.
+    Call,
+    /// Like an array literal, e.g. `vec![a, b]`. Brackets are used.
+    Array,
+    /// Like `format!`, with the given number of arguments before the format string. The
+    /// arguments after the format string may share lines when the call doesn't fit on one.
+    Format(usize),
+    /// As a block of items.
+    Items,
+    /// As a block of statements, for invocations using braces.
+    Statements,
+    /// Left as written.
+    Verbatim,
+}
+
+impl fmt::Display for MacroShape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Call => write!(f, "Call"),
+            Self::Array => write!(f, "Array"),
+            Self::Format(0) => write!(f, "Format"),
+            Self::Format(n) => write!(f, "Format({n})"),
+            Self::Items => write!(f, "Items"),
+            Self::Statements => write!(f, "Statements"),
+            Self::Verbatim => write!(f, "Verbatim"),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+#[error(
+    "invalid macro shape `{0}`, expected `Call`, `Array`, `Format`, `Format(<number>)`, \
+     `Items`, `Statements` or `Verbatim`"
+)]
+pub struct MacroShapeError(String);
+
+impl str::FromStr for MacroShape {
+    type Err = MacroShapeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || MacroShapeError(s.to_owned());
+        Ok(match s.trim() {
+            "Call" => Self::Call,
+            "Array" => Self::Array,
+            "Format" => Self::Format(0),
+            "Items" => Self::Items,
+            "Statements" => Self::Statements,
+            "Verbatim" => Self::Verbatim,
+            shape => {
+                let n = shape
+                    .strip_prefix("Format(")
+                    .and_then(|n| n.strip_suffix(')'))
+                    .ok_or_else(invalid)?;
+                Self::Format(n.trim().parse().map_err(|_| invalid())?)
+            }
+        })
+    }
+}
+
+impl Serialize for MacroShape {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for MacroShape {
+    fn deserialize<D>(de: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(de)?;
+        std::str::FromStr::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+/// The shapes of the invocations of the selected macros.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+pub struct MacroShapes(pub BTreeMap<MacroSelector, MacroShape>);
+
+impl MacroShapes {
+    /// Returns the shape of the invocations of the macro `name`, preferring a selector naming
+    /// the macro to `*`.
+    pub fn get(&self, name: &str) -> Option<MacroShape> {
+        self.0
+            .iter()
+            .find(|(selector, _)| matches!(selector, MacroSelector::Name(n) if n.0 == name))
+            .or_else(|| self.0.get_key_value(&MacroSelector::All))
+            .map(|(_, shape)| *shape)
+    }
+}
+
+impl fmt::Display for MacroShapes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{{{}}}",
+            self.0
+                .iter()
+                .format_with(", ", |(selector, shape), f| f(&format_args!(
+                    "{selector} = {shape}"
+                )))
+        )
+    }
+}
+
+// Selectors are written as strings, so that they can be used as TOML keys.
+impl Serialize for MacroShapes {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_map(
+            self.0
+                .iter()
+                .map(|(selector, shape)| (selector.to_string(), shape)),
+        )
+    }
+}
+
+// This impl is needed for `Config::override_value` to work for use in tests.
+impl str::FromStr for MacroShapes {
+    type Err = MacroSelectorsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        json::from_str(s).map_err(MacroSelectorsError::Json)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -125,4 +260,28 @@ mod test {
         let macro_names = MacroSelectors::from_str(r#"["foo", "*", "bar"]"#).unwrap();
         assert_eq!(format!("{macro_names}"), "foo, *, bar");
     }
+
+    #[test]
+    fn macro_shapes_from_str() {
+        let macro_shapes = MacroShapes::from_str(
+            r#"{"ensure": "Format(1)", "bail": "Format", "my_vec": "Array", "*": "Call"}"#,
+        )
+        .unwrap();
+        assert_eq!(macro_shapes.get("ensure"), Some(MacroShape::Format(1)));
+        assert_eq!(macro_shapes.get("bail"), Some(MacroShape::Format(0)));
+        assert_eq!(macro_shapes.get("my_vec"), Some(MacroShape::Array));
+        assert_eq!(macro_shapes.get("other"), Some(MacroShape::Call));
+        assert!(MacroShapes::from_str(r#"{"ensure": "Format(x)"}"#).is_err());
+        assert!(MacroShapes::from_str(r#"{"ensure": "Block"}"#).is_err());
+    }
+
+    #[test]
+    fn macro_shapes_display() {
+        let macro_shapes =
+            MacroShapes::from_str(r#"{"sql_params": "Call", "html": "Verbatim"}"#).unwrap();
+        assert_eq!(
+            format!("{macro_shapes}"),
+            "{html = Verbatim, sql_params = Call}"
+        );
+    }
 }
@@ -10,7 +10,7 @@ use crate::config::config_type::ConfigType;
 #[allow(unreachable_pub)]
 pub use crate::config::file_lines::{FileLines, FileName, Range};
 #[allow(unreachable_pub)]
-pub use crate::config::macro_names::MacroSelector;
+pub use crate::config::macro_names::{MacroSelector, MacroShape};
 #[allow(unreachable_pub)]
 pub use crate::config::options::*;
 
@@ -74,13 +74,27 @@ create_config! {
         possible";
     normalize_doc_attributes: NormalizeDocAttributes, false, "Normalize doc attributes as doc \
         comments";
+    normalize_doc_comments: NormalizeDocComments, false, "Convert /** */ doc comments to ///, \
+        normalize the space after the comment marker and blank lines, and shorten redundant \
+        intra-doc links";
+    doc_comment_markdown: DocCommentMarkdown, false, "Reflow doc comments as Markdown, wrapping \
+        paragraphs to comment_width and normalizing list markers and headings";
+    doc_code_block_rust_tags: DocCodeBlockRustTags, false, "Additional code block tags that \
+        mark a code block in a doc comment as Rust code to format";
+    doc_code_block_skip_tags: DocCodeBlockSkipTags, false, "Code block tags that prevent a code \
+        block in a doc comment from being formatted";
     format_strings: FormatStrings, false, "Format string literals where necessary";
+    string_split_style: StringSplitStyleConfig, false,
+        "How to split string literals which are too long when formatting strings";
     format_macro_matchers: FormatMacroMatchers, false,
         "Format the metavariable matching patterns in macros";
     format_macro_bodies: FormatMacroBodies, false,
         "Format the bodies of declarative macro definitions";
     skip_macro_invocations: SkipMacroInvocations, false,
         "Skip formatting the bodies of macros invoked with the following names.";
+    macro_shapes: MacroShapesConfig, false,
+        "Format the invocations of the selected macros like function calls, arrays, `format!`, \
+        blocks of items or statements, or leave them as they are";
     hex_literal_case: HexLiteralCaseConfig, true, "Format hexadecimal integer literals";
     float_literal_trailing_zero: FloatLiteralTrailingZeroConfig, false,
         "Add or remove trailing zero in floating-point literals";
@@ -164,12 +178,20 @@ create_config! {
     format_generated_files: FormatGeneratedFiles, false, "Format generated files";
     generated_marker_line_search_limit: GeneratedMarkerLineSearchLimit, false, "Number of lines to \
         check for a `@generated` marker when `format_generated_files` is enabled";
+    generated_markers: GeneratedMarkersConfig, false, "Regular expressions which mark a file as \
+        generated when they match one of its first lines";
+    generated_paths: GeneratedPaths, false, "Treat the specified files and directories as \
+        generated";
 
     // Options that can change the source code beyond whitespace/blocks (somewhat linty things)
     merge_derives: MergeDerives, true, "Merge multiple `#[derive(...)]` into a single one";
+    derive_order: DeriveOrderConfig, false, "Order of the traits of `#[derive(...)]` \
+        attributes, whose duplicates are removed unless the order is preserved";
     use_try_shorthand: UseTryShorthand, true, "Replace uses of the try! macro by the ? shorthand";
     use_field_init_shorthand: UseFieldInitShorthand, true, "Use field initialization shorthand if \
         possible";
+    inline_format_args: InlineFormatArgs, false, "Inline the trailing identifier arguments of \
+        format-like macros into their format strings";
     force_explicit_abi: ForceExplicitAbi, true, "Always print the abi for extern items";
     condense_wildcard_suffixes: CondenseWildcardSuffixes, false, "Replace strings of _ wildcards \
         by a single .. in tuple patterns";
@@ -204,6 +226,13 @@ create_config! {
     print_misformatted_file_names: PrintMisformattedFileNames, true,
         "Prints the names of mismatched files that were formatted. Prints the names of \
          files that would be formatted when used with `--check` mode. ";
+    verify_idempotent: VerifyIdempotent, false,
+        "Format each file a second time and report any changes made by the second pass";
+    verify_tokens: VerifyTokens, false,
+        "Compare the tokens and comments of each formatted file with the original, and leave \
+         the file unchanged if they differ";
+    markdown: Markdown, false,
+        "Format the Rust code blocks of Markdown files instead of Rust source files";
 }
 
 #[derive(Error, Debug)]
@@ -218,6 +247,9 @@ impl PartialConfig {
         cloned.verbose = None;
         cloned.width_heuristics = None;
         cloned.print_misformatted_file_names = None;
+        cloned.verify_idempotent = None;
+        cloned.verify_tokens = None;
+        cloned.markdown = None;
         cloned.merge_imports = None;
         cloned.fn_args_layout = None;
         cloned.hide_parse_errors = None;
@@ -779,7 +811,12 @@ doc_comment_code_block_small_heuristics = "Inherit"
 comment_width = 80
 normalize_comments = false
 normalize_doc_attributes = false
+normalize_doc_comments = false
+doc_comment_markdown = false
+doc_code_block_rust_tags = []
+doc_code_block_skip_tags = []
 format_strings = false
+string_split_style = "Backslash"
 format_macro_matchers = false
 format_macro_bodies = true
 skip_macro_invocations = []
@@ -825,9 +862,13 @@ version = "One"
 inline_attribute_width = 0
 format_generated_files = true
 generated_marker_line_search_limit = 5
+generated_markers = ["@generated"]
+generated_paths = []
 merge_derives = true
+derive_order = "Preserve"
 use_try_shorthand = false
 use_field_init_shorthand = false
+inline_format_args = false
 force_explicit_abi = true
 condense_wildcard_suffixes = false
 color = "Auto"
@@ -841,6 +882,8 @@ error_on_unformatted = false
 ignore = []
 emit_mode = "Files"
 make_backup = false
+
+[macro_shapes]
 "#,
             env!("CARGO_PKG_VERSION")
         );
@@ -872,7 +915,12 @@ doc_comment_code_block_small_heuristics = "Inherit"
 comment_width = 80
 normalize_comments = false
 normalize_doc_attributes = false
+normalize_doc_comments = false
+doc_comment_markdown = false
+doc_code_block_rust_tags = []
+doc_code_block_skip_tags = []
 format_strings = false
+string_split_style = "Backslash"
 format_macro_matchers = false
 format_macro_bodies = true
 skip_macro_invocations = []
@@ -918,9 +966,13 @@ version = "Two"
 inline_attribute_width = 0
 format_generated_files = true
 generated_marker_line_search_limit = 5
+generated_markers = ["@generated"]
+generated_paths = []
 merge_derives = true
+derive_order = "Preserve"
 use_try_shorthand = false
 use_field_init_shorthand = false
+inline_format_args = false
 force_explicit_abi = true
 condense_wildcard_suffixes = false
 color = "Auto"
@@ -934,6 +986,8 @@ error_on_unformatted = false
 ignore = []
 emit_mode = "Files"
 make_backup = false
+
+[macro_shapes]
 "#,
             env!("CARGO_PKG_VERSION")
         );
@@ -14,7 +14,7 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use crate::config::Config;
 use crate::config::file_lines::FileLines;
 use crate::config::lists::*;
-use crate::config::macro_names::MacroSelectors;
+use crate::config::macro_names::{MacroSelectors, MacroShapes};
 
 #[config_type]
 pub enum NewlineStyle {
@@ -192,6 +192,18 @@ pub enum FloatLiteralTrailingZero {
     Never,
 }
 
+/// How to split string literals that are too long with `format_strings`.
+#[config_type]
+pub enum StringSplitStyle {
+    /// Continue the literal on the next line after a `\`.
+    Backslash,
+    /// Split the literal into the arguments of a `concat!` invocation, one per line.
+    Concat,
+    /// Like `Concat`, except that the format strings of format-like macros are split with `\`,
+    /// so that they remain a single literal which can capture variables.
+    Mixed,
+}
+
 #[config_type]
 pub enum ReportTactic {
     Always,
@@ -221,6 +233,8 @@ pub enum EmitMode {
     /// This option is designed to be run in CI where a non-zero exit signifies
     /// non-standard code formatting. Used for `--check`.
     Diff,
+    /// Lists the regions of each file which were left unformatted, and why.
+    UnformattedReport,
 }
 
 /// Client-preference for coloured output.
@@ -457,6 +471,154 @@ impl FromStr for IgnoreList {
     }
 }
 
+/// A list of code block tags, such as `no_run` or `ignore-*`, used to decide which code blocks in
+/// doc comments are formatted. A trailing `*` matches every tag starting with the given prefix.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct CodeBlockTags(pub Vec<String>);
+
+impl CodeBlockTags {
+    /// Returns `true` if `tag` matches one of the tags of the list.
+    pub fn matches(&self, tag: &str) -> bool {
+        self.0
+            .iter()
+            .any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => tag.starts_with(prefix),
+                None => tag == pattern,
+            })
+    }
+}
+
+impl fmt::Display for CodeBlockTags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}]", self.0.iter().format(", "))
+    }
+}
+
+// This impl is needed for `Config::override_value` to work for use in tests.
+impl FromStr for CodeBlockTags {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s).map(Self)
+    }
+}
+
+/// A list of regular expressions matched against the first lines of a file to decide whether it
+/// is generated.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct GeneratedMarkers(pub Vec<String>);
+
+impl Default for GeneratedMarkers {
+    fn default() -> Self {
+        GeneratedMarkers(vec![String::from("@generated")])
+    }
+}
+
+impl fmt::Display for GeneratedMarkers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}]", self.0.iter().format(", "))
+    }
+}
+
+// This impl is needed for `Config::override_value` to work for use in tests.
+impl FromStr for GeneratedMarkers {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s).map(Self)
+    }
+}
+
+/// The order of the traits of `#[derive(...)]` attributes.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum DeriveOrder {
+    /// Keep the traits in the order they are written.
+    #[default]
+    Preserve,
+    /// Sort the traits alphabetically by their names, ignoring their paths.
+    Alphabetical,
+    /// Put the traits of the list first, in the order of the list, followed by the other traits
+    /// in the order they are written. A trait matches a name of the list if its path is the name
+    /// or ends with `::` and the name.
+    Custom(Vec<String>),
+}
+
+impl fmt::Display for DeriveOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeriveOrder::Preserve => write!(f, "Preserve"),
+            DeriveOrder::Alphabetical => write!(f, "Alphabetical"),
+            DeriveOrder::Custom(names) => write!(f, "[{}]", names.iter().format(", ")),
+        }
+    }
+}
+
+impl Serialize for DeriveOrder {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            DeriveOrder::Custom(names) => names.serialize(serializer),
+            _ => serializer.collect_str(self),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for DeriveOrder {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct DeriveOrderVisitor;
+        impl<'v> Visitor<'v> for DeriveOrderVisitor {
+            type Value = DeriveOrder;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("`Preserve`, `Alphabetical` or a sequence of trait names")
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                match value {
+                    _ if value.eq_ignore_ascii_case("Preserve") => Ok(DeriveOrder::Preserve),
+                    _ if value.eq_ignore_ascii_case("Alphabetical") => {
+                        Ok(DeriveOrder::Alphabetical)
+                    }
+                    _ => Err(E::invalid_value(serde::de::Unexpected::Str(value), &self)),
+                }
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: SeqAccess<'v>,
+            {
+                let mut names = vec![];
+                while let Some(name) = seq.next_element()? {
+                    names.push(name);
+                }
+                Ok(DeriveOrder::Custom(names))
+            }
+        }
+        deserializer.deserialize_any(DeriveOrderVisitor)
+    }
+}
+
+// This impl is needed for `Config::override_value` to work for use in tests.
+impl FromStr for DeriveOrder {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim_start().starts_with('[') {
+            serde_json::from_str(s)
+        } else {
+            serde_json::from_value(serde_json::Value::String(s.trim().to_owned()))
+        }
+    }
+}
+
 /// Maps client-supplied options to Rustfmt's internals, mostly overriding
 /// values in a config with values from the command line.
 pub trait CliOptions {
@@ -655,10 +817,16 @@ config_option_with_style_edition_default!(
     CommentWidth, usize, _ => 80;
     NormalizeComments, bool, _ => false;
     NormalizeDocAttributes, bool, _ => false;
+    NormalizeDocComments, bool, _ => false;
+    DocCommentMarkdown, bool, _ => false;
+    DocCodeBlockRustTags, CodeBlockTags, _ => CodeBlockTags::default();
+    DocCodeBlockSkipTags, CodeBlockTags, _ => CodeBlockTags::default();
     FormatStrings, bool, _ => false;
+    StringSplitStyleConfig, StringSplitStyle, _ => StringSplitStyle::Backslash;
     FormatMacroMatchers, bool, _ => false;
     FormatMacroBodies, bool, _ => true;
     SkipMacroInvocations, MacroSelectors, _ => MacroSelectors::default();
+    MacroShapesConfig, MacroShapes, _ => MacroShapes::default();
     HexLiteralCaseConfig, HexLiteralCase, _ => HexLiteralCase::Preserve;
     FloatLiteralTrailingZeroConfig, FloatLiteralTrailingZero, _ =>
         FloatLiteralTrailingZero::Preserve;
@@ -715,11 +883,15 @@ config_option_with_style_edition_default!(
     InlineAttributeWidth, usize, _ => 0;
     FormatGeneratedFiles, bool, _ => true;
     GeneratedMarkerLineSearchLimit, usize, _ => 5;
+    GeneratedMarkersConfig, GeneratedMarkers, _ => GeneratedMarkers::default();
+    GeneratedPaths, IgnoreList, _ => IgnoreList::default();
 
     // Options that can change the source code beyond whitespace/blocks (somewhat linty things)
     MergeDerives, bool, _ => true;
+    DeriveOrderConfig, DeriveOrder, _ => DeriveOrder::Preserve;
     UseTryShorthand, bool, _ => false;
     UseFieldInitShorthand, bool, _ => false;
+    InlineFormatArgs, bool, _ => false;
     ForceExplicitAbi, bool, _ => true;
     CondenseWildcardSuffixes, bool, _ => false;
 
@@ -741,6 +913,9 @@ config_option_with_style_edition_default!(
     EmitModeConfig, EmitMode, _ => EmitMode::Files;
     MakeBackup, bool, _ => false;
     PrintMisformattedFileNames, bool, _ => false;
+    VerifyIdempotent, bool, _ => false;
+    VerifyTokens, bool, _ => false;
+    Markdown, bool, _ => false;
 );
 
 #[test]
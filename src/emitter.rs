@@ -5,7 +5,9 @@ pub(crate) use self::files_with_backup::*;
 pub(crate) use self::json::*;
 pub(crate) use self::modified_lines::*;
 pub(crate) use self::stdout::*;
+pub(crate) use self::unformatted_report::*;
 use crate::FileName;
+use crate::formatting::UnformattedRegion;
 use std::io::{self, Write};
 use std::path::Path;
 
@@ -16,6 +18,7 @@ mod files_with_backup;
 mod json;
 mod modified_lines;
 mod stdout;
+mod unformatted_report;
 
 pub(crate) struct FormattedFile<'a> {
     pub(crate) filename: &'a FileName,
@@ -35,6 +38,18 @@ pub(crate) trait Emitter {
         formatted_file: FormattedFile<'_>,
     ) -> Result<EmitterResult, io::Error>;
 
+    /// Called with the regions of a file which were left as they are in the source, after the
+    /// file itself has been emitted. A file which was skipped entirely has a single region
+    /// covering all of it.
+    fn emit_unformatted_regions(
+        &mut self,
+        _output: &mut dyn Write,
+        _filename: &FileName,
+        _regions: &[UnformattedRegion],
+    ) -> Result<(), io::Error> {
+        Ok(())
+    }
+
     fn emit_header(&self, _output: &mut dyn Write) -> Result<(), io::Error> {
         Ok(())
     }
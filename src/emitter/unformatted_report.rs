@@ -0,0 +1,98 @@
+use super::*;
+
+/// Lists the regions of each file which were left as they are in the source, and why. The
+/// files themselves are not written.
+#[derive(Debug, Default)]
+pub(crate) struct UnformattedReportEmitter;
+
+impl Emitter for UnformattedReportEmitter {
+    fn emit_formatted_file(
+        &mut self,
+        _output: &mut dyn Write,
+        _formatted_file: FormattedFile<'_>,
+    ) -> Result<EmitterResult, io::Error> {
+        Ok(EmitterResult::default())
+    }
+
+    fn emit_unformatted_regions(
+        &mut self,
+        output: &mut dyn Write,
+        filename: &FileName,
+        regions: &[UnformattedRegion],
+    ) -> Result<(), io::Error> {
+        for region in regions {
+            match region.lines {
+                Some((lo, hi)) if lo == hi => {
+                    writeln!(output, "{filename}:{lo}: {}", region.reason)?
+                }
+                Some((lo, hi)) => writeln!(output, "{filename}:{lo}-{hi}: {}", region.reason)?,
+                None => writeln!(output, "{filename}: {}", region.reason)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formatting::UnformattedReason;
+    use crate::rewrite::MacroErrorKind;
+    use std::path::PathBuf;
+
+    #[test]
+    fn does_not_emit_formatted_files() {
+        let mut writer = Vec::new();
+        let mut emitter = UnformattedReportEmitter::default();
+        let result = emitter
+            .emit_formatted_file(
+                &mut writer,
+                FormattedFile {
+                    filename: &FileName::Real(PathBuf::from("src/lib.rs")),
+                    original_text: "fn empty() {}\n",
+                    formatted_text: "fn empty() {}\n\n",
+                },
+            )
+            .unwrap();
+        assert!(!result.has_diff);
+        assert_eq!(writer, Vec::<u8>::new());
+    }
+
+    #[test]
+    fn emits_one_line_per_region() {
+        let mut writer = Vec::new();
+        let mut emitter = UnformattedReportEmitter::default();
+        emitter
+            .emit_unformatted_regions(
+                &mut writer,
+                &FileName::Real(PathBuf::from("src/lib.rs")),
+                &[
+                    UnformattedRegion {
+                        lines: Some((3, 5)),
+                        reason: UnformattedReason::SkipAttribute,
+                    },
+                    UnformattedRegion {
+                        lines: Some((9, 9)),
+                        reason: UnformattedReason::MacroFailure(MacroErrorKind::ParseFailure),
+                    },
+                ],
+            )
+            .unwrap();
+        emitter
+            .emit_unformatted_regions(
+                &mut writer,
+                &FileName::Real(PathBuf::from("src/generated.rs")),
+                &[UnformattedRegion {
+                    lines: None,
+                    reason: UnformattedReason::Generated,
+                }],
+            )
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(writer).unwrap(),
+            "src/lib.rs:3-5: skipped by `#[rustfmt::skip]`\n\
+             src/lib.rs:9: failed to format macro (parse failure)\n\
+             src/generated.rs: detected as generated\n"
+        );
+    }
+}
@@ -14,7 +14,7 @@ use crate::comment::{
     recover_comment_removed, rewrite_comment, rewrite_missing_comment,
 };
 use crate::config::{Config, ControlBraceStyle, HexLiteralCase, IndentStyle, StyleEdition};
-use crate::config::{FloatLiteralTrailingZero, lists::*};
+use crate::config::{FloatLiteralTrailingZero, StringSplitStyle, lists::*};
 use crate::lists::{
     ListFormatting, Separator, definitive_tactic, itemize_list, shape_for_tactic,
     struct_lit_formatting, struct_lit_shape, struct_lit_tactic, write_list,
@@ -29,7 +29,9 @@ use crate::shape::{Indent, Shape};
 use crate::source_map::{LineRangeUtils, SpanUtils};
 use crate::spanned::Spanned;
 use crate::stmt;
-use crate::string::{StringFormat, rewrite_string};
+use crate::string::{
+    StringFormat, has_named_placeholders, rewrite_string, rewrite_string_with_concat,
+};
 use crate::types::{PathContext, rewrite_path};
 use crate::utils::{
     colon_spaces, contains_skip, count_newlines, filtered_str_fits, first_line_ends_with,
@@ -111,7 +113,10 @@ pub(crate) fn format_expr(
             None,
         ),
         ast::ExprKind::Lit(token_lit) => {
-            if let Ok(expr_rw) = rewrite_literal(context, token_lit, expr.span, shape) {
+            if let Some(expr_rw) = rewrite_str_lit_with_concat(context, token_lit, expr.span, shape)
+            {
+                Ok(expr_rw)
+            } else if let Ok(expr_rw) = rewrite_literal(context, token_lit, expr.span, shape) {
                 Ok(expr_rw)
             } else {
                 if let LitKind::StrRaw(_) = token_lit.kind {
@@ -1318,7 +1323,7 @@ pub(crate) fn rewrite_literal(
     shape: Shape,
 ) -> RewriteResult {
     match token_lit.kind {
-        token::LitKind::Str => rewrite_string_lit(context, span, shape),
+        token::LitKind::Str => rewrite_string_lit(context, context.snippet(span), span, shape),
         token::LitKind::Integer => rewrite_int_lit(context, token_lit, span, shape),
         token::LitKind::Float => rewrite_float_lit(context, token_lit, span, shape),
         _ => wrap_str(
@@ -1330,9 +1335,69 @@ pub(crate) fn rewrite_literal(
     }
 }
 
-fn rewrite_string_lit(context: &RewriteContext<'_>, span: Span, shape: Shape) -> RewriteResult {
-    let string_lit = context.snippet(span);
+/// Rewrites a string literal, normal or raw, in expression position, splitting it into a
+/// `concat!` of pieces of it if `string_split_style` allows it. Returns `None` if the literal
+/// isn't split this way.
+///
+/// Literals in macro arguments are never split with `concat!`, since a macro may expect a
+/// literal, e.g. with a `$x:literal` matcher, or use it as a format string which captures
+/// variables. The format strings of known format-like macros are rewritten with
+/// `rewrite_format_str` instead.
+fn rewrite_str_lit_with_concat(
+    context: &RewriteContext<'_>,
+    token_lit: token::Lit,
+    span: Span,
+    shape: Shape,
+) -> Option<String> {
+    if !context.config.format_strings()
+        || context.config.string_split_style() == StringSplitStyle::Backslash
+        || context.inside_macro()
+        || context.is_macro_def
+        || token_lit.suffix.is_some()
+        || !matches!(
+            token_lit.kind,
+            token::LitKind::Str | token::LitKind::StrRaw(_)
+        )
+    {
+        return None;
+    }
+    rewrite_string_with_concat(context.snippet(span), shape, context.config)
+}
+
+/// Rewrites `string_lit`, the source of the format string of a format-like macro, normal or raw.
+///
+/// Format strings with named placeholders are never split with `concat!`, since `format_args!`
+/// can't capture variables in a format string which comes from a macro.
+pub(crate) fn rewrite_format_str(
+    context: &RewriteContext<'_>,
+    string_lit: &str,
+    span: Span,
+    shape: Shape,
+) -> RewriteResult {
+    if context.config.format_strings()
+        && context.config.string_split_style() == StringSplitStyle::Concat
+        && !has_named_placeholders(string_lit)
+    {
+        if let Some(rewrite) = rewrite_string_with_concat(string_lit, shape, context.config) {
+            return Ok(rewrite);
+        }
+    }
+    if string_lit.starts_with('r') {
+        wrap_str(string_lit.to_owned(), context.config.max_width(), shape)
+            .max_width_error(shape.width, span)
+    } else {
+        rewrite_string_lit(context, string_lit, span, shape)
+    }
+}
 
+/// Rewrites `string_lit`, the source of a string literal which isn't raw, breaking it with `\`
+/// line continuations if `format_strings` is set.
+pub(crate) fn rewrite_string_lit(
+    context: &RewriteContext<'_>,
+    string_lit: &str,
+    span: Span,
+    shape: Shape,
+) -> RewriteResult {
     if !context.config.format_strings() {
         if string_lit
             .lines()
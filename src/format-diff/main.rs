@@ -18,7 +18,7 @@ use std::process;
 
 use regex::Regex;
 
-use clap::{CommandFactory, Parser};
+use clap::{CommandFactory, Parser, ValueEnum};
 
 /// The default pattern of files to format.
 ///
@@ -59,6 +59,21 @@ pub struct Opts {
         default_value = DEFAULT_PATTERN
     )]
     filter: String,
+
+    /// Report whether the changed lines are formatted, without modifying files
+    #[arg(long = "check", conflicts_with = "emit")]
+    check: bool,
+
+    /// Report the changed lines which are not formatted in the given format, without
+    /// modifying files
+    #[arg(long = "emit", value_name = "MODE")]
+    emit: Option<EmitMode>,
+}
+
+/// The output formats supported by `--emit`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum EmitMode {
+    Json,
 }
 
 fn main() {
@@ -66,12 +81,15 @@ fn main() {
         .with_env_filter(EnvFilter::from_env("RUSTFMT_LOG"))
         .init();
     let opts = Opts::parse();
-    if let Err(e) = run(opts) {
-        println!("{e}");
-        Opts::command()
-            .print_help()
-            .expect("cannot write to stdout");
-        process::exit(1);
+    match run(opts) {
+        Ok(exit_code) => process::exit(exit_code),
+        Err(e) => {
+            println!("{e}");
+            Opts::command()
+                .print_help()
+                .expect("cannot write to stdout");
+            process::exit(1);
+        }
     }
 }
 
@@ -81,15 +99,23 @@ struct Range {
     range: [u32; 2],
 }
 
-fn run(opts: Opts) -> Result<(), FormatDiffError> {
+// Returned i32 is an exit code
+fn run(opts: Opts) -> Result<i32, FormatDiffError> {
     let (files, ranges) = scan_diff(io::stdin(), opts.skip_prefix, &opts.filter)?;
-    run_rustfmt(&files, &ranges)
+    run_rustfmt(&files, &ranges, &opts)
 }
 
-fn run_rustfmt(files: &HashSet<String>, ranges: &[Range]) -> Result<(), FormatDiffError> {
+fn run_rustfmt(
+    files: &HashSet<String>,
+    ranges: &[Range],
+    opts: &Opts,
+) -> Result<i32, FormatDiffError> {
     if files.is_empty() || ranges.is_empty() {
         debug!("No files to format found");
-        return Ok(());
+        if opts.emit == Some(EmitMode::Json) {
+            println!("[]");
+        }
+        return Ok(0);
     }
 
     let ranges_as_json = json::to_string(ranges).unwrap();
@@ -102,19 +128,187 @@ fn run_rustfmt(files: &HashSet<String>, ranges: &[Range]) -> Result<(), FormatDi
         Some(rustfmt) => rustfmt,
         None => OsStr::new("rustfmt"),
     };
-    let exit_status = process::Command::new(rustfmt)
+    let mut command = process::Command::new(rustfmt);
+    command
         .args(files)
+        .arg("--unstable-features")
         .arg("--file-lines")
-        .arg(ranges_as_json)
-        .status()?;
+        .arg(ranges_as_json);
+    if opts.check {
+        command.arg("--check");
+    } else if let Some(EmitMode::Json) = opts.emit {
+        command.args(["--emit", "json"]);
+    }
+    let exit_status = command.status()?;
 
+    // In check mode, a failure means that some of the changed lines are not formatted.
+    if opts.check {
+        return Ok(exit_status.code().unwrap_or(1));
+    }
     if !exit_status.success() {
         return Err(FormatDiffError::IoError(io::Error::new(
             io::ErrorKind::Other,
             format!("rustfmt failed with {exit_status}"),
         )));
     }
-    Ok(())
+    Ok(0)
+}
+
+/// Unquotes a path which git quoted because it contains unusual characters, e.g.
+/// `"b/caf\303\251.rs"`.
+fn unquote_c_style(quoted: &str) -> Option<String> {
+    let inner = quoted.strip_prefix('"')?.strip_suffix('"')?;
+    let mut bytes = Vec::with_capacity(inner.len());
+    let mut iter = inner.bytes();
+    while let Some(b) = iter.next() {
+        if b != b'\\' {
+            bytes.push(b);
+            continue;
+        }
+        let unescaped = match iter.next()? {
+            b'a' => 0x07,
+            b'b' => 0x08,
+            b'f' => 0x0c,
+            b'n' => b'\n',
+            b'r' => b'\r',
+            b't' => b'\t',
+            b'v' => 0x0b,
+            digit @ b'0'..=b'3' => {
+                let mut value = digit - b'0';
+                for _ in 0..2 {
+                    match iter.next()? {
+                        digit @ b'0'..=b'7' => value = value * 8 + (digit - b'0'),
+                        _ => return None,
+                    }
+                }
+                value
+            }
+            other => other,
+        };
+        bytes.push(unescaped);
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// Returns the path of a `+++ ` diff header with the smallest prefix containing `skip_prefix`
+/// slashes removed, or `None` if the file does not exist after the change.
+fn parse_header_path(path: &str, skip_prefix: u32) -> Option<String> {
+    // `diff -u` appends a tab and a timestamp, and git appends a tab to paths containing
+    // spaces.
+    let path = path.split('\t').next().unwrap_or(path);
+    let path = if path.starts_with('"') {
+        unquote_c_style(path)?
+    } else {
+        path.to_owned()
+    };
+    if path == "/dev/null" {
+        return None;
+    }
+
+    let mut path = path.as_str();
+    for _ in 0..skip_prefix {
+        path = &path[path.find('/')? + 1..];
+    }
+    Some(path.to_owned())
+}
+
+/// A hunk of a unified or combined diff.
+#[derive(Debug, PartialEq)]
+struct Hunk {
+    /// The number of lines of each original file which are not yet scanned.
+    old_remaining: Vec<u32>,
+    /// The number of lines of the new file which are not yet scanned.
+    new_remaining: u32,
+    /// The first line of the hunk in the new file.
+    new_start: u32,
+    /// The number of lines of the hunk in the new file.
+    new_count: u32,
+}
+
+impl Hunk {
+    /// Returns the range of lines of the hunk in the new file, or `None` if the hunk only
+    /// removes lines.
+    fn new_range(&self) -> Option<[u32; 2]> {
+        if self.new_count == 0 {
+            return None;
+        }
+        Some([self.new_start, self.new_start + self.new_count - 1])
+    }
+
+    /// Consumes `line` if it is part of the hunk, and returns whether it was.
+    fn consume(&mut self, line: &str) -> bool {
+        // `\ No newline at end of file`
+        if line.starts_with('\\') {
+            return true;
+        }
+        if self.new_remaining == 0 && self.old_remaining.iter().all(|&n| n == 0) {
+            return false;
+        }
+
+        // Combined diffs have one column of markers per original file. A line marked as
+        // removed is only in the original files it is removed from. Any other line is in the new
+        // file, and in every original file it is not marked as added to.
+        let markers: Vec<u8> = line
+            .bytes()
+            .chain(std::iter::repeat(b' '))
+            .take(self.old_remaining.len())
+            .collect();
+        let is_removed = markers.contains(&b'-');
+        for (marker, remaining) in markers.iter().zip(self.old_remaining.iter_mut()) {
+            let in_old_file = if is_removed {
+                *marker == b'-'
+            } else {
+                *marker != b'+'
+            };
+            if in_old_file {
+                *remaining = remaining.saturating_sub(1);
+            }
+        }
+        if !is_removed {
+            self.new_remaining = self.new_remaining.saturating_sub(1);
+        }
+        true
+    }
+}
+
+/// Parses a line and count, e.g. `12,3` or `12`, from a hunk header.
+fn parse_hunk_range(range: &str) -> Option<(u32, u32)> {
+    match range.split_once(',') {
+        Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
+
+/// Parses a hunk header such as `@@ -1,2 +3,4 @@`, or `@@@ -1,2 -1,2 +3,4 @@@` in combined
+/// diffs.
+fn parse_hunk_header(line: &str) -> Option<Hunk> {
+    let marker_len = line.bytes().take_while(|&b| b == b'@').count();
+    if marker_len < 2 {
+        return None;
+    }
+    let marker = &line[..marker_len];
+    let ranges = line[marker_len..].split(marker).next()?;
+
+    let mut old_remaining = vec![];
+    let mut new_range = None;
+    for range in ranges.split_whitespace() {
+        if let Some(range) = range.strip_prefix('-') {
+            old_remaining.push(parse_hunk_range(range)?.1);
+        } else if let Some(range) = range.strip_prefix('+') {
+            new_range = Some(parse_hunk_range(range)?);
+        }
+    }
+    let (new_start, new_count) = new_range?;
+    if old_remaining.len() != marker_len - 1 {
+        return None;
+    }
+
+    Some(Hunk {
+        old_remaining,
+        new_remaining: new_count,
+        new_start,
+        new_count,
+    })
 }
 
 /// Scans a diff from `from`, and returns the set of files found, and the ranges
@@ -127,61 +321,42 @@ fn scan_diff<R>(
 where
     R: io::Read,
 {
-    let diff_pattern = format!(r"^\+\+\+\s(?:.*?/){{{skip_prefix}}}(\S*)");
-    let diff_pattern = Regex::new(&diff_pattern).unwrap();
-
-    let lines_pattern = Regex::new(r"^@@.*\+(\d+)(,(\d+))?").unwrap();
-
     let file_filter = Regex::new(&format!("^{file_filter}$"))?;
 
-    let mut current_file = None;
+    let mut current_file: Option<String> = None;
+    let mut hunk: Option<Hunk> = None;
 
     let mut files = HashSet::new();
     let mut ranges = vec![];
     for line in io::BufReader::new(from).lines() {
-        let line = line.unwrap();
-
-        if let Some(captures) = diff_pattern.captures(&line) {
-            current_file = Some(captures.get(1).unwrap().as_str().to_owned());
-        }
-
-        let file = match current_file {
-            Some(ref f) => &**f,
-            None => continue,
-        };
-
-        // FIXME(emilio): We could avoid this most of the time if needed, but
-        // it's not clear it's worth it.
-        if !file_filter.is_match(file) {
-            continue;
+        let line = line?;
+
+        // Skip the content of hunks, so that e.g. added lines starting with `++` are not
+        // mistaken for headers.
+        if let Some(ref mut hunk) = hunk {
+            if hunk.consume(&line) {
+                continue;
+            }
         }
-
-        let lines_captures = match lines_pattern.captures(&line) {
-            Some(captures) => captures,
-            None => continue,
-        };
-
-        let start_line = lines_captures
-            .get(1)
-            .unwrap()
-            .as_str()
-            .parse::<u32>()
-            .unwrap();
-        let line_count = match lines_captures.get(3) {
-            Some(line_count) => line_count.as_str().parse::<u32>().unwrap(),
-            None => 1,
-        };
-
-        if line_count == 0 {
-            continue;
+        hunk = None;
+
+        if line.starts_with("diff ") {
+            // A new file starts. Renames, copies and mode changes aren't necessarily followed
+            // by any hunks, so don't attribute later hunks to the previous file.
+            current_file = None;
+        } else if let Some(path) = line.strip_prefix("+++ ") {
+            current_file =
+                parse_header_path(path, skip_prefix).filter(|file| file_filter.is_match(file));
+        } else if let Some(new_hunk) = parse_hunk_header(&line) {
+            if let (Some(file), Some(range)) = (&current_file, new_hunk.new_range()) {
+                files.insert(file.clone());
+                ranges.push(Range {
+                    file: file.clone(),
+                    range,
+                });
+            }
+            hunk = Some(new_hunk);
         }
-
-        let end_line = start_line + line_count - 1;
-        files.insert(file.to_owned());
-        ranges.push(Range {
-            file: file.to_owned(),
-            range: [start_line, end_line],
-        });
     }
 
     Ok((files, ranges))
@@ -225,6 +400,66 @@ fn scan_simple_git_diff() {
     );
 }
 
+#[test]
+fn scan_git_diff_edge_cases() {
+    const DIFF: &str = include_str!("test/edge_cases.diff");
+    let (files, ranges) = scan_diff(DIFF.as_bytes(), 1, r".*\.rs").expect("scan_diff failed?");
+
+    assert!(
+        !files.contains("src/removed.rs"),
+        "Deleted files have nothing to format"
+    );
+    assert!(
+        !files.contains("src/renamed.rs"),
+        "Pure renames have nothing to format"
+    );
+
+    assert_eq!(
+        &ranges,
+        &[
+            Range {
+                file: "src/moved.rs".to_owned(),
+                range: [3, 3],
+            },
+            Range {
+                file: "src/copied.rs".to_owned(),
+                range: [1, 2],
+            },
+            Range {
+                file: "src/with space.rs".to_owned(),
+                range: [2, 2],
+            },
+            Range {
+                file: "src/caf\u{e9}.rs".to_owned(),
+                range: [1, 1],
+            },
+            Range {
+                file: "src/plus.rs".to_owned(),
+                range: [1, 2],
+            },
+            Range {
+                file: "src/plus.rs".to_owned(),
+                range: [10, 12],
+            },
+            Range {
+                file: "src/merged.rs".to_owned(),
+                range: [3, 6],
+            },
+        ]
+    );
+}
+
+#[test]
+fn parse_hunk_headers() {
+    let new_range = |line| parse_hunk_header(line).and_then(|hunk| hunk.new_range());
+    assert_eq!(new_range("@@ -1,2 +3,4 @@ fn main() {"), Some([3, 6]));
+    assert_eq!(new_range("@@ -1 +1 @@"), Some([1, 1]));
+    assert_eq!(new_range("@@ -1,2 +0,0 @@"), None);
+    assert_eq!(new_range("@@@ -1,2 -1,3 +1,4 @@@"), Some([1, 4]));
+    assert_eq!(new_range("@@ -1,2 -1,3 +1,4 @@"), None);
+    assert!(parse_hunk_header("+@@ -1,2 +3,4 @@").is_none());
+}
+
 #[cfg(test)]
 mod cmd_line_tests {
     use super::*;
@@ -279,4 +514,17 @@ mod cmd_line_tests {
                 .is_err()
         );
     }
+
+    #[test]
+    fn check_and_emit_options() {
+        let o = Opts::parse_from(["test", "--check"]);
+        assert!(o.check);
+        let o = Opts::parse_from(["test", "--emit", "json"]);
+        assert_eq!(Some(EmitMode::Json), o.emit);
+        assert!(
+            Opts::command()
+                .try_get_matches_from(["test", "--check", "--emit", "json"])
+                .is_err()
+        );
+    }
 }
@@ -106,7 +106,11 @@ fn error_kind_to_snippet_annotation_level(error_kind: &ErrorKind) -> Level<'_> {
         | ErrorKind::LostComment
         | ErrorKind::BadAttr
         | ErrorKind::InvalidGlobPattern(_)
+        | ErrorKind::InvalidGeneratedPath(_)
+        | ErrorKind::InvalidGeneratedMarker(_)
+        | ErrorKind::NonIdempotent(_)
+        | ErrorKind::TokenMismatch { .. }
         | ErrorKind::VersionMismatch => Level::ERROR,
-        ErrorKind::DeprecatedAttr => Level::WARNING,
+        ErrorKind::DeprecatedAttr | ErrorKind::UnbalancedMarker => Level::WARNING,
     }
 }
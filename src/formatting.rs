@@ -1,27 +1,36 @@
 // High level formatting functions.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
 use std::io::{self, Write};
 use std::ops::Range;
+use std::path::Path;
 use std::time::{Duration, Instant};
 
 use rustc_ast::ast;
 use rustc_span::Span;
+use serde::Serialize;
 use tracing::debug;
 
 use self::newline_style::apply_newline_style;
-use crate::comment::{CharClasses, FullCodeCharKind};
-use crate::config::{Config, FileName, Verbosity};
-use crate::formatting::generated::is_generated_file;
+use crate::comment::{CharClasses, FullCodeCharKind, format_code_blocks_in_markdown};
+use crate::config::{Config, EmitMode, FileName, Verbosity};
+use crate::formatting::generated::GeneratedFiles;
+use crate::formatting::verify_tokens::verify_tokens;
 use crate::modules::Module;
 use crate::parse::parser::{DirectoryOwnership, Parser, ParserError};
 use crate::parse::session::ParseSess;
+use crate::rewrite::{MacroErrorKind, RewriteError};
+use crate::rustfmt_diff::{DiffLine, make_diff};
+use crate::skip::OffRegions;
 use crate::utils::{contains_skip, count_newlines};
 use crate::visitor::FmtVisitor;
 use crate::{ErrorKind, FormatReport, Input, Session, modules, source_file};
 
 mod generated;
 mod newline_style;
+mod verify_tokens;
 
 // A map of the files of a crate, with their new content
 pub(crate) type SourceFile = Vec<FileRecord>;
@@ -47,7 +56,11 @@ impl<'b, T: Write + 'b> Session<'b, T> {
             }
 
             let config = &self.config.clone();
-            let format_result = format_project(input, config, self, is_macro_def);
+            let format_result = if config.markdown() {
+                format_markdown(input, config, self)
+            } else {
+                format_project(input, config, self, is_macro_def)
+            };
 
             format_result.map(|report| {
                 self.errors.add(&report.internal.borrow().1);
@@ -57,7 +70,8 @@ impl<'b, T: Write + 'b> Session<'b, T> {
     }
 }
 
-/// Determine if a module should be skipped. True if the module should be skipped, false otherwise.
+/// Determine if a module should be skipped. Returns the reason the module should be skipped, or
+/// `None` if it should be formatted.
 fn should_skip_module<T: FormatHandler>(
     config: &Config,
     context: &FormatContext<'_, T>,
@@ -65,31 +79,55 @@ fn should_skip_module<T: FormatHandler>(
     main_file: &FileName,
     path: &FileName,
     module: &Module<'_>,
-) -> bool {
+    generated_files: Option<&GeneratedFiles>,
+) -> Option<SkipReason> {
     if contains_skip(module.attrs()) {
-        return true;
+        return Some(SkipReason::SkipAttribute);
     }
 
     if config.skip_children() && path != main_file {
-        return true;
+        return Some(SkipReason::SkipChildren);
     }
 
     if !input_is_stdin && context.ignore_file(path) {
-        return true;
+        return Some(SkipReason::Ignored);
     }
 
     // FIXME(calebcartwright) - we need to determine how we'll handle the
     // `format_generated_files` option with stdin based input.
-    if !input_is_stdin && !config.format_generated_files() {
+    if let Some(generated_files) = generated_files.filter(|_| !input_is_stdin) {
         let source_file = context.psess.span_to_file_contents(module.span);
         let src = source_file.src.as_ref().expect("SourceFile without src");
 
-        if is_generated_file(src, config) {
-            return true;
+        if let Some(generated) = generated_files.matches(path, src) {
+            should_emit_verbose(input_is_stdin, config, || {
+                println!("Skipping generated file {path}: {generated}")
+            });
+            return Some(SkipReason::Generated);
         }
     }
 
-    false
+    None
+}
+
+/// Returns whether the file at `path` is skipped if it holds `text`, see `Session::skips_file`.
+pub(crate) fn skips_file(config: &Config, path: &Path, text: &str) -> Result<bool, ErrorKind> {
+    rustc_span::create_session_if_not_set_then(config.edition().into(), |_| {
+        let mut psess = ParseSess::new(config)?;
+        let path = FileName::Real(path.to_path_buf());
+        if psess.ignore_file(&path) {
+            return Ok(true);
+        }
+        if !config.format_generated_files()
+            && GeneratedFiles::new(config)?.matches(&path, text).is_some()
+        {
+            return Ok(true);
+        }
+        // Parsing errors are reported when the text is formatted.
+        psess.set_silent_emitter();
+        Ok(Parser::parse_crate(Input::Text(text.to_owned()), &psess)
+            .is_ok_and(|krate| contains_skip(&krate.attrs)))
+    })
 }
 
 fn echo_back_stdin(input: &str) -> Result<FormatReport, ErrorKind> {
@@ -113,7 +151,9 @@ fn format_project<T: FormatHandler>(
 
     let psess = ParseSess::new(config)?;
     if config.skip_children() && psess.ignore_file(&main_file) {
-        return Ok(FormatReport::new());
+        let report = FormatReport::new();
+        report.add_skipped_file(SkipReason::Ignored);
+        return Ok(report);
     }
 
     // Parse the crate.
@@ -133,7 +173,14 @@ fn format_project<T: FormatHandler>(
         }
     };
 
+    let generated_files = if config.format_generated_files() {
+        None
+    } else {
+        Some(GeneratedFiles::new(config)?)
+    };
+
     let mut context = FormatContext::new(&krate, report, psess, config, handler);
+    let mut skipped_files = vec![];
     let files = modules::ModResolver::new(
         &context.psess,
         directory_ownership.unwrap_or(DirectoryOwnership::UnownedViaBlock),
@@ -142,11 +189,34 @@ fn format_project<T: FormatHandler>(
     .visit_crate(&krate)?
     .into_iter()
     .filter(|(path, module)| {
-        input_is_stdin
-            || !should_skip_module(config, &context, input_is_stdin, &main_file, path, module)
+        if input_is_stdin {
+            return true;
+        }
+        match should_skip_module(
+            config,
+            &context,
+            input_is_stdin,
+            &main_file,
+            path,
+            module,
+            generated_files.as_ref(),
+        ) {
+            Some(reason) => {
+                context.report.add_skipped_file(reason);
+                if let Some(reason) = reason.unformatted_reason() {
+                    skipped_files.push((path.clone(), reason));
+                }
+                false
+            }
+            None => true,
+        }
     })
     .collect::<Vec<_>>();
 
+    for (path, reason) in skipped_files {
+        context.handler.handle_skipped_file(path, reason)?;
+    }
+
     timer = timer.done_parsing();
 
     // Suppress error output if we have to do any further parsing.
@@ -172,6 +242,36 @@ fn format_project<T: FormatHandler>(
     Ok(context.report)
 }
 
+// Format the Rust code blocks of a Markdown file, leaving the rest of it untouched.
+fn format_markdown<T: FormatHandler>(
+    input: Input,
+    config: &Config,
+    handler: &mut T,
+) -> Result<FormatReport, ErrorKind> {
+    let path = input.file_name();
+    let input_is_stdin = path == FileName::Stdin;
+
+    let psess = ParseSess::new(config)?;
+    let mut report = FormatReport::new();
+    if !input_is_stdin && psess.ignore_file(&path) {
+        report.add_skipped_file(SkipReason::Ignored);
+        return Ok(report);
+    }
+
+    let text = match input {
+        Input::File(ref file) => fs::read_to_string(file)?,
+        Input::Text(text) => text,
+    };
+    let original = psess.add_source_file(&path, text);
+
+    should_emit_verbose(input_is_stdin, config, || println!("Formatting {}", path));
+    let mut result =
+        format_code_blocks_in_markdown(&original, config).unwrap_or_else(|| original.to_string());
+    apply_newline_style(config.newline_style(), &mut result, &original);
+    handler.handle_formatted_file(&psess, path, result, &mut report)?;
+    Ok(report)
+}
+
 // Used for formatting files.
 struct FormatContext<'a, T: FormatHandler> {
     krate: &'a ast::Crate,
@@ -217,6 +317,24 @@ impl<'a, T: FormatHandler + 'a> FormatContext<'a, T> {
             self.report.clone(),
         );
         visitor.skip_context.update_with_attrs(&self.krate.attrs);
+        let (off_regions, unbalanced_markers) = OffRegions::from_source(
+            snippet_provider.entire_snippet(),
+            snippet_provider.start_pos(),
+        );
+        for region in off_regions.iter() {
+            self.report
+                .add_unformatted_region(&self.psess, region, UnformattedReason::OffMarker);
+        }
+        visitor.skip_context.off_regions = off_regions;
+        if !unbalanced_markers.is_empty() {
+            let errors = unbalanced_markers
+                .into_iter()
+                .map(|span| {
+                    FormattingError::from_span(span, &self.psess, ErrorKind::UnbalancedMarker)
+                })
+                .collect();
+            self.report.append(path.clone(), errors);
+        }
         visitor.is_macro_def = is_macro_def;
         visitor.last_pos = snippet_provider.start_pos();
         visitor.skip_empty_lines(snippet_provider.end_pos());
@@ -260,6 +378,30 @@ impl<'a, T: FormatHandler + 'a> FormatContext<'a, T> {
         self.report
             .add_non_formatted_ranges(visitor.skipped_range.borrow().clone());
 
+        let mut tokens_changed = false;
+        if self.config.verify_tokens() {
+            let original = snippet_provider.entire_snippet();
+            if let Some(error) =
+                verify_tokens(&self.psess, &path, original, &visitor.buffer, self.config)
+            {
+                // Leave the file as it was rather than risk changing what the code means.
+                self.report.append(path.clone(), vec![error]);
+                visitor.buffer = original.to_owned();
+                apply_newline_style(self.config.newline_style(), &mut visitor.buffer, original);
+                tokens_changed = true;
+            }
+        }
+
+        // Line ranges can't be mapped onto the formatted code, so only whole files are verified.
+        if self.config.verify_idempotent() && self.config.file_lines().is_all() && !tokens_changed {
+            if let Some(second_pass) = format_again(&visitor.buffer, self.config, is_macro_def) {
+                let errors = idempotence_errors(&visitor.buffer, &second_pass);
+                if !errors.is_empty() {
+                    self.report.append(path.clone(), errors);
+                }
+            }
+        }
+
         self.handler.handle_formatted_file(
             &self.psess,
             path,
@@ -269,6 +411,69 @@ impl<'a, T: FormatHandler + 'a> FormatContext<'a, T> {
     }
 }
 
+/// Formats already formatted code a second time, or returns `None` if that fails.
+fn format_again(formatted: &str, config: &Config, is_macro_def: bool) -> Option<String> {
+    let mut config = config.clone();
+    config.set().emit_mode(EmitMode::Stdout);
+    config.set().verbose(Verbosity::Quiet);
+    config.set().show_parse_errors(false);
+    config.set().verify_idempotent(false);
+    config.set().verify_tokens(false);
+
+    let mut out: Vec<u8> = Vec::with_capacity(formatted.len());
+    let failed = {
+        let input = Input::Text(formatted.to_owned());
+        let mut session = Session::new(config, Some(&mut out));
+        let result = session.format_input_inner(input, is_macro_def);
+        result.is_err() || session.has_parsing_errors()
+    };
+    if failed {
+        return None;
+    }
+    String::from_utf8(out).ok()
+}
+
+/// Returns an error for each chunk of lines of `first_pass` which formatting it again changes
+/// into `second_pass`.
+pub(crate) fn idempotence_errors(first_pass: &str, second_pass: &str) -> Vec<FormattingError> {
+    make_diff(first_pass, second_pass, 0)
+        .into_iter()
+        .map(|mismatch| {
+            let mut diff = vec![];
+            let mut changed_lines = vec![];
+            for line in &mismatch.lines {
+                match line {
+                    DiffLine::Context(line) => diff.push(format!(" {line}")),
+                    DiffLine::Resulting(line) => {
+                        diff.push(format!("-{line}"));
+                        changed_lines.push(line.as_str());
+                    }
+                    DiffLine::Expected(line) => diff.push(format!("+{line}")),
+                }
+            }
+
+            let line = mismatch.line_number_orig as usize;
+            let (line_buffer, highlight) = if changed_lines.is_empty() {
+                // The second pass only inserts lines, in front of this one.
+                let line_buffer = first_pass.lines().nth(line.saturating_sub(1));
+                (line_buffer.unwrap_or_default().to_owned(), None)
+            } else {
+                let line_buffer = changed_lines.join("\n");
+                let highlight = Some(0..line_buffer.len());
+                (line_buffer, highlight)
+            };
+            FormattingError {
+                line,
+                kind: ErrorKind::NonIdempotent(diff.join("\n")),
+                is_comment: false,
+                is_string: false,
+                line_buffer,
+                highlight,
+            }
+        })
+        .collect()
+}
+
 // Handle the results of formatting.
 trait FormatHandler {
     fn handle_formatted_file(
@@ -278,6 +483,12 @@ trait FormatHandler {
         result: String,
         report: &mut FormatReport,
     ) -> Result<(), ErrorKind>;
+
+    fn handle_skipped_file(
+        &mut self,
+        path: FileName,
+        reason: UnformattedReason,
+    ) -> Result<(), ErrorKind>;
 }
 
 impl<'b, T: Write + 'b> FormatHandler for Session<'b, T> {
@@ -289,6 +500,11 @@ impl<'b, T: Write + 'b> FormatHandler for Session<'b, T> {
         result: String,
         report: &mut FormatReport,
     ) -> Result<(), ErrorKind> {
+        let reformatted = psess
+            .get_original_snippet(&path)
+            .is_some_and(|original| *original != result);
+        report.add_checked_file(reformatted);
+
         if let Some(ref mut out) = self.out {
             match source_file::write_file(
                 Some(psess),
@@ -306,11 +522,30 @@ impl<'b, T: Write + 'b> FormatHandler for Session<'b, T> {
                 }
                 _ => {}
             }
+            let regions = report.take_unformatted_regions(&path);
+            self.emitter
+                .emit_unformatted_regions(out, &path, &regions)?;
         }
 
         self.source_file.push((path, result));
         Ok(())
     }
+
+    fn handle_skipped_file(
+        &mut self,
+        path: FileName,
+        reason: UnformattedReason,
+    ) -> Result<(), ErrorKind> {
+        if let Some(ref mut out) = self.out {
+            let region = UnformattedRegion {
+                lines: None,
+                reason,
+            };
+            self.emitter
+                .emit_unformatted_regions(out, &path, &[region])?;
+        }
+        Ok(())
+    }
 }
 
 pub(crate) struct FormattingError {
@@ -341,7 +576,9 @@ impl FormattingError {
             | ErrorKind::TrailingWhitespace
             | ErrorKind::IoError(_)
             | ErrorKind::ParseError
-            | ErrorKind::LostComment => true,
+            | ErrorKind::LostComment
+            | ErrorKind::NonIdempotent(_)
+            | ErrorKind::TokenMismatch { .. } => true,
             _ => false,
         }
     }
@@ -397,6 +634,153 @@ impl ReportedErrors {
     }
 }
 
+/// Why a file was left out of formatting.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum SkipReason {
+    /// The module is annotated with `#[rustfmt::skip]`.
+    SkipAttribute,
+    /// The module is a child module and `skip_children` is set.
+    SkipChildren,
+    /// The file matches the `ignore` option.
+    Ignored,
+    /// The file is detected as generated, see `format_generated_files`.
+    Generated,
+}
+
+impl SkipReason {
+    /// The reason to list the file in an unformatted report, if it should be listed at all.
+    fn unformatted_reason(self) -> Option<UnformattedReason> {
+        match self {
+            SkipReason::SkipAttribute => Some(UnformattedReason::SkipAttribute),
+            SkipReason::Generated => Some(UnformattedReason::Generated),
+            // These files were left out at the user's request rather than given up on.
+            SkipReason::SkipChildren | SkipReason::Ignored => None,
+        }
+    }
+}
+
+/// Why a region of a file was left as it is in the source.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum UnformattedReason {
+    /// The region is annotated with `#[rustfmt::skip]`.
+    SkipAttribute,
+    /// A macro call or definition in the region could not be formatted.
+    MacroFailure(MacroErrorKind),
+    /// The region does not fit within `max_width`.
+    ExceedsMaxWidth,
+    /// The region could not be formatted for some other reason.
+    RewriteFailure,
+    /// Formatting the region would have lost a comment.
+    LostComment,
+    /// The region is outside the lines selected with `file_lines`.
+    OutOfFileLines,
+    /// The file is detected as generated, see `format_generated_files`.
+    Generated,
+    /// The region is enclosed by `// rustfmt::off` and `// rustfmt::on` comments, or holds
+    /// such a region which could not be copied on its own.
+    OffMarker,
+}
+
+impl UnformattedReason {
+    /// The reason a failed rewrite left its span unformatted, or `None` if the span was
+    /// deliberately skipped.
+    pub(crate) fn from_rewrite_error(error: &RewriteError) -> Option<UnformattedReason> {
+        match error {
+            RewriteError::SkipFormatting => None,
+            RewriteError::ExceedsMaxWidth { .. } => Some(UnformattedReason::ExceedsMaxWidth),
+            RewriteError::MacroFailure { kind, .. } => {
+                Some(UnformattedReason::MacroFailure(kind.clone()))
+            }
+            RewriteError::Unknown => Some(UnformattedReason::RewriteFailure),
+        }
+    }
+}
+
+impl fmt::Display for UnformattedReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnformattedReason::SkipAttribute => write!(f, "skipped by `#[rustfmt::skip]`"),
+            UnformattedReason::MacroFailure(MacroErrorKind::Unknown) => {
+                write!(f, "failed to format macro")
+            }
+            UnformattedReason::MacroFailure(kind) => write!(f, "failed to format macro {kind}"),
+            UnformattedReason::ExceedsMaxWidth => write!(f, "does not fit within `max_width`"),
+            UnformattedReason::RewriteFailure => write!(f, "failed to format"),
+            UnformattedReason::LostComment => write!(f, "formatting would lose a comment"),
+            UnformattedReason::OutOfFileLines => write!(f, "outside of `file_lines`"),
+            UnformattedReason::Generated => write!(f, "detected as generated"),
+            UnformattedReason::OffMarker => write!(f, "disabled with `// rustfmt::off`"),
+        }
+    }
+}
+
+/// A region of a file which was left as it is in the source.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct UnformattedRegion {
+    /// The 1-based, inclusive range of source lines, or `None` for the whole file.
+    pub(crate) lines: Option<(usize, usize)>,
+    pub(crate) reason: UnformattedReason,
+}
+
+impl UnformattedRegion {
+    pub(crate) fn from_span(
+        span: Span,
+        psess: &ParseSess,
+        reason: UnformattedReason,
+    ) -> UnformattedRegion {
+        let lo = psess.line_of_byte_pos(span.lo());
+        let hi = psess.line_of_byte_pos(span.hi());
+        UnformattedRegion {
+            lines: Some((lo, hi.max(lo))),
+            reason,
+        }
+    }
+}
+
+/// Counts of the files skipped while formatting, by reason.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct SkippedFiles {
+    /// Files matched by the `ignore` option.
+    pub ignored: usize,
+    /// Files whose module is annotated with `#[rustfmt::skip]`.
+    pub skip_attribute: usize,
+    /// Files detected as generated.
+    pub generated: usize,
+}
+
+impl SkippedFiles {
+    pub(crate) fn add(&mut self, reason: SkipReason) {
+        match reason {
+            SkipReason::SkipAttribute => self.skip_attribute += 1,
+            SkipReason::Ignored => self.ignored += 1,
+            SkipReason::Generated => self.generated += 1,
+            // Child modules are not looked at when `skip_children` is set, so
+            // they are not counted as skipped either.
+            SkipReason::SkipChildren => {}
+        }
+    }
+}
+
+/// A machine-readable summary of a single run of rustfmt over one input.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct FormatSummary {
+    /// The number of files that were formatted or checked.
+    pub files_checked: usize,
+    /// The number of files whose formatted output differs from the original.
+    pub files_reformatted: usize,
+    /// The files that were not formatted, by reason.
+    pub skipped: SkippedFiles,
+    /// The number of errors encountered, keyed by [`ErrorKind::name`].
+    pub errors: BTreeMap<String, usize>,
+}
+
+impl FormatSummary {
+    /// Records one error of the given kind.
+    pub fn add_error(&mut self, kind: &ErrorKind) {
+        *self.errors.entry(kind.name().to_owned()).or_default() += 1;
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 enum Timer {
     Disabled,
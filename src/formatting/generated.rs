@@ -1,10 +1,145 @@
-use crate::Config;
-
-/// Returns `true` if the given span is a part of generated files.
-pub(super) fn is_generated_file(original_snippet: &str, config: &Config) -> bool {
-    original_snippet
-        .lines()
-        // looking for marker only in the beginning of the file
-        .take(config.generated_marker_line_search_limit())
-        .any(|line| line.contains("@generated"))
+use std::fmt;
+
+use regex::Regex;
+
+use crate::config::FileName;
+use crate::ignore_path::IgnorePathSet;
+use crate::{Config, ErrorKind};
+
+/// Decides whether a file is generated, from its path or from a marker in its first lines.
+pub(super) struct GeneratedFiles {
+    paths: IgnorePathSet,
+    markers: Vec<Regex>,
+    line_search_limit: usize,
+}
+
+/// Why a file is considered generated.
+pub(super) enum GeneratedMatch<'a> {
+    /// The path of the file matches `generated_paths`.
+    Path,
+    /// One of the first lines of the file matches this pattern of `generated_markers`.
+    Marker(&'a str),
+}
+
+impl GeneratedFiles {
+    pub(super) fn new(config: &Config) -> Result<GeneratedFiles, ErrorKind> {
+        let paths = IgnorePathSet::from_ignore_list(&config.generated_paths())
+            .map_err(ErrorKind::InvalidGeneratedPath)?;
+        let markers = config
+            .generated_markers()
+            .0
+            .iter()
+            .map(|marker| Regex::new(marker))
+            .collect::<Result<_, _>>()
+            .map_err(ErrorKind::InvalidGeneratedMarker)?;
+        Ok(GeneratedFiles {
+            paths,
+            markers,
+            line_search_limit: config.generated_marker_line_search_limit(),
+        })
+    }
+
+    /// Returns why the file at `path` is considered generated, or `None` if it isn't.
+    pub(super) fn matches(
+        &self,
+        path: &FileName,
+        original_snippet: &str,
+    ) -> Option<GeneratedMatch<'_>> {
+        if self.paths.is_match(path) {
+            return Some(GeneratedMatch::Path);
+        }
+        original_snippet
+            .lines()
+            // looking for marker only in the beginning of the file
+            .take(self.line_search_limit)
+            .find_map(|line| self.markers.iter().find(|marker| marker.is_match(line)))
+            .map(|marker| GeneratedMatch::Marker(marker.as_str()))
+    }
+}
+
+impl fmt::Display for GeneratedMatch<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GeneratedMatch::Path => write!(f, "path matches `generated_paths`"),
+            GeneratedMatch::Marker(marker) => write!(f, "found generated marker `{marker}`"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rustfmt_config_proc_macro::nightly_only_test;
+    use std::path::{Path, PathBuf};
+
+    fn generated_files(toml: &str) -> GeneratedFiles {
+        let config = Config::from_toml(toml, Path::new("./rustfmt.toml")).unwrap();
+        GeneratedFiles::new(&config).unwrap()
+    }
+
+    fn file(path: &str) -> FileName {
+        FileName::Real(PathBuf::from(path))
+    }
+
+    #[test]
+    fn detects_default_marker() {
+        let generated_files = generated_files("");
+        let src = "// @generated by build.rs\nfn main() {}\n";
+        assert!(generated_files.matches(&file("src/lib.rs"), src).is_some());
+        assert!(
+            generated_files
+                .matches(&file("src/lib.rs"), "fn main() {}\n")
+                .is_none()
+        );
+    }
+
+    #[nightly_only_test]
+    #[test]
+    fn detects_custom_markers_within_search_limit() {
+        let generated_files = generated_files(
+            r#"
+            generated_markers = ["^// Code generated .* DO NOT EDIT\\.$", "automatically generated"]
+            generated_marker_line_search_limit = 2
+            "#,
+        );
+        let src = "// Code generated by protoc-gen-rust. DO NOT EDIT.\n";
+        assert!(generated_files.matches(&file("src/lib.rs"), src).is_some());
+        let src = "//\n// This file is automatically generated\n";
+        assert!(generated_files.matches(&file("src/lib.rs"), src).is_some());
+        let src = "//\n//\n// This file is automatically generated\n";
+        assert!(generated_files.matches(&file("src/lib.rs"), src).is_none());
+        let src = "// @generated\n";
+        assert!(generated_files.matches(&file("src/lib.rs"), src).is_none());
+    }
+
+    #[nightly_only_test]
+    #[test]
+    fn detects_generated_paths() {
+        let generated_files = generated_files(r#"generated_paths = ["*.pb.rs", "src/gen/"]"#);
+        assert!(
+            generated_files
+                .matches(&file("src/proto/foo.pb.rs"), "")
+                .is_some()
+        );
+        assert!(
+            generated_files
+                .matches(&file("src/gen/bar.rs"), "")
+                .is_some()
+        );
+        assert!(generated_files.matches(&file("src/lib.rs"), "").is_none());
+    }
+
+    #[nightly_only_test]
+    #[test]
+    fn rejects_invalid_markers() {
+        let config = Config::from_toml(
+            r#"generated_markers = ["(unclosed"]"#,
+            Path::new("./rustfmt.toml"),
+        )
+        .unwrap();
+        assert!(matches!(
+            GeneratedFiles::new(&config),
+            Err(ErrorKind::InvalidGeneratedMarker(_))
+        ));
+    }
 }
@@ -0,0 +1,1292 @@
+//! Checks that formatting only changed the code in ways that rustfmt changes it on purpose.
+//!
+//! The original and the formatted code are both lexed and normalized, so that e.g. reordered
+//! imports, trailing commas or merged derives compare equal, and the resulting tokens are compared.
+//! The words of comments are compared separately, since rustfmt is free to rewrap them.
+
+use std::borrow::Cow;
+
+use rustc_ast::token::{Delimiter, LitKind, TokenKind};
+use rustc_ast::tokenstream::{TokenStream, TokenTree};
+use rustc_ast_pretty::pprust;
+use rustc_span::symbol::Ident;
+
+use crate::ErrorKind;
+use crate::comment::{CharClasses, shorten_intra_doc_links};
+use crate::config::{
+    Config, DeriveOrder, Edition, FileName, FloatLiteralTrailingZero, MacroShape, StringSplitStyle,
+};
+use crate::formatting::FormattingError;
+use crate::macros::inline_args::inline_args;
+use crate::macros::macro_shape;
+use crate::parse::session::ParseSess;
+use crate::string::split_str_lit;
+
+/// Compares `original` with its formatted version, and returns an error pointing at the first
+/// token or comment word of `original` that doesn't match, if any.
+pub(crate) fn verify_tokens(
+    psess: &ParseSess,
+    path: &FileName,
+    original: &str,
+    formatted: &str,
+    config: &Config,
+) -> Option<FormattingError> {
+    // Nothing can be compared if the original code doesn't lex.
+    let original_stream = psess.lex_str(format!("{path} (original)"), original.to_owned())?;
+    let Some(formatted_stream) = psess.lex_str(format!("{path} (formatted)"), formatted.to_owned())
+    else {
+        return Some(mismatch_error(
+            original,
+            0,
+            0,
+            "code that can be lexed".to_owned(),
+            "invalid tokens".to_owned(),
+        ));
+    };
+
+    let normalizer = Normalizer { psess, config };
+    let original_tokens = flatten(&normalizer.lower(&original_stream, None, false));
+    let formatted_tokens = flatten(&normalizer.lower(&formatted_stream, None, false));
+    if let Some(error) = compare_tokens(original, &original_tokens, &formatted_tokens) {
+        return Some(error);
+    }
+
+    compare_comment_words(
+        original,
+        &comment_words(original, config),
+        &comment_words(formatted, config),
+    )
+}
+
+fn compare_tokens(original: &str, expected: &[Token], found: &[Token]) -> Option<FormattingError> {
+    let index = expected
+        .iter()
+        .zip(found)
+        .position(|(expected, found)| expected.text != found.text)
+        .or_else(|| (expected.len() != found.len()).then_some(expected.len().min(found.len())))?;
+
+    let describe = |token: Option<&Token>| match token {
+        Some(token) => format!("`{}`", token.text),
+        None => "the end of the file".to_owned(),
+    };
+    let (offset, len) = expected.get(index).map_or((original.len(), 0), |token| {
+        (token.offset, token.text.len())
+    });
+    Some(mismatch_error(
+        original,
+        offset,
+        len,
+        describe(expected.get(index)),
+        describe(found.get(index)),
+    ))
+}
+
+fn compare_comment_words(
+    original: &str,
+    expected: &[(usize, String)],
+    found: &[(usize, String)],
+) -> Option<FormattingError> {
+    let index = expected
+        .iter()
+        .zip(found)
+        .position(|((_, expected), (_, found))| expected != found)
+        .or_else(|| (expected.len() != found.len()).then_some(expected.len().min(found.len())))?;
+
+    let describe = |word: Option<&(usize, String)>| match word {
+        Some((_, word)) => format!("`{word}` in a comment"),
+        None => "no such comment text".to_owned(),
+    };
+    // Words that only exist in the formatted code have no position in the original.
+    let (offset, len) = expected
+        .get(index)
+        .map_or((0, 0), |(offset, word)| (*offset, word.len()));
+    Some(mismatch_error(
+        original,
+        offset,
+        len,
+        describe(expected.get(index)),
+        describe(found.get(index)),
+    ))
+}
+
+/// Creates an error for the mismatch at `offset` in `original`, highlighting up to `len` bytes.
+fn mismatch_error(
+    original: &str,
+    offset: usize,
+    len: usize,
+    expected: String,
+    found: String,
+) -> FormattingError {
+    let line_start = original[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = original[offset..]
+        .find('\n')
+        .map_or(original.len(), |i| offset + i);
+    let line_buffer = original[line_start..line_end].to_owned();
+    let column = offset - line_start;
+    let mut end = (column + len).min(line_buffer.len());
+    while !line_buffer.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    FormattingError {
+        line: original[..offset].matches('\n').count() + 1,
+        kind: ErrorKind::TokenMismatch { expected, found },
+        is_comment: false,
+        is_string: false,
+        line_buffer,
+        highlight: (end > column).then_some(column..end),
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Token {
+    text: String,
+    /// The offset of the token in the code it was lexed from.
+    offset: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Delim {
+    Paren,
+    Brace,
+    Bracket,
+}
+
+impl Delim {
+    fn open(self) -> &'static str {
+        match self {
+            Delim::Paren => "(",
+            Delim::Brace => "{",
+            Delim::Bracket => "[",
+        }
+    }
+
+    fn close(self) -> &'static str {
+        match self {
+            Delim::Paren => ")",
+            Delim::Brace => "}",
+            Delim::Bracket => "]",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Tree {
+    Token(Token),
+    /// A delimited group, with the offsets of its opening and closing delimiters.
+    Group(Delim, Vec<Tree>, usize, usize),
+}
+
+impl Tree {
+    fn is(&self, text: &str) -> bool {
+        matches!(self, Tree::Token(token) if token.text == text)
+    }
+
+    fn is_group(&self, delim: Delim) -> bool {
+        matches!(self, Tree::Group(d, ..) if *d == delim)
+    }
+
+    fn offset(&self) -> usize {
+        match self {
+            Tree::Token(token) => token.offset,
+            Tree::Group(_, _, open, _) => *open,
+        }
+    }
+
+    fn text(&self) -> String {
+        flatten(std::slice::from_ref(self))
+            .iter()
+            .map(|token| token.text.as_str())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+fn flatten(trees: &[Tree]) -> Vec<Token> {
+    fn flatten_into(trees: &[Tree], tokens: &mut Vec<Token>) {
+        for tree in trees {
+            match tree {
+                Tree::Token(token) => tokens.push(token.clone()),
+                Tree::Group(delim, inner, open, close) => {
+                    tokens.push(Token {
+                        text: delim.open().to_owned(),
+                        offset: *open,
+                    });
+                    flatten_into(inner, tokens);
+                    tokens.push(Token {
+                        text: delim.close().to_owned(),
+                        offset: *close,
+                    });
+                }
+            }
+        }
+    }
+
+    let mut tokens = vec![];
+    flatten_into(trees, &mut tokens);
+    tokens
+}
+
+fn new_token(text: impl Into<String>, offset: usize) -> Tree {
+    Tree::Token(Token {
+        text: text.into(),
+        offset,
+    })
+}
+
+/// Whether the trees contain a `;` outside of any group.
+fn has_statements(trees: &[Tree]) -> bool {
+    trees.iter().any(|tree| tree.is(";"))
+}
+
+/// Whether `tree` may be the start of a range pattern, i.e., a literal or the end of a path.
+fn is_range_operand(tree: &Tree) -> bool {
+    matches!(tree, Tree::Token(token)
+        if token.text.starts_with(|c: char| c.is_alphanumeric() || matches!(c, '_' | '\'' | '"')))
+}
+
+/// Whether `text` is an identifier which may be inlined into a format string.
+fn is_plain_ident(text: &str) -> bool {
+    text.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && text.chars().all(|c| c.is_alphanumeric() || c == '_')
+        && !Ident::from_str(text).is_reserved()
+}
+
+/// Whether `trees` are the contents of a block that rustfmt may turn into a bare expression.
+fn is_unwrappable_block(trees: &[Tree]) -> bool {
+    !trees.is_empty() && !has_statements(trees) && !trees[0].is("let") && !trees[0].is("#")
+}
+
+struct Normalizer<'a> {
+    psess: &'a ParseSess,
+    config: &'a Config,
+}
+
+impl Normalizer<'_> {
+    /// Converts `stream` into normalized trees. `delim` is the delimiter of the group containing
+    /// the stream, if any, and `in_macro` whether the stream is part of the arguments of a macro
+    /// call or the body of a macro definition.
+    fn lower(&self, stream: &TokenStream, delim: Option<Delim>, in_macro: bool) -> Vec<Tree> {
+        let mut trees = vec![];
+        // Whether the previous token may end the path of a macro, and whether the next group
+        // follows `name!` or `macro_rules! name`.
+        let mut after_path = false;
+        let mut macro_group_next = false;
+        // The path of the macro whose group comes next, and whether the previous tree is the
+        // group of a macro call.
+        let mut macro_path = String::new();
+        let mut after_macro_group = false;
+        for tree in stream.iter() {
+            match tree {
+                TokenTree::Token(token, _) => {
+                    // Doc comments are compared along with the other comments.
+                    if matches!(token.kind, TokenKind::DocComment(..)) {
+                        continue;
+                    }
+                    let text = pprust::token_to_string(token).into_owned();
+                    // rustfmt adds `;` after the calls of item macros whose braces it replaces.
+                    let follows_macro_group = std::mem::take(&mut after_macro_group);
+                    if text == ";" && follows_macro_group && !in_macro {
+                        continue;
+                    }
+                    macro_group_next =
+                        (text == "!" && after_path) || (macro_group_next && token.is_ident());
+                    after_path = token.is_ident() && !token.is_reserved_ident();
+                    if token.is_ident() {
+                        if !macro_path.ends_with("::") {
+                            macro_path.clear();
+                        }
+                        macro_path.push_str(&text);
+                    } else if text == "::" && !macro_path.is_empty() {
+                        macro_path.push_str(&text);
+                    } else if text != "!" {
+                        macro_path.clear();
+                    }
+                    let text = match token.kind {
+                        TokenKind::Literal(lit) => self.normalize_literal(lit.kind, text),
+                        _ => text,
+                    };
+                    trees.push(new_token(text, self.psess.byte_offset(token.span.lo())));
+                }
+                TokenTree::Delimited(span, _, delimiter, stream) => {
+                    after_macro_group = false;
+                    let mut delim = match delimiter {
+                        Delimiter::Parenthesis => Delim::Paren,
+                        Delimiter::Brace => Delim::Brace,
+                        Delimiter::Bracket => Delim::Bracket,
+                        Delimiter::Invisible(..) => {
+                            trees.extend(self.lower(stream, None, in_macro));
+                            continue;
+                        }
+                    };
+                    let is_macro_group = std::mem::take(&mut macro_group_next);
+                    let in_macro_group = in_macro || is_macro_group;
+                    after_path = false;
+                    // `try!(x)` may become `x?`.
+                    if is_macro_group
+                        && !in_macro
+                        && self.config.use_try_shorthand()
+                        && matches!(macro_path.as_str(), "try" | "r#try")
+                        && trees.len() >= 2
+                    {
+                        trees.truncate(trees.len() - 2);
+                        trees.extend(self.lower(stream, None, false));
+                        trees.push(new_token("?", self.psess.byte_offset(span.close.lo())));
+                        continue;
+                    }
+                    let mut inner = self.lower(stream, Some(delim), in_macro_group);
+                    if is_macro_group {
+                        inner = self.inline_format_args(&macro_path, inner);
+                        // The delimiter of a macro call may be changed by `macro_shapes`.
+                        delim = Delim::Paren;
+                        after_macro_group = true;
+                    }
+                    // Redundant parentheses may be removed.
+                    if !in_macro_group
+                        && delim == Delim::Paren
+                        && inner.len() == 1
+                        && inner[0].is_group(Delim::Paren)
+                    {
+                        trees.extend(inner);
+                        continue;
+                    }
+                    trees.push(Tree::Group(
+                        delim,
+                        inner,
+                        self.psess.byte_offset(span.open.lo()),
+                        self.psess.byte_offset(span.close.lo()),
+                    ));
+                }
+            }
+        }
+        if in_macro {
+            // A macro sees the exact tokens, which rustfmt only changes by splitting the format
+            // strings of known format-like macros.
+            self.merge_concat_literals(trees)
+        } else {
+            self.normalize(trees, delim)
+        }
+    }
+
+    /// Inlines the trailing arguments of a format-like macro which are plain identifiers into
+    /// its format string, as `inline_format_args` does, so that the arguments compare equal
+    /// whether they were inlined or not.
+    fn inline_format_args(&self, macro_path: &str, args: Vec<Tree>) -> Vec<Tree> {
+        if !self.config.inline_format_args() {
+            return args;
+        }
+        let Some(MacroShape::Format(format_str_index)) =
+            macro_shape(self.config, &format!("{macro_path}!"))
+        else {
+            return args;
+        };
+
+        let mut arg_list: Vec<&[Tree]> = args.split(|tree| tree.is(",")).collect();
+        if arg_list.last().is_some_and(|arg| arg.is_empty()) {
+            arg_list.pop();
+        }
+        let Some([Tree::Token(format_str)]) = arg_list.get(format_str_index) else {
+            return args;
+        };
+        let is_str_lit = (format_str.text.starts_with('"') || format_str.text.starts_with('r'))
+            && format_str.text.ends_with(['"', '#']);
+        if !is_str_lit {
+            return args;
+        }
+
+        let mut names = Vec::with_capacity(arg_list.len());
+        for arg in &arg_list[format_str_index + 1..] {
+            // Named arguments could shadow the identifiers which would be inlined.
+            if arg.iter().any(|tree| tree.is("=")) {
+                return args;
+            }
+            names.push(match arg {
+                [Tree::Token(token)] if is_plain_ident(&token.text) => Some(token.text.as_str()),
+                _ => None,
+            });
+        }
+        let num_inlined = names.iter().rev().take_while(|name| name.is_some()).count();
+        let num_kept = names.len() - num_inlined;
+        if num_inlined == 0 || (num_kept == 0 && self.config.edition() < Edition::Edition2021) {
+            return args;
+        }
+        names[..num_kept].fill(None);
+        let Some(new_format_str) = inline_args(&format_str.text, &names) else {
+            return args;
+        };
+
+        let num_args = format_str_index + 1 + num_kept;
+        let format_str_offset = format_str.offset;
+        let mut result = Vec::with_capacity(args.len());
+        for (i, arg) in arg_list[..num_args].iter().enumerate() {
+            if i > 0 {
+                result.push(new_token(",", arg.first().map_or(0, Tree::offset)));
+            }
+            if i == format_str_index {
+                result.push(new_token(new_format_str.clone(), format_str_offset));
+            } else {
+                result.extend(arg.iter().cloned());
+            }
+        }
+        result
+    }
+
+    fn normalize_literal(&self, kind: LitKind, text: String) -> String {
+        match kind {
+            LitKind::Integer if text.starts_with("0x") => text.to_ascii_lowercase(),
+            LitKind::Float
+                if self.config.float_literal_trailing_zero()
+                    != FloatLiteralTrailingZero::Preserve =>
+            {
+                normalize_float(&text)
+            }
+            LitKind::Str | LitKind::ByteStr | LitKind::CStr => strip_line_continuations(&text),
+            _ => text,
+        }
+    }
+
+    fn normalize(&self, trees: Vec<Tree>, delim: Option<Delim>) -> Vec<Tree> {
+        let trees = self.normalize_attributes(trees);
+        let trees = self.normalize_derives(trees);
+        let trees = self.merge_concat_literals(trees);
+        let trees = self.normalize_tokens(trees, delim);
+        let trees = self.normalize_blocks(trees);
+        let trees = self.normalize_imports(trees);
+        self.normalize_separators(trees)
+    }
+
+    /// Drops `#[doc = "..."]` attributes, which are compared as comments, and splits derives.
+    fn normalize_attributes(&self, trees: Vec<Tree>) -> Vec<Tree> {
+        let mut result = Vec::with_capacity(trees.len());
+        let mut trees = trees.into_iter().peekable();
+        while let Some(tree) = trees.next() {
+            if !tree.is("#") {
+                result.push(tree);
+                continue;
+            }
+            let bang = trees.next_if(|tree| tree.is("!"));
+            let Some(Tree::Group(Delim::Bracket, inner, open, close)) =
+                trees.next_if(|tree| tree.is_group(Delim::Bracket))
+            else {
+                result.push(tree);
+                result.extend(bang);
+                continue;
+            };
+
+            if inner.len() == 3 && inner[0].is("doc") && inner[1].is("=") {
+                continue;
+            }
+            let derives: Option<Vec<_>> = match inner.as_slice() {
+                [name, Tree::Group(Delim::Paren, paths, ..)] if name.is("derive") => Some(
+                    paths
+                        .split(|tree| tree.is(","))
+                        .filter(|path| !path.is_empty())
+                        .map(|path| {
+                            let offset = path[0].offset();
+                            let paths = Tree::Group(Delim::Paren, path.to_vec(), offset, offset);
+                            vec![name.clone(), paths]
+                        })
+                        .collect(),
+                ),
+                _ => None,
+            };
+            for inner in derives.unwrap_or_else(|| vec![inner]) {
+                result.push(tree.clone());
+                result.extend(bang.clone());
+                result.push(Tree::Group(Delim::Bracket, inner, open, close));
+            }
+        }
+        result
+    }
+
+    /// Sorts and deduplicates the runs of derives split by `normalize_attributes`, if rustfmt
+    /// reorders them.
+    fn normalize_derives(&self, trees: Vec<Tree>) -> Vec<Tree> {
+        if self.config.derive_order() == DeriveOrder::Preserve {
+            return trees;
+        }
+        let is_derive = |tree: &Tree| match tree {
+            Tree::Group(Delim::Bracket, inner, ..) => inner.first().is_some_and(|t| t.is("derive")),
+            _ => false,
+        };
+        let mut result = Vec::with_capacity(trees.len());
+        let mut derives: Vec<(String, Tree, Tree)> = vec![];
+        let mut trees = trees.into_iter().peekable();
+        while let Some(tree) = trees.next() {
+            if tree.is("#") {
+                if let Some(attr) = trees.next_if(is_derive) {
+                    derives.push((attr.text(), tree, attr));
+                    continue;
+                }
+            }
+            flush_derives(&mut derives, &mut result);
+            result.push(tree);
+        }
+        flush_derives(&mut derives, &mut result);
+        result
+    }
+
+    /// Replaces the `concat!` of the pieces of a long string literal split by
+    /// `string_split_style` with the literal.
+    fn merge_concat_literals(&self, trees: Vec<Tree>) -> Vec<Tree> {
+        let mut result: Vec<Tree> = Vec::with_capacity(trees.len());
+        for tree in trees {
+            match self.concat_literal(&result, &tree) {
+                Some(literal) => {
+                    let offset = result[result.len() - 2].offset();
+                    result.truncate(result.len() - 2);
+                    result.push(new_token(literal, offset));
+                }
+                None => result.push(tree),
+            }
+        }
+        result
+    }
+
+    fn normalize_tokens(&self, trees: Vec<Tree>, delim: Option<Delim>) -> Vec<Tree> {
+        let mut result: Vec<Tree> = Vec::with_capacity(trees.len());
+        for tree in trees {
+            match tree {
+                Tree::Token(mut token) => {
+                    // `a...b` range patterns become `a..=b`, unlike the `...` of C-variadic
+                    // functions.
+                    if token.text == "..." && result.last().is_some_and(is_range_operand) {
+                        token.text = "..=".to_owned();
+                    }
+                    // `_, _, ..` may be condensed into `..`.
+                    if self.config.condense_wildcard_suffixes() && token.text == ".." {
+                        while result.len() >= 2
+                            && result[result.len() - 1].is(",")
+                            && result[result.len() - 2].is("_")
+                        {
+                            result.truncate(result.len() - 2);
+                        }
+                    }
+                    result.push(Tree::Token(token));
+                }
+                tree => result.push(tree),
+            }
+        }
+
+        // `extern "C"` is the same as `extern` in front of a function or an extern block.
+        let mut i = 1;
+        while i < result.len() {
+            if result[i].is("\"C\"")
+                && result[i - 1].is("extern")
+                && result
+                    .get(i + 1)
+                    .is_some_and(|next| next.is("fn") || next.is_group(Delim::Brace))
+            {
+                result.remove(i);
+            } else {
+                i += 1;
+            }
+        }
+
+        // Field init shorthand: `x: x` may become `x`.
+        if delim == Some(Delim::Brace) && self.config.use_field_init_shorthand() {
+            let is_field = |tree: &Tree| match tree {
+                Tree::Token(token) => token.text.chars().all(|c| c.is_alphanumeric() || c == '_'),
+                _ => false,
+            };
+            let mut i = 0;
+            while i + 2 < result.len() {
+                let field_start = i == 0 || result[i - 1].is(",");
+                let field_end = result.get(i + 3).is_none_or(|tree| tree.is(","));
+                if field_start
+                    && field_end
+                    && is_field(&result[i])
+                    && result[i + 1].is(":")
+                    && result[i].text() == result[i + 2].text()
+                {
+                    result.drain(i + 1..i + 3);
+                }
+                i += 1;
+            }
+        }
+        result
+    }
+
+    /// Returns the string literal which `tree` amounts to, if it follows `concat!` in `preceding`
+    /// and only holds pieces of a literal split by `string_split_style`.
+    fn concat_literal(&self, preceding: &[Tree], tree: &Tree) -> Option<String> {
+        if !self.config.format_strings()
+            || self.config.string_split_style() == StringSplitStyle::Backslash
+        {
+            return None;
+        }
+        let ([.., name, bang], Tree::Group(Delim::Paren, pieces, ..)) = (preceding, tree) else {
+            return None;
+        };
+        if !name.is("concat") || !bang.is("!") || pieces.is_empty() {
+            return None;
+        }
+
+        let mut quotes = None;
+        let mut contents = String::new();
+        for (i, piece) in pieces.iter().enumerate() {
+            match piece {
+                Tree::Token(token) if i % 2 == 1 && token.text == "," => {}
+                Tree::Token(token)
+                    if i % 2 == 0 && token.text.trim_start_matches(['r', '#']).starts_with('"') =>
+                {
+                    let (opening, piece_contents, closing) = split_str_lit(&token.text)?;
+                    if *quotes.get_or_insert((opening, closing)) != (opening, closing) {
+                        return None;
+                    }
+                    contents.push_str(piece_contents);
+                }
+                _ => return None,
+            }
+        }
+        let (opening, closing) = quotes?;
+        Some(format!("{opening}{contents}{closing}"))
+    }
+
+    /// Unwraps the block bodies of closures and match arms, and sorts impl items if they may be
+    /// reordered.
+    fn normalize_blocks(&self, trees: Vec<Tree>) -> Vec<Tree> {
+        let mut result: Vec<Tree> = Vec::with_capacity(trees.len());
+        let mut match_pending = false;
+        let mut impl_pending = false;
+        for tree in trees {
+            match tree {
+                Tree::Group(Delim::Brace, inner, open, close) => {
+                    if result
+                        .last()
+                        .is_some_and(|tree| tree.is("|") || tree.is("||"))
+                        && is_unwrappable_block(&inner)
+                    {
+                        result.extend(inner);
+                        continue;
+                    }
+                    let inner = if std::mem::take(&mut match_pending) {
+                        normalize_match_arms(inner)
+                    } else if std::mem::take(&mut impl_pending) {
+                        sort_items(inner)
+                    } else {
+                        inner
+                    };
+                    result.push(Tree::Group(Delim::Brace, inner, open, close));
+                }
+                tree => {
+                    if tree.is("match") {
+                        match_pending = true;
+                    } else if tree.is("impl") && self.config.reorder_impl_items() {
+                        let item_start = result.last().is_none_or(|last| {
+                            last.is(";")
+                                || last.is("unsafe")
+                                || last.is_group(Delim::Brace)
+                                || last.is_group(Delim::Bracket)
+                        });
+                        impl_pending |= item_start;
+                    }
+                    result.push(tree);
+                }
+            }
+        }
+        result
+    }
+
+    /// Replaces each run of imports, `mod foo;` and `extern crate` items with its sorted and
+    /// deduplicated entries, since rustfmt may reorder, merge or split them.
+    fn normalize_imports(&self, trees: Vec<Tree>) -> Vec<Tree> {
+        let mut result = Vec::with_capacity(trees.len());
+        let mut entries: Vec<(String, usize)> = vec![];
+        let mut i = 0;
+        while i < trees.len() {
+            if let Some((len, item_entries)) = import_item(&trees[i..]) {
+                let offset = trees[i].offset();
+                entries.extend(item_entries.into_iter().map(|entry| (entry, offset)));
+                i += len;
+                continue;
+            }
+            flush_entries(&mut entries, &mut result);
+            result.push(trees[i].clone());
+            i += 1;
+        }
+        flush_entries(&mut entries, &mut result);
+        result
+    }
+
+    /// Drops trailing commas, and semicolons after blocks or other semicolons.
+    fn normalize_separators(&self, trees: Vec<Tree>) -> Vec<Tree> {
+        let mut result: Vec<Tree> = Vec::with_capacity(trees.len());
+        let mut trees = trees.into_iter().peekable();
+        while let Some(tree) = trees.next() {
+            let next = trees.peek();
+            if tree.is(",") && next.is_none_or(|next| next.is(">") || next.is_group(Delim::Brace)) {
+                continue;
+            }
+            if tree.is(";")
+                && result
+                    .last()
+                    .is_some_and(|last| last.is(";") || last.is_group(Delim::Brace))
+            {
+                continue;
+            }
+            result.push(tree);
+        }
+        result
+    }
+}
+
+fn flush_derives(derives: &mut Vec<(String, Tree, Tree)>, result: &mut Vec<Tree>) {
+    derives.sort_by(|a, b| a.0.cmp(&b.0));
+    derives.dedup_by(|a, b| a.0 == b.0);
+    for (_, hash, attr) in derives.drain(..) {
+        result.push(hash);
+        result.push(attr);
+    }
+}
+
+fn flush_entries(entries: &mut Vec<(String, usize)>, result: &mut Vec<Tree>) {
+    entries.sort();
+    entries.dedup_by(|a, b| a.0 == b.0);
+    result.extend(
+        entries
+            .drain(..)
+            .map(|(entry, offset)| new_token(entry, offset)),
+    );
+}
+
+/// Returns the number of trees making up the `use`, `mod foo;` or `extern crate` item at the
+/// start of `trees`, and the normalized entries of that item.
+fn import_item(trees: &[Tree]) -> Option<(usize, Vec<String>)> {
+    let mut prefix = vec![];
+    let mut i = 0;
+    while trees.get(i)?.is("#") && trees.get(i + 1)?.is_group(Delim::Bracket) {
+        prefix.push(format!("# {}", trees[i + 1].text()));
+        i += 2;
+    }
+    if trees.get(i)?.is("pub") {
+        prefix.push("pub".to_owned());
+        i += 1;
+        if let Some(restriction @ Tree::Group(Delim::Paren, ..)) = trees.get(i) {
+            prefix.push(restriction.text());
+            i += 1;
+        }
+    }
+    if !["use", "mod", "extern"]
+        .iter()
+        .any(|keyword| trees.get(i).is_some_and(|tree| tree.is(keyword)))
+    {
+        return None;
+    }
+
+    let end = i + trees[i..].iter().position(|tree| tree.is(";"))?;
+    let item = &trees[i..end];
+    let prefix = prefix.join(" ");
+    let entries = match item {
+        [keyword, path @ ..] if keyword.is("use") => {
+            let mut entries = vec![];
+            flatten_use_tree(format!("{prefix} use"), path, &mut entries);
+            entries
+        }
+        [keyword, name] if keyword.is("mod") => vec![format!("{prefix} mod {}", name.text())],
+        [keyword, krate, ..] if keyword.is("extern") && krate.is("crate") => {
+            let text: Vec<_> = item.iter().map(Tree::text).collect();
+            vec![format!("{prefix} {}", text.join(" "))]
+        }
+        _ => return None,
+    };
+    Some((end + 1, entries))
+}
+
+/// Expands the nested groups of a use tree into one entry per imported path.
+fn flatten_use_tree(mut path: String, trees: &[Tree], entries: &mut Vec<String>) {
+    for tree in trees {
+        if let Tree::Group(Delim::Brace, inner, ..) = tree {
+            for nested in inner.split(|tree| tree.is(",")) {
+                if !nested.is_empty() {
+                    flatten_use_tree(path.clone(), nested, entries);
+                }
+            }
+            return;
+        }
+        path.push(' ');
+        path.push_str(&tree.text());
+    }
+    // `a::{self}` is the same as `a`.
+    entries.push(path.replace(" :: self", ""));
+}
+
+/// Drops the commas and leading `|` of match arms, and unwraps their block bodies.
+fn normalize_match_arms(trees: Vec<Tree>) -> Vec<Tree> {
+    let mut result: Vec<Tree> = Vec::with_capacity(trees.len());
+    let mut arm_start = true;
+    for tree in trees {
+        if tree.is(",") || (arm_start && tree.is("|")) {
+            arm_start = true;
+            continue;
+        }
+        let after_arrow = result.last().is_some_and(|last| last.is("=>"));
+        match tree {
+            Tree::Group(Delim::Brace, inner, ..) if after_arrow && is_unwrappable_block(&inner) => {
+                result.extend(inner);
+                arm_start = true;
+            }
+            tree => {
+                arm_start = after_arrow && tree.is_group(Delim::Brace);
+                result.push(tree);
+            }
+        }
+    }
+    result
+}
+
+/// Sorts the items of an impl or trait body.
+fn sort_items(trees: Vec<Tree>) -> Vec<Tree> {
+    let mut items: Vec<Vec<Tree>> = vec![];
+    let mut item = vec![];
+    for tree in trees {
+        let ends_item = tree.is(";") || tree.is_group(Delim::Brace);
+        if !tree.is(";") {
+            item.push(tree);
+        }
+        if ends_item {
+            items.push(std::mem::take(&mut item));
+        }
+    }
+    if !item.is_empty() {
+        items.push(item);
+    }
+    items.sort_by_cached_key(|item| item.iter().map(Tree::text).collect::<Vec<_>>().join(" "));
+    items.into_iter().flatten().collect()
+}
+
+/// Removes the digits of a float literal that rustfmt may add or remove, e.g. `1.0` and `1.`
+/// both become `1.`, and `1.0e5` becomes `1e5`.
+fn normalize_float(text: &str) -> String {
+    let Some((integer, rest)) = text.split_once('.') else {
+        return text.to_owned();
+    };
+    let fraction_len = rest
+        .find(|c: char| !c.is_ascii_digit() && c != '_')
+        .unwrap_or(rest.len());
+    let (fraction, suffix) = rest.split_at(fraction_len);
+    let fraction = fraction.trim_end_matches(['0', '_']);
+    match (fraction.is_empty(), suffix.is_empty()) {
+        (true, true) => format!("{integer}."),
+        (true, false) => format!("{integer}{suffix}"),
+        (false, _) => format!("{integer}.{fraction}{suffix}"),
+    }
+}
+
+/// Removes escaped newlines, and the whitespace following them, from a string literal.
+fn strip_line_continuations(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('\n') => while chars.next_if(|c| c.is_whitespace()).is_some() {},
+            Some(escaped) => {
+                result.push(c);
+                result.push(escaped);
+            }
+            None => result.push(c),
+        }
+    }
+    result
+}
+
+/// Returns the words of all comments and `doc` attributes in `text`, with their offsets.
+fn comment_words(text: &str, config: &Config) -> Vec<(usize, String)> {
+    let mut words = vec![];
+    let mut in_code_block = false;
+    // The index in `words` of the first word of the current Markdown paragraph.
+    let mut paragraph_start = None;
+    // Words of comments get their own offsets, words of `doc` attributes the attribute's.
+    let mut add_line =
+        |line: &str, offset: usize, exact: bool, words: &mut Vec<(usize, String)>| {
+            // Code blocks may be reformatted.
+            if config.format_code_in_doc_comments() {
+                if line.trim_start().starts_with("```") {
+                    in_code_block = !in_code_block;
+                    paragraph_start = None;
+                    return;
+                }
+                if in_code_block {
+                    return;
+                }
+            }
+            // Setext headings may be turned into `#` headings.
+            if config.doc_comment_markdown() {
+                let trimmed = line.trim();
+                if trimmed.is_empty() {
+                    paragraph_start = None;
+                } else if let Some((level, start)) = setext_heading_level(trimmed)
+                    .and_then(|level| Some((level, paragraph_start.take()?)))
+                {
+                    let (offset, _) = words[start];
+                    words.insert(start, (offset, "#".repeat(level)));
+                    return;
+                }
+            }
+            // Links may be shortened to intra-doc links.
+            let line = if config.normalize_doc_comments() {
+                shorten_intra_doc_links(line)
+            } else {
+                Cow::Borrowed(line)
+            };
+            let mut line_words = vec![];
+            let mut word_offset = offset;
+            for part in line.split_inclusive(char::is_whitespace) {
+                let word = part.trim_end_matches(char::is_whitespace);
+                if !word.is_empty() {
+                    line_words.push((if exact { word_offset } else { offset }, word.to_owned()));
+                }
+                word_offset += part.len();
+            }
+            if config.doc_comment_markdown() {
+                let content_start = normalize_markdown_markers(&mut line_words);
+                if content_start.is_none() {
+                    paragraph_start = None;
+                } else if paragraph_start.is_none() && content_start < Some(line_words.len()) {
+                    paragraph_start = content_start.map(|start| words.len() + start);
+                }
+            }
+            words.extend(line_words);
+        };
+
+    let mut comment_start = None;
+    let mut offset = 0;
+    for (kind, c) in CharClasses::new(text.chars()) {
+        if kind.is_comment() {
+            comment_start.get_or_insert(offset);
+        } else if let Some(start) = comment_start.take() {
+            add_comment(&text[start..offset], start, &mut |line, offset| {
+                add_line(line, offset, true, &mut words)
+            });
+        }
+        offset += c.len_utf8();
+    }
+    if let Some(start) = comment_start {
+        add_comment(&text[start..], start, &mut |line, offset| {
+            add_line(line, offset, true, &mut words)
+        });
+    }
+
+    // `#[doc = "..."]` attributes may be turned into doc comments and back.
+    let mut rest = text;
+    while let Some(start) = rest.find("doc") {
+        let offset = text.len() - rest.len() + start;
+        rest = &rest[start + 3..];
+        let Some(literal) = rest.trim_start().strip_prefix('=').map(str::trim_start) else {
+            continue;
+        };
+        let in_attribute = text[..offset]
+            .trim_end()
+            .strip_suffix('[')
+            .is_some_and(|before| before.trim_end().ends_with('#') || before.ends_with('!'));
+        if let (true, Some(value)) = (in_attribute, doc_attribute_value(literal)) {
+            for line in value.lines() {
+                add_line(line, offset, false, &mut words);
+            }
+        }
+    }
+    words.sort_by_key(|(offset, _)| *offset);
+    words
+}
+
+/// Returns the level of the setext heading that `trimmed` underlines, if it is an underline.
+fn setext_heading_level(trimmed: &str) -> Option<usize> {
+    if trimmed.chars().all(|c| c == '=') {
+        Some(1)
+    } else if trimmed.chars().all(|c| c == '-') {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+/// Normalizes the Markdown list marker at the start of `words` the way `doc_comment_markdown`
+/// does, and drops the closing sequence of a `#` heading. Returns the index of the first word
+/// of a paragraph, or `None` if the line is a heading.
+fn normalize_markdown_markers(words: &mut Vec<(usize, String)>) -> Option<usize> {
+    let mut start = words.iter().take_while(|(_, word)| word == ">").count();
+    let Some((_, first)) = words.get_mut(start) else {
+        return Some(start);
+    };
+    if (1..=6).contains(&first.len()) && first.chars().all(|c| c == '#') {
+        if words.len() > start + 1
+            && words
+                .last()
+                .is_some_and(|(_, w)| w.chars().all(|c| c == '#'))
+        {
+            words.pop();
+        }
+        return None;
+    }
+    let digits = first.trim_end_matches(['.', ')']);
+    let is_ordered = first.len() == digits.len() + 1
+        && (1..=9).contains(&digits.len())
+        && digits.chars().all(|c| c.is_ascii_digit());
+    if matches!(first.as_str(), "-" | "*" | "+") {
+        *first = "-".to_owned();
+        start += 1;
+    } else if is_ordered {
+        // Ordered lists are renumbered, and may switch between `.` and `)`.
+        *first = "1.".to_owned();
+        start += 1;
+    }
+    Some(start)
+}
+
+/// Calls `add_line` for each line of `comment` with its comment markers removed, along with the
+/// offset of the remaining text.
+fn add_comment(comment: &str, start: usize, add_line: &mut dyn FnMut(&str, usize)) {
+    let mut offset = start;
+    for line in comment.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+        let trimmed = trimmed.trim_end();
+        let trimmed = trimmed.strip_suffix("*/").unwrap_or(trimmed);
+        let (marker_len, text) = ["///", "//!", "//", "/**", "/*!", "/*", "*"]
+            .iter()
+            .find_map(|marker| Some((marker.len(), trimmed.strip_prefix(marker)?)))
+            .unwrap_or((0, trimmed));
+        add_line(text, offset + indent + marker_len);
+        offset += line.len();
+    }
+}
+
+/// Returns the unescaped value of the string literal at the start of `literal`.
+fn doc_attribute_value(literal: &str) -> Option<String> {
+    if let Some(raw) = literal.strip_prefix('r') {
+        let hashes = raw.len() - raw.trim_start_matches('#').len();
+        let value = raw[hashes..].strip_prefix('"')?;
+        let end = value.find(&format!("\"{}", "#".repeat(hashes)))?;
+        return Some(value[..end].to_owned());
+    }
+
+    let mut chars = literal.strip_prefix('"')?.chars();
+    let mut value = String::new();
+    loop {
+        match chars.next()? {
+            '"' => return Some(value),
+            '\\' => match chars.next()? {
+                'n' => value.push('\n'),
+                't' => value.push('\t'),
+                '\n' => {}
+                escaped => value.push(escaped),
+            },
+            c => value.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::Edition;
+
+    fn verify(original: &str, formatted: &str) -> Option<FormattingError> {
+        rustc_span::create_session_if_not_set_then(Edition::Edition2021.into(), |_| {
+            let config = Config::default();
+            let psess = ParseSess::new(&config).unwrap();
+            verify_tokens(&psess, &FileName::Stdin, original, formatted, &config)
+        })
+    }
+
+    #[test]
+    fn verify_tokens_accepts_intended_changes() {
+        assert!(verify("use b;use a;\n", "use a;\nuse b;\n").is_none());
+        assert!(verify("fn f(){g(a,b,)}", "fn f() {\n    g(a, b)\n}\n").is_none());
+        assert!(verify("fn f(){ (|| { a })() }", "fn f() {\n    (|| a)()\n}\n").is_none());
+        assert!(verify("//a b\nfn f() {}", "// a\n// b\nfn f() {}\n").is_none());
+    }
+
+    #[test]
+    fn verify_tokens_rejects_changes_rustfmt_does_not_make() {
+        assert!(verify("m!(a, b,);", "m!(a, b);").is_some());
+        assert!(verify("m! { {a}, }", "m! { {a} }").is_some());
+        assert!(verify("m!(0...9);", "m!(0..=9);").is_some());
+        assert!(verify("m!(extern \"C\" fn f() {});", "m!(extern fn f() {});").is_some());
+        assert!(verify("// a\n// b\nfn f() {}", "// b\n// a\nfn f() {}\n").is_some());
+        assert!(
+            verify(
+                "fn f() { match x { 0...9 => {} } }",
+                "fn f() {\n    match x {\n        0..=9 => {}\n    }\n}\n"
+            )
+            .is_none()
+        );
+        assert!(verify("extern \"C\" fn f() {}", "extern fn f() {}\n").is_none());
+    }
+
+    #[test]
+    fn verify_tokens_accepts_macro_delimiters() {
+        assert!(verify("fn f() { vec!(1, 2); }", "fn f() {\n    vec![1, 2];\n}\n").is_none());
+        assert!(verify("fn f() { vec!(1, 2); }", "fn f() {\n    vec![1, 3];\n}\n").is_some());
+    }
+
+    #[test]
+    fn verify_tokens_accepts_try_shorthand() {
+        rustc_span::create_session_if_not_set_then(Edition::Edition2021.into(), |_| {
+            let mut config = Config::default();
+            config.set().use_try_shorthand(true);
+            let psess = ParseSess::new(&config).unwrap();
+            let verify = |original: &str, formatted: &str| {
+                verify_tokens(&psess, &FileName::Stdin, original, formatted, &config)
+            };
+            let original = "fn f() -> R { r#try!(g(a)); }";
+            assert!(verify(original, "fn f() -> R {\n    g(a)?;\n}\n").is_none());
+            assert!(verify(original, "fn f() -> R {\n    g(b)?;\n}\n").is_some());
+        })
+    }
+
+    #[test]
+    fn verify_tokens_accepts_inlined_format_args() {
+        rustc_span::create_session_if_not_set_then(Edition::Edition2021.into(), |_| {
+            let mut config = Config::default();
+            config.set().inline_format_args(true);
+            let psess = ParseSess::new(&config).unwrap();
+            let verify = |original: &str, formatted: &str| {
+                verify_tokens(&psess, &FileName::Stdin, original, formatted, &config)
+            };
+            let original = r#"fn f() { println!("{} {:?}", g(a), b); }"#;
+            let inlined = "fn f() {\n    println!(\"{} {b:?}\", g(a));\n}\n";
+            assert!(verify(original, inlined).is_none());
+            let wrong = "fn f() {\n    println!(\"{} {c:?}\", g(a));\n}\n";
+            assert!(verify(original, wrong).is_some());
+        })
+    }
+
+    #[test]
+    fn verify_tokens_accepts_concat_pieces() {
+        rustc_span::create_session_if_not_set_then(Edition::Edition2021.into(), |_| {
+            let mut config = Config::default();
+            config.set().format_strings(true);
+            config.set().string_split_style(StringSplitStyle::Concat);
+            let psess = ParseSess::new(&config).unwrap();
+            let verify = |original: &str, formatted: &str| {
+                verify_tokens(&psess, &FileName::Stdin, original, formatted, &config)
+            };
+            assert!(verify(r#"f("ab cd");"#, r#"f(concat!("ab ", "cd",));"#).is_none());
+            assert!(verify(r##"f(r#"ab"#);"##, r##"f(concat!(r#"a"#, r#"b"#));"##).is_none());
+            assert!(verify(r#"f("ab cd");"#, r#"f(concat!("ab", "cd"));"#).is_some());
+        })
+    }
+
+    #[test]
+    fn verify_tokens_accepts_ordered_derives() {
+        rustc_span::create_session_if_not_set_then(Edition::Edition2021.into(), |_| {
+            let mut config = Config::default();
+            config.set().derive_order(DeriveOrder::Alphabetical);
+            let psess = ParseSess::new(&config).unwrap();
+            let verify = |original: &str, formatted: &str| {
+                verify_tokens(&psess, &FileName::Stdin, original, formatted, &config)
+            };
+            let original = "#[derive(Debug, Clone)]\n#[derive(Debug)]\nstruct S;\n";
+            assert!(verify(original, "#[derive(Clone, Debug)]\nstruct S;\n").is_none());
+            assert!(verify(original, "#[derive(Clone)]\nstruct S;\n").is_some());
+        })
+    }
+
+    #[test]
+    fn verify_tokens_reports_first_difference() {
+        let error = verify(
+            "fn f() {\n    foo(a, b);\n}\n",
+            "fn f() {\n    foo(a);\n}\n",
+        )
+        .unwrap();
+        assert_eq!(error.line, 2);
+        assert_eq!(error.highlight, Some(9..10));
+        assert_eq!(
+            error.kind.to_string(),
+            "formatting changed the code, expected `,` but found `)`"
+        );
+
+        let error = verify("// a b\nfn f() {}\n", "// a\nfn f() {}\n").unwrap();
+        assert_eq!(error.line, 1);
+        assert_eq!(
+            error.kind.to_string(),
+            "formatting changed the code, expected `b` in a comment but found no such comment \
+             text"
+        );
+    }
+
+    #[test]
+    fn normalize_float_literals() {
+        assert_eq!(normalize_float("1."), "1.");
+        assert_eq!(normalize_float("1.0"), "1.");
+        assert_eq!(normalize_float("1.50"), "1.5");
+        assert_eq!(normalize_float("1.0e5"), "1e5");
+        assert_eq!(normalize_float("1.0_f32"), "1f32");
+        assert_eq!(normalize_float("1e5"), "1e5");
+    }
+
+    #[test]
+    fn strip_string_line_continuations() {
+        assert_eq!(
+            strip_line_continuations("\"foo \\\n        bar\\n\""),
+            "\"foo bar\\n\""
+        );
+    }
+
+    #[test]
+    fn comment_words_ignore_markers_and_wrapping() {
+        let config = Config::default();
+        let words = |text| {
+            comment_words(text, &config)
+                .into_iter()
+                .map(|(_, word)| word)
+                .collect::<Vec<_>>()
+        };
+        let expected = vec!["Foo", "bar", "baz."];
+        assert_eq!(words("/// Foo bar\n/// baz.\nfn f() {}\n"), expected);
+        assert_eq!(
+            words("/**\n * Foo\n * bar baz.\n */\nfn f() {}\n"),
+            expected
+        );
+        assert_eq!(words("#[doc = \"Foo bar baz.\"]\nfn f() {}\n"), expected);
+        assert_eq!(words("fn f() {} // Foo bar\n// baz.\n"), expected);
+    }
+
+    #[test]
+    fn comment_words_normalize_markdown_markers() {
+        let mut config = Config::default();
+        config.set().doc_comment_markdown(true);
+        let words = |text| {
+            comment_words(text, &config)
+                .into_iter()
+                .map(|(_, word)| word)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            words("/// * a\n/// + b\n///\n/// 2) c\n/// 3) d\nfn f() {}\n"),
+            words("/// - a\n/// * b\n///\n/// 2. c\n/// 3. d\nfn f() {}\n"),
+        );
+        assert_eq!(
+            words("/// Foo\n/// bar\n/// ===\n///\n/// Baz\n/// ---\nfn f() {}\n"),
+            vec!["#", "Foo", "bar", "##", "Baz"],
+        );
+        assert_eq!(words("/// ## Baz ##\nfn f() {}\n"), vec!["##", "Baz"]);
+        assert_ne!(words("/// - a\nfn f() {}\n"), words("/// - b\nfn f() {}\n"));
+    }
+
+    #[test]
+    fn comment_words_report_offsets() {
+        let config = Config::default();
+        let words = comment_words("fn f() {}\n// a\n//\n// b\n", &config);
+        assert_eq!(words, vec![(13, "a".to_owned()), (21, "b".to_owned())]);
+    }
+
+    #[test]
+    fn mismatch_error_highlights_token() {
+        let error = mismatch_error(
+            "fn f() {\n    foo(a, b);\n}\n",
+            18,
+            1,
+            "`,`".to_owned(),
+            "`)`".to_owned(),
+        );
+        assert_eq!(error.line, 2);
+        assert_eq!(error.line_buffer, "    foo(a, b);");
+        assert_eq!(error.highlight, Some(9..10));
+    }
+}
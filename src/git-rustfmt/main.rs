@@ -2,10 +2,12 @@
 // `rustc_driver`.
 #![feature(rustc_private)]
 
+use std::collections::HashMap;
 use std::env;
-use std::io::stdout;
+use std::fs;
+use std::io::{Write, stdout};
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Stdio};
 use std::str::FromStr;
 
 use getopts::{Matches, Options};
@@ -14,9 +16,16 @@ use tracing::debug;
 use tracing_subscriber::EnvFilter;
 
 use crate::rustfmt::{
-    CliOptions, FormatReportFormatterBuilder, Input, Session, Version, load_config,
+    CliOptions, Config as RustfmtConfig, EmitMode, FileLines, FileName,
+    FormatReportFormatterBuilder, Input, Range, Session, Verbosity, Version, load_config,
 };
 
+/// The pre-commit hook written by `git-rustfmt install-hook`.
+const PRE_COMMIT_HOOK: &str = "#!/bin/sh
+# Installed by `git-rustfmt install-hook`.
+exec git-rustfmt --staged
+";
+
 fn prune_files(files: Vec<&str>) -> Vec<&str> {
     let prefixes: Vec<_> = files
         .iter()
@@ -65,11 +74,18 @@ fn fmt_files(files: &[&str]) -> i32 {
     let (config, _) =
         load_config::<NullOptions>(Some(Path::new(".")), None).expect("couldn't load config");
 
+    fmt_files_with_config(config, files.iter().map(PathBuf::from))
+}
+
+fn fmt_files_with_config<I>(config: RustfmtConfig, files: I) -> i32
+where
+    I: IntoIterator<Item = PathBuf>,
+{
     let mut exit_code = 0;
     let mut out = stdout();
     let mut session = Session::new(config, Some(&mut out));
     for file in files {
-        let report = session.format(Input::File(PathBuf::from(file))).unwrap();
+        let report = session.format(Input::File(file)).unwrap();
         if report.has_warnings() {
             eprintln!("{}", FormatReportFormatterBuilder::new(&report).build());
         }
@@ -80,6 +96,252 @@ fn fmt_files(files: &[&str]) -> i32 {
     exit_code
 }
 
+/// Runs git with the given arguments, optionally feeding `input` to its stdin, and returns its
+/// stdout. Exits the process if git fails.
+fn git(args: &[&str], input: Option<&[u8]>) -> Vec<u8> {
+    let mut child = Command::new("git")
+        .args(args)
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Couldn't execute Git");
+    if let Some(input) = input {
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(input)
+            .expect("Couldn't write to Git");
+    }
+    let output = child.wait_with_output().expect("Couldn't execute Git");
+    if !output.status.success() {
+        eprintln!("`git {}` failed", args.join(" "));
+        std::process::exit(1);
+    }
+    output.stdout
+}
+
+/// Changes the current directory to the top-level directory of the repository, since the paths
+/// reported by git are relative to it.
+fn cd_to_toplevel() {
+    let toplevel = git(&["rev-parse", "--show-toplevel"], None);
+    env::set_current_dir(String::from_utf8_lossy(&toplevel).trim())
+        .expect("Couldn't change to the top-level directory");
+}
+
+/// Returns the `.rs` files with staged changes, excluding deleted files.
+fn staged_files() -> Vec<String> {
+    let output = git(
+        &[
+            "diff",
+            "--cached",
+            "--name-only",
+            "--diff-filter=ACMR",
+            "-z",
+        ],
+        None,
+    );
+    String::from_utf8_lossy(&output)
+        .split('\0')
+        .filter(|s| s.ends_with(".rs"))
+        .map(std::borrow::ToOwned::to_owned)
+        .collect()
+}
+
+/// Returns whether the working tree version of `file` differs from its index version, i.e.,
+/// whether only some of its changes are staged.
+fn has_unstaged_changes(file: &str) -> bool {
+    !git(&["diff", "--name-only", "--", file], None).is_empty()
+}
+
+/// Formats `text`, using the configuration found from the directory of `file`. The text is left
+/// as it is if `file` would be skipped, e.g. because it is ignored or generated. Returns `None`
+/// if the text could not be formatted.
+fn format_text(text: &str, file: &Path) -> Option<String> {
+    let dir = match file.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let (mut config, _) =
+        load_config::<NullOptions>(Some(dir), None).expect("couldn't load config");
+    config.set().emit_mode(EmitMode::Stdout);
+    config.set().verbose(Verbosity::Quiet);
+
+    let mut out = Vec::with_capacity(text.len());
+    {
+        let mut session = Session::new(config, Some(&mut out));
+        if session.skips_file(file, text).ok()? {
+            return Some(text.to_owned());
+        }
+        let report = session.format(Input::Text(text.to_owned())).ok()?;
+        if report.has_warnings() {
+            eprintln!("{}", FormatReportFormatterBuilder::new(&report).build());
+        }
+        if session.has_parsing_errors() || session.has_operational_errors() {
+            return None;
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+/// Formats the index version of every staged `.rs` file and writes the result back to the
+/// index. Files without unstaged changes are also updated in the working tree; partially
+/// staged files keep their unstaged changes in the working tree untouched.
+fn fmt_staged_files() -> i32 {
+    cd_to_toplevel();
+
+    let mut exit_code = 0;
+    for file in staged_files() {
+        // `<mode> <object> <stage>\t<file>`
+        let entry = git(&["ls-files", "--stage", "-z", "--", &file], None);
+        let entry = String::from_utf8_lossy(&entry);
+        let mut fields = entry.split_whitespace();
+        let (Some(mode), Some(object)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+
+        let original = git(&["cat-file", "blob", object], None);
+        let Ok(original) = String::from_utf8(original) else {
+            eprintln!("Skipping `{file}`: not valid UTF-8");
+            continue;
+        };
+        let Some(formatted) = format_text(&original, Path::new(&file)) else {
+            eprintln!("Failed to format `{file}`");
+            exit_code = 1;
+            continue;
+        };
+        if formatted == original {
+            continue;
+        }
+
+        let partially_staged = has_unstaged_changes(&file);
+        let object = git(
+            &["hash-object", "-w", "--stdin", "--path", &file],
+            Some(formatted.as_bytes()),
+        );
+        let object = String::from_utf8_lossy(&object);
+        let cacheinfo = format!("{mode},{},{file}", object.trim());
+        git(&["update-index", "--cacheinfo", &cacheinfo], None);
+
+        if partially_staged {
+            println!("Formatted staged changes of `{file}` (unstaged changes left as is)");
+        } else {
+            fs::write(&file, &formatted).expect("Couldn't write to working tree");
+            println!("Formatted `{file}`");
+        }
+    }
+    exit_code
+}
+
+/// Parses the new-file side of a unified diff hunk header, e.g. `-10,2 +12,3 @@`, into the
+/// range of added or modified lines. Returns `None` for hunks which only delete lines.
+fn parse_hunk_range(hunk: &str) -> Option<Range> {
+    let new_side = hunk.split_whitespace().find_map(|s| s.strip_prefix('+'))?;
+    let (start, count) = match new_side.split_once(',') {
+        Some((start, count)) => (start.parse().ok()?, count.parse().ok()?),
+        None => (new_side.parse().ok()?, 1),
+    };
+    if count == 0 {
+        return None;
+    }
+    Some(Range::new(start, start + count - 1))
+}
+
+/// Returns the lines of `.rs` files changed since `rev`, including uncommitted changes.
+fn changed_lines(rev: &str) -> FileLines {
+    let diff = git(
+        &[
+            "diff",
+            "-U0",
+            "--no-color",
+            "--no-ext-diff",
+            rev,
+            "--",
+            "*.rs",
+        ],
+        None,
+    );
+    let diff = String::from_utf8_lossy(&diff);
+
+    let mut ranges: HashMap<FileName, Vec<Range>> = HashMap::new();
+    let mut file = None;
+    for line in diff.lines() {
+        if let Some(path) = line.strip_prefix("+++ ") {
+            // Deleted files are reported as `+++ /dev/null`.
+            file = path
+                .strip_prefix("b/")
+                .and_then(|path| Path::new(path).canonicalize().ok())
+                .map(FileName::Real);
+        } else if let Some(hunk) = line.strip_prefix("@@ ") {
+            if let (Some(file), Some(range)) = (&file, parse_hunk_range(hunk)) {
+                ranges.entry(file.clone()).or_default().push(range);
+            }
+        }
+    }
+    FileLines::from_ranges(ranges)
+}
+
+/// Formats only the lines changed since `rev` in each changed file.
+fn fmt_changed_lines(rev: &str) -> i32 {
+    cd_to_toplevel();
+
+    let (config, files) = changed_lines_config(rev);
+    fmt_files_with_config(config, files)
+}
+
+/// Returns the config formatting only the lines changed since `rev`, along with the changed
+/// files, sorted.
+fn changed_lines_config(rev: &str) -> (RustfmtConfig, Vec<PathBuf>) {
+    let file_lines = changed_lines(rev);
+    let mut files: Vec<PathBuf> = file_lines
+        .files()
+        .filter_map(|file| match file {
+            FileName::Real(path) => Some(path.clone()),
+            FileName::Stdin => None,
+        })
+        .collect();
+    files.sort();
+    debug!("files: {:?}", files);
+
+    let (mut config, _) =
+        load_config::<NullOptions>(Some(Path::new(".")), None).expect("couldn't load config");
+    config.set().file_lines(file_lines);
+    // Every changed file is formatted on its own, so there is no need to visit child modules.
+    config.set().skip_children(true);
+
+    (config, files)
+}
+
+/// Writes a pre-commit hook which runs `git-rustfmt --staged`.
+fn install_hook(force: bool) -> i32 {
+    let hooks_dir = git(&["rev-parse", "--git-path", "hooks"], None);
+    let hooks_dir = PathBuf::from(String::from_utf8_lossy(&hooks_dir).trim());
+    let hook = hooks_dir.join("pre-commit");
+
+    if !force && hook.exists() {
+        eprintln!(
+            "`{}` already exists, use `--force` to overwrite it",
+            hook.display()
+        );
+        return 1;
+    }
+
+    fs::create_dir_all(&hooks_dir).expect("Couldn't create hooks directory");
+    fs::write(&hook, PRE_COMMIT_HOOK).expect("Couldn't write pre-commit hook");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&hook, fs::Permissions::from_mode(0o755))
+            .expect("Couldn't make pre-commit hook executable");
+    }
+    println!("Installed pre-commit hook at `{}`", hook.display());
+    0
+}
+
 struct NullOptions;
 
 impl CliOptions for NullOptions {
@@ -134,11 +396,37 @@ fn make_opts() -> Options {
     opts.optflag("h", "help", "show this message");
     opts.optflag("c", "check", "check only, don't format (unimplemented)");
     opts.optflag("u", "uncommitted", "format uncommitted files");
+    opts.optflag(
+        "s",
+        "staged",
+        "format the staged version of files and update the index",
+    );
+    opts.optflag(
+        "l",
+        "lines-only",
+        "only format the lines changed since <rev>, including uncommitted changes",
+    );
+    opts.optflag(
+        "f",
+        "force",
+        "overwrite an existing hook when running `install-hook`",
+    );
     opts
 }
 
+enum Operation {
+    /// Format the files changed in the last commits.
+    Commits(String),
+    /// Format the staged version of files.
+    Staged,
+    /// Format the lines changed since the given revision.
+    LinesOnly(String),
+    /// Install a pre-commit hook.
+    InstallHook { force: bool },
+}
+
 struct Config {
-    commits: String,
+    operation: Operation,
     uncommitted: bool,
 }
 
@@ -147,8 +435,11 @@ impl Config {
         // `--help` display help message and quit
         if matches.opt_present("h") {
             let message = format!(
-                "\nusage: {} <commits> [options]\n\n\
-                 commits: number of commits to format, default: 1",
+                "\nusage: {0} <commits> [options]\n       {0} --lines-only [<rev>] [options]\n       \
+                 {0} install-hook [options]\n\n\
+                 commits: number of commits to format, default: 1\n\
+                 rev: revision to compare against with `--lines-only`, default: HEAD\n\
+                 install-hook: write a pre-commit hook which runs `--staged`",
                 env::args_os().next().unwrap().to_string_lossy()
             );
             println!("{}", opts.usage(&message));
@@ -156,7 +447,7 @@ impl Config {
         }
 
         let mut config = Config {
-            commits: "1".to_owned(),
+            operation: Operation::Commits("1".to_owned()),
             uncommitted: false,
         };
 
@@ -172,11 +463,37 @@ impl Config {
             panic!("unknown arguments, use `-h` for usage");
         }
         if matches.free.len() == 1 {
-            let commits = matches.free[0].trim();
-            if u32::from_str(commits).is_err() {
+            let arg = matches.free[0].trim();
+            if arg == "install-hook" {
+                config.operation = Operation::InstallHook {
+                    force: matches.opt_present("f"),
+                };
+                return config;
+            }
+        }
+
+        if matches.opt_present("l") {
+            if matches.opt_present("s") {
+                panic!("`--lines-only` can't be combined with `--staged`");
+            }
+            let rev = matches.free.first().map_or("HEAD", |rev| rev.trim());
+            config.operation = Operation::LinesOnly(rev.to_owned());
+            return config;
+        }
+
+        if matches.free.len() == 1 {
+            let arg = matches.free[0].trim();
+            if u32::from_str(arg).is_err() {
                 panic!("Couldn't parse number of commits");
             }
-            config.commits = commits.to_owned();
+            config.operation = Operation::Commits(arg.to_owned());
+        }
+
+        if matches.opt_present("s") {
+            if matches.free.len() == 1 {
+                panic!("`--staged` can't be combined with a number of commits");
+            }
+            config.operation = Operation::Staged;
         }
 
         config
@@ -194,11 +511,18 @@ fn main() {
         .expect("Couldn't parse command line");
     let config = Config::from_args(&matches, &opts);
 
+    let commits = match config.operation {
+        Operation::InstallHook { force } => std::process::exit(install_hook(force)),
+        Operation::Staged => std::process::exit(fmt_staged_files()),
+        Operation::LinesOnly(rev) => std::process::exit(fmt_changed_lines(&rev)),
+        Operation::Commits(commits) => commits,
+    };
+
     if !config.uncommitted {
         check_uncommitted();
     }
 
-    let stdout = git_diff(&config.commits);
+    let stdout = git_diff(&commits);
     let files = get_files(&stdout);
     debug!("files: {:?}", files);
     let files = prune_files(files);
@@ -206,3 +530,170 @@ fn main() {
     let exit_code = fmt_files(&files);
     std::process::exit(exit_code);
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Mutex, MutexGuard, PoisonError};
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    /// The tests change the current directory, which is shared by all threads.
+    static CURRENT_DIR: Mutex<()> = Mutex::new(());
+
+    /// Creates a git repository in a temporary directory and makes it the current directory.
+    fn temp_repo() -> (TempDir, MutexGuard<'static, ()>) {
+        let guard = CURRENT_DIR.lock().unwrap_or_else(PoisonError::into_inner);
+        let dir = tempfile::tempdir().unwrap();
+        env::set_current_dir(dir.path()).unwrap();
+        git(&["init", "-q"], None);
+        (dir, guard)
+    }
+
+    fn stage(file: &str, contents: &str) {
+        fs::write(file, contents).unwrap();
+        git(&["add", file], None);
+    }
+
+    fn staged_contents(file: &str) -> String {
+        String::from_utf8(git(&["show", &format!(":{file}")], None)).unwrap()
+    }
+
+    fn commit() {
+        git(
+            &[
+                "-c",
+                "user.name=rustfmt",
+                "-c",
+                "user.email=rustfmt@example.com",
+                "commit",
+                "-qm",
+                "commit",
+            ],
+            None,
+        );
+    }
+
+    /// Commits `a.rs` and `b.rs`, then deletes a line of `a.rs`, changes and adds a line to
+    /// `b.rs` and stages a new `c.rs`. Returns the line ranges expected for the changes.
+    fn change_files() -> FileLines {
+        stage("a.rs", "fn a() {}\nfn b() {}\nfn c() {}\n");
+        stage("b.rs", "fn x() {}\nfn y() {}\n");
+        commit();
+        fs::write("a.rs", "fn a() {}\nfn c() {}\n").unwrap();
+        fs::write("b.rs", "fn x() {}\nfn  y( ) {}\nfn z() {}\n").unwrap();
+        stage("c.rs", "fn c() {}\n");
+
+        let file = |path: &str| FileName::Real(Path::new(path).canonicalize().unwrap());
+        FileLines::from_ranges(HashMap::from([
+            (file("b.rs"), vec![Range::new(2, 3)]),
+            (file("c.rs"), vec![Range::new(1, 1)]),
+        ]))
+    }
+
+    #[test]
+    fn parse_hunk_range_returns_new_lines() {
+        assert_eq!(parse_hunk_range("-10,2 +12,3 @@"), Some(Range::new(12, 14)));
+        assert_eq!(
+            parse_hunk_range("-10 +12 @@ fn main() {"),
+            Some(Range::new(12, 12))
+        );
+        assert_eq!(parse_hunk_range("-0,0 +1,2 @@"), Some(Range::new(1, 2)));
+        // Hunks which only delete lines have no new lines.
+        assert_eq!(parse_hunk_range("-10,2 +9,0 @@"), None);
+        assert_eq!(parse_hunk_range("-10,2 @@"), None);
+    }
+
+    #[test]
+    fn changed_lines_lists_added_and_modified_lines() {
+        let _repo = temp_repo();
+        let expected = change_files();
+        assert_eq!(changed_lines("HEAD"), expected);
+    }
+
+    #[test]
+    fn changed_lines_config_formats_changed_lines_of_changed_files() {
+        let _repo = temp_repo();
+        let expected = change_files();
+        let (config, files) = changed_lines_config("HEAD");
+        assert_eq!(config.file_lines(), expected);
+        assert!(config.skip_children());
+        let file = |path: &str| Path::new(path).canonicalize().unwrap();
+        assert_eq!(files, [file("b.rs"), file("c.rs")]);
+    }
+
+    #[test]
+    #[should_panic(expected = "`--lines-only` can't be combined with `--staged`")]
+    fn lines_only_cant_be_combined_with_staged() {
+        let opts = make_opts();
+        let matches = opts.parse(["-l", "-s"]).unwrap();
+        Config::from_args(&matches, &opts);
+    }
+
+    #[test]
+    fn staged_files_lists_staged_rust_files() {
+        let _repo = temp_repo();
+        stage("a.rs", "fn a() {}\n");
+        stage("notes.txt", "notes\n");
+        fs::write("unstaged.rs", "fn b() {}\n").unwrap();
+        assert_eq!(staged_files(), ["a.rs"]);
+    }
+
+    #[test]
+    fn fmt_staged_files_formats_index_and_working_tree() {
+        let _repo = temp_repo();
+        stage("a.rs", "fn  a( ) {}\n");
+        assert_eq!(fmt_staged_files(), 0);
+        assert_eq!(staged_contents("a.rs"), "fn a() {}\n");
+        assert_eq!(fs::read_to_string("a.rs").unwrap(), "fn a() {}\n");
+    }
+
+    #[test]
+    fn fmt_staged_files_keeps_unstaged_changes() {
+        let _repo = temp_repo();
+        stage("a.rs", "fn  a( ) {}\n");
+        fs::write("a.rs", "fn  a( ) {}\nfn  b( ) {}\n").unwrap();
+        assert_eq!(fmt_staged_files(), 0);
+        assert_eq!(staged_contents("a.rs"), "fn a() {}\n");
+        assert_eq!(
+            fs::read_to_string("a.rs").unwrap(),
+            "fn  a( ) {}\nfn  b( ) {}\n"
+        );
+    }
+
+    #[test]
+    fn fmt_staged_files_skips_generated_and_skipped_files() {
+        let _repo = temp_repo();
+        let generated = "// @generated\nfn  a( ) {}\n";
+        let skipped = "#![rustfmt::skip]\nfn  b( ) {}\n";
+        stage("generated.rs", generated);
+        stage("skipped.rs", skipped);
+        assert_eq!(fmt_staged_files(), 0);
+        assert_eq!(staged_contents("generated.rs"), generated);
+        assert_eq!(staged_contents("skipped.rs"), skipped);
+        assert_eq!(fs::read_to_string("skipped.rs").unwrap(), skipped);
+    }
+
+    #[test]
+    fn install_hook_writes_pre_commit_hook() {
+        let _repo = temp_repo();
+        let hook = Path::new(".git/hooks/pre-commit");
+        assert_eq!(install_hook(false), 0);
+        assert_eq!(fs::read_to_string(hook).unwrap(), PRE_COMMIT_HOOK);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(
+                fs::metadata(hook).unwrap().permissions().mode() & 0o111,
+                0o111
+            );
+        }
+
+        fs::write(hook, "#!/bin/sh\n").unwrap();
+        assert_eq!(install_hook(false), 1);
+        assert_eq!(fs::read_to_string(hook).unwrap(), "#!/bin/sh\n");
+        assert_eq!(install_hook(true), 0);
+        assert_eq!(fs::read_to_string(hook).unwrap(), PRE_COMMIT_HOOK);
+    }
+}
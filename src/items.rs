@@ -1516,7 +1516,7 @@ pub(crate) fn format_struct_struct(
     }
 }
 
-fn get_bytepos_after_visibility(vis: &ast::Visibility, default_span: Span) -> BytePos {
+pub(crate) fn get_bytepos_after_visibility(vis: &ast::Visibility, default_span: Span) -> BytePos {
     match vis.kind {
         ast::VisibilityKind::Restricted { .. } => vis.span.hi(),
         _ => default_span.lo(),
@@ -27,18 +27,22 @@ use std::fmt;
 use std::io::{self, Write};
 use std::mem;
 use std::panic;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
 use rustc_ast::ast;
-use rustc_span::symbol;
+use rustc_span::{Span, symbol};
 use thiserror::Error;
 
 use crate::comment::LineClasses;
 use crate::emitter::Emitter;
-use crate::formatting::{FormatErrorMap, FormattingError, ReportedErrors, SourceFile};
+use crate::formatting::{
+    FormatErrorMap, FormattingError, ReportedErrors, SkipReason, SourceFile, UnformattedReason,
+    UnformattedRegion,
+};
 use crate::modules::ModuleResolutionError;
 use crate::parse::parser::DirectoryOwnership;
+use crate::parse::session::ParseSess;
 use crate::shape::Indent;
 use crate::utils::indent_next_line;
 
@@ -49,6 +53,8 @@ pub use crate::config::{
 
 pub use crate::format_report_formatter::{FormatReportFormatter, FormatReportFormatterBuilder};
 
+pub use crate::formatting::{FormatSummary, SkippedFiles};
+
 pub use crate::rustfmt_diff::{ModifiedChunk, ModifiedLines};
 
 #[macro_use]
@@ -141,12 +147,54 @@ pub enum ErrorKind {
     /// Invalid glob pattern in `ignore` configuration option.
     #[error("Invalid glob pattern found in ignore list: {0}")]
     InvalidGlobPattern(ignore::Error),
+    /// Invalid glob pattern in `generated_paths` configuration option.
+    #[error("Invalid glob pattern found in generated paths: {0}")]
+    InvalidGeneratedPath(ignore::Error),
+    /// Invalid regular expression in `generated_markers` configuration option.
+    #[error("Invalid regular expression found in generated markers: {0}")]
+    InvalidGeneratedMarker(regex::Error),
+    /// Formatting the formatted code again changes it. Contains a diff of the
+    /// first pass against the second.
+    #[error("formatting is not idempotent, a second pass changes the output:\n{0}")]
+    NonIdempotent(String),
+    /// The formatted code doesn't have the same tokens or comments as the original, so it
+    /// wasn't written.
+    #[error("formatting changed the code, expected {expected} but found {found}")]
+    TokenMismatch { expected: String, found: String },
+    /// A `// rustfmt::off` or `// rustfmt::on` comment is not paired with another marker in
+    /// the same block, so it was ignored.
+    #[error(
+        "unbalanced marker, `// rustfmt::off` and `// rustfmt::on` must be paired \
+         within the same block"
+    )]
+    UnbalancedMarker,
 }
 
 impl ErrorKind {
     fn is_comment(&self) -> bool {
         matches!(self, ErrorKind::LostComment)
     }
+
+    /// A stable, machine-readable name for this kind of error.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ErrorKind::LineOverflow(..) => "line_overflow",
+            ErrorKind::TrailingWhitespace => "trailing_whitespace",
+            ErrorKind::DeprecatedAttr => "deprecated_attr",
+            ErrorKind::BadAttr => "bad_attr",
+            ErrorKind::IoError(_) => "io_error",
+            ErrorKind::ModuleResolutionError(_) => "module_resolution_error",
+            ErrorKind::ParseError => "parse_error",
+            ErrorKind::VersionMismatch => "version_mismatch",
+            ErrorKind::LostComment => "lost_comment",
+            ErrorKind::InvalidGlobPattern(_) => "invalid_glob_pattern",
+            ErrorKind::InvalidGeneratedPath(_) => "invalid_generated_path",
+            ErrorKind::InvalidGeneratedMarker(_) => "invalid_generated_marker",
+            ErrorKind::NonIdempotent(_) => "non_idempotent",
+            ErrorKind::TokenMismatch { .. } => "token_mismatch",
+            ErrorKind::UnbalancedMarker => "unbalanced_marker",
+        }
+    }
 }
 
 impl From<io::Error> for ErrorKind {
@@ -190,6 +238,10 @@ impl FormattedSnippet {
 pub struct FormatReport {
     // Maps stringified file paths to their associated formatting errors.
     internal: Rc<RefCell<(FormatErrorMap, ReportedErrors)>>,
+    // Counts of the files that were checked, reformatted or skipped.
+    summary: Rc<RefCell<FormatSummary>>,
+    // The regions of each file that were left as they are in the source.
+    unformatted_regions: Rc<RefCell<HashMap<FileName, Vec<UnformattedRegion>>>>,
     non_formatted_ranges: Vec<(usize, usize)>,
 }
 
@@ -197,10 +249,56 @@ impl FormatReport {
     fn new() -> FormatReport {
         FormatReport {
             internal: Rc::new(RefCell::new((HashMap::new(), ReportedErrors::default()))),
+            summary: Rc::new(RefCell::new(FormatSummary::default())),
+            unformatted_regions: Rc::new(RefCell::new(HashMap::new())),
             non_formatted_ranges: Vec::new(),
         }
     }
 
+    fn add_checked_file(&self, reformatted: bool) {
+        let mut summary = self.summary.borrow_mut();
+        summary.files_checked += 1;
+        if reformatted {
+            summary.files_reformatted += 1;
+        }
+    }
+
+    fn add_skipped_file(&self, reason: SkipReason) {
+        self.summary.borrow_mut().skipped.add(reason);
+    }
+
+    fn add_unformatted_region(&self, psess: &ParseSess, span: Span, reason: UnformattedReason) {
+        self.unformatted_regions
+            .borrow_mut()
+            .entry(psess.span_to_filename(span))
+            .or_default()
+            .push(UnformattedRegion::from_span(span, psess, reason));
+    }
+
+    /// Removes and returns the unformatted regions of `file`, ordered by line and with
+    /// overlapping regions left unformatted for the same reason merged.
+    fn take_unformatted_regions(&self, file: &FileName) -> Vec<UnformattedRegion> {
+        let mut regions = self
+            .unformatted_regions
+            .borrow_mut()
+            .remove(file)
+            .unwrap_or_default();
+        regions.sort_by_key(|region| region.lines);
+        let mut merged: Vec<UnformattedRegion> = Vec::with_capacity(regions.len());
+        for region in regions {
+            if let Some(last) = merged.last_mut() {
+                if let (Some((_, last_hi)), Some((lo, hi))) = (&mut last.lines, region.lines) {
+                    if last.reason == region.reason && lo <= *last_hi + 1 {
+                        *last_hi = hi.max(*last_hi);
+                        continue;
+                    }
+                }
+            }
+            merged.push(region);
+        }
+        merged
+    }
+
     fn add_non_formatted_ranges(&mut self, mut ranges: Vec<(usize, usize)>) {
         self.non_formatted_ranges.append(&mut ranges);
     }
@@ -226,7 +324,9 @@ impl FormatReport {
         }
         for err in new_errors {
             match err.kind {
-                ErrorKind::LineOverflow(..) => {
+                ErrorKind::LineOverflow(..)
+                | ErrorKind::NonIdempotent(_)
+                | ErrorKind::TokenMismatch { .. } => {
                     errs.has_operational_errors = true;
                 }
                 ErrorKind::TrailingWhitespace => {
@@ -236,7 +336,10 @@ impl FormatReport {
                 ErrorKind::LostComment => {
                     errs.has_unformatted_code_errors = true;
                 }
-                ErrorKind::DeprecatedAttr | ErrorKind::BadAttr | ErrorKind::VersionMismatch => {
+                ErrorKind::DeprecatedAttr
+                | ErrorKind::BadAttr
+                | ErrorKind::VersionMismatch
+                | ErrorKind::UnbalancedMarker => {
                     errs.has_check_errors = true;
                 }
                 _ => {}
@@ -270,6 +373,20 @@ impl FormatReport {
         self.internal.borrow().1.has_formatting_errors
     }
 
+    /// Counts of the files checked, reformatted and skipped, along with the
+    /// errors encountered, grouped by [`ErrorKind::name`].
+    pub fn summary(&self) -> FormatSummary {
+        let mut summary = self.summary.borrow().clone();
+        let internal = self.internal.borrow();
+        for error in internal.0.values().flatten() {
+            summary.add_error(&error.kind);
+        }
+        if internal.1.has_parsing_errors {
+            summary.add_error(&ErrorKind::ParseError);
+        }
+        summary
+    }
+
     /// Print the report to a terminal using colours and potentially other
     /// fancy output.
     #[deprecated(note = "Use FormatReportFormatter with colors enabled instead")]
@@ -308,6 +425,7 @@ fn format_snippet(snippet: &str, config: &Config, is_macro_def: bool) -> Option<
         config.set().emit_mode(config::EmitMode::Stdout);
         config.set().verbose(Verbosity::Quiet);
         config.set().show_parse_errors(false);
+        config.set().markdown(false);
         if is_macro_def {
             config.set().error_on_unformatted(true);
         }
@@ -467,6 +585,14 @@ impl<'b, T: Write + 'b> Session<'b, T> {
         self.format_input_inner(input, false)
     }
 
+    /// Returns whether the file at `path` would be left unformatted if it held `text`, because
+    /// it matches `ignore`, is generated, or is annotated with `#![rustfmt::skip]`. This allows
+    /// an `Input::Text` holding another version of a file, e.g. its staged version, to be
+    /// skipped like the file itself.
+    pub fn skips_file(&self, path: &Path, text: &str) -> Result<bool, ErrorKind> {
+        formatting::skips_file(&self.config, path, text)
+    }
+
     pub fn override_config<F, U>(&mut self, mut config: Config, f: F) -> U
     where
         F: FnOnce(&mut Session<'b, T>) -> U,
@@ -531,6 +657,7 @@ pub(crate) fn create_emitter<'a>(config: &Config) -> Box<dyn Emitter + 'a> {
         EmitMode::ModifiedLines => Box::new(emitter::ModifiedLinesEmitter::default()),
         EmitMode::Checkstyle => Box::new(emitter::CheckstyleEmitter::default()),
         EmitMode::Diff => Box::new(emitter::DiffEmitter::new(config.clone())),
+        EmitMode::UnformattedReport => Box::new(emitter::UnformattedReportEmitter::default()),
     }
 }
 
@@ -8,41 +8,122 @@
 // reformatted.
 // List-like invocations with parentheses will be formatted as function calls,
 // and those with brackets will be formatted as array literals.
+// Since style edition 2027, the bodies of invocations with braces are formatted
+// as items, statements or match arms when they parse as such.
 
 use std::collections::HashMap;
 use std::panic::{AssertUnwindSafe, catch_unwind};
 
 use rustc_ast::ast;
-use rustc_ast::token::{Delimiter, Token, TokenKind};
+use rustc_ast::token::{self, Delimiter, Token, TokenKind};
 use rustc_ast::tokenstream::{TokenStream, TokenStreamIter, TokenTree};
 use rustc_ast_pretty::pprust;
+use rustc_parse::parser::asm::{AsmArg, AsmArgKind};
 use rustc_span::{BytePos, DUMMY_SP, Ident, Span, Symbol};
 use tracing::debug;
 
+use crate::FormattedSnippet;
 use crate::comment::{
     CharClasses, FindUncommented, FullCodeCharKind, LineClasses, contains_comment,
 };
-use crate::config::StyleEdition;
 use crate::config::lists::*;
-use crate::expr::{RhsAssignKind, rewrite_array, rewrite_assign_rhs};
+use crate::config::{Config, MacroShape, StringSplitStyle, StyleEdition};
+use crate::expr::{RhsAssignKind, rewrite_array, rewrite_assign_rhs, rewrite_format_str};
+use crate::formatting::UnformattedReason;
 use crate::header::{HeaderPart, format_header};
+use crate::items::get_bytepos_after_visibility;
 use crate::lists::{ListFormatting, itemize_list, write_list};
+use crate::macros::inline_args::inline_format_args;
+use crate::matches::rewrite_macro_arms;
 use crate::overflow;
+use crate::parse::macros::asm::parse_asm;
 use crate::parse::macros::lazy_static::parse_lazy_static;
-use crate::parse::macros::{ParsedMacroArgs, parse_expr, parse_macro_args};
+use crate::parse::macros::{
+    ParsedMacroArgs, parse_expr, parse_macro_args, parse_macro_arms, parse_macro_block,
+    parse_macro_items,
+};
 use crate::rewrite::{
     MacroErrorKind, Rewrite, RewriteContext, RewriteError, RewriteErrorExt, RewriteResult,
 };
 use crate::shape::{Indent, Shape};
 use crate::source_map::SpanUtils;
 use crate::spanned::Spanned;
+use crate::types::{PathContext, rewrite_path};
 use crate::utils::{
-    NodeIdExt, filtered_str_fits, indent_next_line, is_empty_line, mk_sp,
+    NodeIdExt, filtered_str_fits, indent_next_line, is_empty_line, last_line_width, mk_sp,
     remove_trailing_white_spaces, rewrite_ident, trim_left_preserve_layout,
 };
 use crate::visitor::FmtVisitor;
 
-const FORCED_BRACKET_MACROS: &[&str] = &["vec!"];
+pub(crate) mod inline_args;
+
+/// The shapes of the invocations of well-known macros, unless `macro_shapes` says otherwise.
+///
+/// `format!`-like macros take a long format string and a list of arguments to format, after a
+/// number of other arguments: none for `format!("format", ...)`, one for `assert!(result,
+/// "format", ...)`, two for `assert_eq!(left, right, "format", ...)`.
+const BUILTIN_MACRO_SHAPES: &[(&str, MacroShape)] = &[
+    ("vec!", MacroShape::Array),
+    // format! like macros
+    // From the Rust Standard Library.
+    ("eprint!", MacroShape::Format(0)),
+    ("eprintln!", MacroShape::Format(0)),
+    ("format!", MacroShape::Format(0)),
+    ("format_args!", MacroShape::Format(0)),
+    ("print!", MacroShape::Format(0)),
+    ("println!", MacroShape::Format(0)),
+    ("panic!", MacroShape::Format(0)),
+    ("unreachable!", MacroShape::Format(0)),
+    // From the `log` crate.
+    ("debug!", MacroShape::Format(0)),
+    ("error!", MacroShape::Format(0)),
+    ("info!", MacroShape::Format(0)),
+    ("warn!", MacroShape::Format(0)),
+    // write! like macros
+    ("assert!", MacroShape::Format(1)),
+    ("debug_assert!", MacroShape::Format(1)),
+    ("write!", MacroShape::Format(1)),
+    ("writeln!", MacroShape::Format(1)),
+    // assert_eq! like macros
+    ("assert_eq!", MacroShape::Format(2)),
+    ("assert_ne!", MacroShape::Format(2)),
+    ("debug_assert_eq!", MacroShape::Format(2)),
+    ("debug_assert_ne!", MacroShape::Format(2)),
+];
+
+/// Additional built-in macro shapes for style edition 2024; these are separated to avoid
+/// breaking changes in earlier style editions.
+const BUILTIN_MACRO_SHAPES_2024: &[(&str, MacroShape)] = &[
+    // From the `log` crate.
+    ("trace!", MacroShape::Format(0)),
+];
+
+/// The macros of the `quote` and `syn` crates whose arguments are Rust code with `#`
+/// interpolations.
+const QUOTE_MACROS: &[&str] = &[
+    "quote!",
+    "quote::quote!",
+    "parse_quote!",
+    "syn::parse_quote!",
+];
+
+/// Returns the shape of the invocations of `macro_name`, e.g. `vec!`, as configured with
+/// `macro_shapes` or built into rustfmt.
+pub(crate) fn macro_shape(config: &Config, macro_name: &str) -> Option<MacroShape> {
+    let name = macro_name.strip_suffix('!').unwrap_or(macro_name);
+    config.macro_shapes().get(name).or_else(|| {
+        let edition_2024_shapes = if config.style_edition() >= StyleEdition::Edition2024 {
+            BUILTIN_MACRO_SHAPES_2024
+        } else {
+            &[]
+        };
+        BUILTIN_MACRO_SHAPES
+            .iter()
+            .chain(edition_2024_shapes)
+            .find(|(builtin_name, _)| *builtin_name == macro_name)
+            .map(|&(_, shape)| shape)
+    })
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) enum MacroPosition {
@@ -59,6 +140,9 @@ pub(crate) enum MacroArg {
     Pat(Box<ast::Pat>),
     Item(Box<ast::Item>),
     Keyword(Ident, Span),
+    /// The format string literal of a format-like macro, whose arguments may have been inlined
+    /// with `inline_format_args`.
+    FormatStr(String, Span),
 }
 
 impl MacroArg {
@@ -96,6 +180,7 @@ impl Rewrite for MacroArg {
             MacroArg::Pat(ref pat) => pat.rewrite_result(context, shape),
             MacroArg::Item(ref item) => item.rewrite_result(context, shape),
             MacroArg::Keyword(ident, _) => Ok(ident.name.to_string()),
+            MacroArg::FormatStr(ref lit, span) => rewrite_format_str(context, lit, span, shape),
         }
     }
 }
@@ -110,9 +195,20 @@ fn rewrite_macro_name(context: &RewriteContext<'_>, path: &ast::Path) -> String
     }
 }
 
+/// Whether `expr` is a string literal, normal or raw, without attributes.
+fn is_str_lit(expr: &ast::Expr) -> bool {
+    expr.attrs.is_empty()
+        && matches!(
+            expr.kind,
+            ast::ExprKind::Lit(token::Lit {
+                kind: token::LitKind::Str | token::LitKind::StrRaw(_),
+                suffix: None,
+                ..
+            })
+        )
+}
+
 // Use this on failing to format the macro call.
-// TODO(ding-young) We should also report macro parse failure to tell users why given snippet
-// is left unformatted. One possible improvement is appending formatting error to context.report
 fn return_macro_parse_failure_fallback(
     context: &RewriteContext<'_>,
     indent: Indent,
@@ -121,6 +217,11 @@ fn return_macro_parse_failure_fallback(
 ) -> RewriteResult {
     // Mark this as a failure however we format it
     context.macro_rewrite_failure.replace(true);
+    context.report.add_unformatted_region(
+        context.psess,
+        span,
+        UnformattedReason::MacroFailure(MacroErrorKind::ParseFailure),
+    );
 
     // Heuristically determine whether the last line of the macro uses "Block" style
     // rather than using "Visual" style, or another indentation style.
@@ -162,7 +263,9 @@ pub(crate) fn rewrite_macro(
     let should_skip = context
         .skip_context
         .macros
-        .skip(context.snippet(mac.path.span));
+        .skip(context.snippet(mac.path.span))
+        || macro_shape(context.config, &rewrite_macro_name(context, &mac.path))
+            == Some(MacroShape::Verbatim);
     if should_skip {
         Err(RewriteError::SkipFormatting)
     } else {
@@ -204,12 +307,19 @@ fn rewrite_macro_inner(
     let original_style = macro_style(mac, context);
 
     let macro_name = rewrite_macro_name(context, &mac.path);
-    let is_forced_bracket = FORCED_BRACKET_MACROS.contains(&&macro_name[..]);
-
-    let style = if is_forced_bracket && !is_nested_macro {
-        Delimiter::Bracket
-    } else {
-        original_style
+    let macro_shape = macro_shape(context.config, &macro_name);
+    let is_forced_bracket = macro_shape == Some(MacroShape::Array);
+
+    // A statement `foo! { .. }` needs no `;`, unlike `foo!(..)`, so its braces are kept unless
+    // a `;` follows.
+    let keeps_braces = position == MacroPosition::Statement
+        && original_style == Delimiter::Brace
+        && !is_followed_by_semicolon(context, mac.span());
+    let style = match macro_shape {
+        _ if keeps_braces => original_style,
+        Some(MacroShape::Array) if !is_nested_macro => Delimiter::Bracket,
+        Some(MacroShape::Call) if !is_nested_macro => Delimiter::Parenthesis,
+        _ => original_style,
     };
 
     let ts = mac.args.tokens.clone();
@@ -245,8 +355,78 @@ fn rewrite_macro_inner(
         }
     }
 
+    if QUOTE_MACROS.contains(&macro_name.as_str())
+        && macro_shape.is_none()
+        && context.config.style_edition() >= StyleEdition::Edition2027
+    {
+        match format_quote(context, shape, mac, &macro_name, style, position) {
+            Ok(rw) => return Ok(rw),
+            // Fall back to parsing the arguments like other macros.
+            Err(RewriteError::MacroFailure {
+                kind: MacroErrorKind::ParseFailure | MacroErrorKind::ReplaceMacroVariable,
+                span: _,
+            }) => {}
+            Err(err) => return Err(err),
+        }
+    }
+
+    // Format the macros declared to hold items or statements, falling back to the usual
+    // heuristics if they don't.
+    match macro_shape {
+        Some(MacroShape::Items) if !has_comment => {
+            if let Some(items) = parse_macro_items(context, ts.clone()) {
+                return rewrite_macro_with_items(
+                    context,
+                    &items,
+                    &macro_name,
+                    shape,
+                    style,
+                    original_style,
+                    position,
+                    mac.span(),
+                );
+            }
+        }
+        Some(MacroShape::Statements) if style == Delimiter::Brace => {
+            if let Some(block) = parse_macro_block(context, &mac.args) {
+                return rewrite_macro_with_block(context, &block, &macro_name, shape, mac.span());
+            }
+        }
+        None if style == Delimiter::Brace
+            && context.config.style_edition() >= StyleEdition::Edition2027 =>
+        {
+            match format_brace_macro_body(context, shape, mac, &macro_name, position, has_comment) {
+                Ok(rw) => return Ok(rw),
+                // Fall back to parsing the arguments like other macros.
+                Err(RewriteError::MacroFailure {
+                    kind: MacroErrorKind::ParseFailure,
+                    span: _,
+                }) => {}
+                Err(err) => return Err(err),
+            }
+        }
+        _ => {}
+    }
+
+    if let Some(asm_macro) = asm_macro_kind(&mac.path) {
+        if style == Delimiter::Parenthesis
+            && !has_comment
+            && context.config.style_edition() >= StyleEdition::Edition2027
+        {
+            match format_asm(context, shape, mac, asm_macro, &macro_name, position) {
+                Ok(rw) => return Ok(rw),
+                // Fall back to parsing the arguments like other macros.
+                Err(RewriteError::MacroFailure {
+                    kind: MacroErrorKind::ParseFailure,
+                    span: _,
+                }) => {}
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
     let ParsedMacroArgs {
-        args: arg_vec,
+        args: mut arg_vec,
         vec_with_semi,
         trailing_comma,
     } = match parse_macro_args(context, ts, style, is_forced_bracket) {
@@ -261,6 +441,37 @@ fn rewrite_macro_inner(
         }
     };
 
+    // The arguments moved into the format string are left out of the span of the list.
+    let mut args_span = mac.span();
+    if let Some(MacroShape::Format(format_str_index)) = macro_shape {
+        // The bodies of macro definitions and `quote!` are formatted with their variables
+        // replaced by identifiers, which must not end up inside the format string.
+        if context.config.inline_format_args()
+            && !context.is_macro_def
+            && style == Delimiter::Parenthesis
+            && !has_comment
+        {
+            if let Some((format_str, num_args)) =
+                inline_format_args(context, &arg_vec, format_str_index)
+            {
+                args_span = mk_sp(mac.span().lo(), arg_vec[num_args].span().lo());
+                arg_vec[format_str_index] =
+                    MacroArg::FormatStr(format_str, arg_vec[format_str_index].span());
+                arg_vec.truncate(num_args);
+            }
+        }
+        if context.config.format_strings()
+            && context.config.string_split_style() != StringSplitStyle::Backslash
+        {
+            if let Some(MacroArg::Expr(expr)) = arg_vec.get(format_str_index) {
+                if is_str_lit(expr) {
+                    arg_vec[format_str_index] =
+                        MacroArg::FormatStr(context.snippet(expr.span).to_owned(), expr.span);
+                }
+            }
+        }
+    }
+
     if !arg_vec.is_empty() && arg_vec.iter().all(MacroArg::is_item) {
         return rewrite_macro_with_items(
             context,
@@ -287,7 +498,7 @@ fn rewrite_macro_inner(
                     &macro_name,
                     arg_vec.iter(),
                     shape,
-                    mac.span(),
+                    args_span,
                     context.config.fn_call_width(),
                     if trailing_comma {
                         Some(SeparatorTactic::Always)
@@ -351,6 +562,57 @@ fn rewrite_macro_inner(
     }
 }
 
+/// Formats the body of a macro invoked with braces as items, then as statements, then as
+/// match arms, stopping at the first which parses.
+fn format_brace_macro_body(
+    context: &RewriteContext<'_>,
+    shape: Shape,
+    mac: &ast::MacCall,
+    macro_name: &str,
+    position: MacroPosition,
+    has_comment: bool,
+) -> RewriteResult {
+    // Comments after the last item would be lost, but a block keeps them.
+    if !has_comment {
+        if let Some(items) = parse_macro_items(context, mac.args.tokens.clone()) {
+            return rewrite_macro_with_items(
+                context,
+                &items,
+                macro_name,
+                shape,
+                Delimiter::Brace,
+                Delimiter::Brace,
+                position,
+                mac.span(),
+            );
+        }
+    }
+    if let Some(block) = parse_macro_block(context, &mac.args) {
+        return rewrite_macro_with_block(context, &block, macro_name, shape, mac.span());
+    }
+    if let Some(arms) = parse_macro_arms(context, &mac.args) {
+        let body = rewrite_macro_arms(context, &arms, shape, mac.args.dspan.entire())?;
+        return Ok(format!("{macro_name} {body}"));
+    }
+    Err(RewriteError::MacroFailure {
+        kind: MacroErrorKind::ParseFailure,
+        span: mac.span(),
+    })
+}
+
+fn rewrite_macro_with_block(
+    context: &RewriteContext<'_>,
+    block: &ast::Expr,
+    macro_name: &str,
+    shape: Shape,
+    span: Span,
+) -> RewriteResult {
+    // 1 = ` `
+    let block_shape = shape.offset_left(macro_name.len() + 1, span)?;
+    let block = block.rewrite_result(context, block_shape)?;
+    Ok(format!("{macro_name} {block}"))
+}
+
 fn handle_vec_semi(
     context: &RewriteContext<'_>,
     shape: Shape,
@@ -423,8 +685,14 @@ pub(crate) fn rewrite_macro_def(
         return snippet;
     }
 
+    // The arms of `macro_rules!` are separated by `;`, those of a `macro` by `,`.
+    let (separator, separator_kind) = if def.macro_rules {
+        (";", TokenKind::Semi)
+    } else {
+        (",", TokenKind::Comma)
+    };
     let ts = def.body.tokens.clone();
-    let mut parser = MacroParser::new(ts.iter());
+    let mut parser = MacroParser::new(ts.iter(), separator_kind);
     let parsed_def = match parser.parse() {
         Some(def) => def,
         None => return snippet,
@@ -434,7 +702,11 @@ pub(crate) fn rewrite_macro_def(
         let pos = context.snippet_provider.span_after(span, "!");
         vec![HeaderPart::new("macro_rules!", span.with_hi(pos))]
     } else {
-        let macro_lo = context.snippet_provider.span_before(span, "macro");
+        // Skip the visibility, which may contain `macro` in a path.
+        let macro_lo = context.snippet_provider.span_before(
+            span.with_lo(get_bytepos_after_visibility(vis, span)),
+            "macro",
+        );
         let macro_hi = macro_lo + BytePos("macro".len() as u32);
         vec![
             HeaderPart::visibility(context, vis),
@@ -446,7 +718,10 @@ pub(crate) fn rewrite_macro_def(
 
     let mut result = format_header(context, shape, header);
 
-    let multi_branch_style = def.macro_rules || parsed_def.branches.len() != 1;
+    // A `macro` with a single arm may be written as `macro name(...) { ... }`, in which case
+    // the parser leaves the opening delimiter of its body out of the source.
+    let is_braced = def.macro_rules || context.snippet(def.body.dspan.open) == "{";
+    let multi_branch_style = is_braced || parsed_def.branches.len() != 1;
 
     let arm_shape = if multi_branch_style {
         shape
@@ -467,27 +742,50 @@ pub(crate) fn rewrite_macro_def(
         context.snippet_provider,
         parsed_def.branches.iter(),
         "}",
-        ";",
+        separator,
         |branch| branch.span.lo(),
         |branch| branch.span.hi(),
         |branch| match branch.rewrite(context, arm_shape, multi_branch_style) {
             Ok(v) => Ok(v),
             // if the rewrite returned None because a macro could not be rewritten, then return the
             // original body
-            // TODO(ding-young) report rewrite error even if we return Ok with original snippet
-            Err(_) if context.macro_rewrite_failure.get() => {
+            Err(e) if context.macro_rewrite_failure.get() => {
+                let kind = match e {
+                    RewriteError::MacroFailure { kind, .. } => kind,
+                    _ => MacroErrorKind::Unknown,
+                };
+                context.report.add_unformatted_region(
+                    context.psess,
+                    branch.body,
+                    UnformattedReason::MacroFailure(kind),
+                );
                 Ok(context.snippet(branch.body).trim().to_string())
             }
             Err(e) => Err(e),
         },
-        context.snippet_provider.span_after(span, "{"),
+        if is_braced {
+            def.body.dspan.open.hi()
+        } else {
+            context.snippet_provider.span_after(span, "{")
+        },
         span.hi(),
         false,
     )
     .collect::<Vec<_>>();
 
+    // `macro_rules!` arms always get a `;`. The arms of a `macro` only get a `,` if they were
+    // separated in the source.
+    let separate_arms = def.macro_rules
+        || parsed_def
+            .branches
+            .iter()
+            .any(|branch| branch.has_separator);
     let fmt = ListFormatting::new(arm_shape, context.config)
-        .separator(if def.macro_rules { ";" } else { "" })
+        .separator(if multi_branch_style && separate_arms {
+            separator
+        } else {
+            ""
+        })
         .trailing_separator(SeparatorTactic::Always)
         .preserve_newline(true);
 
@@ -526,17 +824,24 @@ fn register_metavariable(
     map.insert(old_name, new_name);
 }
 
-// Replaces `$foo` with `zfoo`. We must check for name overlap to ensure we
-// aren't causing problems.
+// Replaces `$foo` with `zfoo`, and repetitions like `$($foo),*` with `z0`, or with `z0;` if
+// they look like statements, if `with_repetitions` is set. The repetitions are formatted on
+// their own when the names are put back. We must check for name overlap to ensure we aren't
+// causing problems.
 // This should also work for escaped `$` variables, where we leave earlier `$`s.
-fn replace_names(input: &str) -> Option<(String, HashMap<String, String>)> {
+fn replace_names(input: &str, with_repetitions: bool) -> Option<(String, HashMap<String, String>)> {
     // Each substitution will require five or six extra bytes.
     let mut result = String::with_capacity(input.len() + 64);
     let mut substs = HashMap::new();
     let mut dollar_count = 0;
     let mut cur_name = String::new();
+    let mut repetitions = 0;
+    let chars: Vec<_> = CharClasses::new(input.chars()).collect();
+    let mut i = 0;
 
-    for (kind, c) in CharClasses::new(input.chars()) {
+    while i < chars.len() {
+        let (kind, c) = chars[i];
+        i += 1;
         if kind != FullCodeCharKind::Normal {
             result.push(c);
         } else if c == '$' {
@@ -551,8 +856,29 @@ fn replace_names(input: &str) -> Option<(String, HashMap<String, String>)> {
             dollar_count = 0;
             cur_name.clear();
         } else if c == '(' && cur_name.is_empty() {
-            // FIXME: Support macro def with repeat.
-            return None;
+            if dollar_count != 1 || !with_repetitions {
+                return None;
+            }
+            let end = find_repetition_end(&chars, i - 1, &['*', '+', '?'])?;
+            let repetition: String = std::iter::once('$')
+                .chain(chars[i - 1..=end].iter().map(|&(_, c)| c))
+                .collect();
+            let is_stmt_like = chars[end - 1].1 == ')'
+                && matches!(
+                    repetition[2..repetition.len() - 2]
+                        .trim_end()
+                        .chars()
+                        .last(),
+                    Some(';' | '}')
+                );
+            let mut placeholder = format!("z{repetitions}");
+            if is_stmt_like {
+                placeholder.push(';');
+            }
+            register_interpolation(&mut substs, &mut result, repetition, placeholder);
+            repetitions += 1;
+            dollar_count = 0;
+            i = end + 1;
         } else if c.is_alphanumeric() || c == '_' {
             cur_name.push(c);
         }
@@ -567,6 +893,97 @@ fn replace_names(input: &str) -> Option<(String, HashMap<String, String>)> {
     Some((result, substs))
 }
 
+fn register_interpolation(
+    map: &mut HashMap<String, String>,
+    result: &mut String,
+    old_name: String,
+    new_name: String,
+) {
+    // The same interpolation may be repeated, keep its first name.
+    result.push_str(map.entry(old_name).or_insert(new_name));
+}
+
+// Returns the index of the repetition operator, one of `ops`, of the repetition whose opening
+// parenthesis is at `open`, e.g. the `*` of `($foo),*`.
+fn find_repetition_end(
+    chars: &[(FullCodeCharKind, char)],
+    open: usize,
+    ops: &[char],
+) -> Option<usize> {
+    let mut depth = 0;
+    let mut close = open;
+    loop {
+        match chars.get(close)? {
+            (FullCodeCharKind::Normal, '(') => depth += 1,
+            (FullCodeCharKind::Normal, ')') if depth == 1 => break,
+            (FullCodeCharKind::Normal, ')') => depth -= 1,
+            _ => {}
+        }
+        close += 1;
+    }
+    // The operator may follow a separator.
+    match (
+        chars.get(close + 1)?.1,
+        chars.get(close + 2).map(|&(_, c)| c),
+    ) {
+        (op, _) if ops.contains(&op) => Some(close + 1),
+        (sep, Some(op)) if !sep.is_whitespace() && ops.contains(&op) => Some(close + 2),
+        _ => None,
+    }
+}
+
+// Replaces the interpolations of `quote!`: `#foo` with `zfoo`, and repetitions like
+// `#(#foo),*` with `z0_______`, an identifier of the same length. The repetitions are not
+// formatted, so they must fit on a single line.
+fn replace_interpolations(input: &str) -> Option<(String, HashMap<String, String>)> {
+    let chars: Vec<_> = CharClasses::new(input.chars()).collect();
+    let mut result = String::with_capacity(input.len() + 64);
+    let mut substs = HashMap::new();
+    let mut repetitions = 0;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let (kind, c) = chars[i];
+        let next = chars.get(i + 1).map(|&(_, c)| c);
+        if kind != FullCodeCharKind::Normal || c != '#' {
+            result.push(c);
+            i += 1;
+        } else if next.map_or(false, |c| c.is_alphabetic() || c == '_') {
+            let len = chars[i + 1..]
+                .iter()
+                .take_while(|(_, c)| c.is_alphanumeric() || *c == '_')
+                .count();
+            let name: String = chars[i + 1..=i + len].iter().map(|&(_, c)| c).collect();
+            register_interpolation(
+                &mut substs,
+                &mut result,
+                format!("#{name}"),
+                format!("z{name}"),
+            );
+            i += len + 1;
+        } else if next == Some('(') {
+            let end = find_repetition_end(&chars, i + 1, &['*'])?;
+            let old_name: String = chars[i..=end].iter().map(|&(_, c)| c).collect();
+            if old_name.contains('\n') {
+                return None;
+            }
+            let mut new_name = format!("z{repetitions}");
+            let padding = old_name.chars().count().saturating_sub(new_name.len());
+            new_name.extend(std::iter::repeat('_').take(padding));
+            register_interpolation(&mut substs, &mut result, old_name, new_name);
+            repetitions += 1;
+            i = end + 1;
+        } else {
+            result.push(c);
+            i += 1;
+        }
+    }
+
+    debug!("replace_interpolations `{}` {:?}", result, substs);
+
+    Some((result, substs))
+}
+
 #[derive(Debug, Clone)]
 enum MacroArgKind {
     /// e.g., `$x: expr`.
@@ -1173,6 +1590,12 @@ pub(crate) fn convert_try_mac(
     }
 }
 
+/// Whether the code following `span` starts with `;`.
+fn is_followed_by_semicolon(context: &RewriteContext<'_>, span: Span) -> bool {
+    let rest = mk_sp(span.hi(), context.snippet_provider.end_pos());
+    context.snippet(rest).trim_start().starts_with(';')
+}
+
 pub(crate) fn macro_style(mac: &ast::MacCall, context: &RewriteContext<'_>) -> Delimiter {
     let snippet = context.snippet(mac.span());
     let paren_pos = snippet.find_uncommented("(").unwrap_or(usize::MAX);
@@ -1188,18 +1611,20 @@ pub(crate) fn macro_style(mac: &ast::MacCall, context: &RewriteContext<'_>) -> D
     }
 }
 
-// A very simple parser that just parses a macros 2.0 definition into its branches.
-// Currently we do not attempt to parse any further than that.
+// A very simple parser that just parses a `macro_rules!` or macros 2.0 definition into its
+// branches. Currently we do not attempt to parse any further than that.
 struct MacroParser<'a> {
     iter: TokenStreamIter<'a>,
+    // The token separating the branches, `;` for `macro_rules!` and `,` for macros 2.0.
+    separator: TokenKind,
 }
 
 impl<'a> MacroParser<'a> {
-    const fn new(iter: TokenStreamIter<'a>) -> Self {
-        Self { iter }
+    const fn new(iter: TokenStreamIter<'a>, separator: TokenKind) -> Self {
+        Self { iter, separator }
     }
 
-    // (`(` ... `)` `=>` `{` ... `}`)*
+    // (`(` ... `)` `=>` `{` ... `}` `;`?)*
     fn parse(&mut self) -> Option<Macro> {
         let mut branches = vec![];
         while self.iter.peek().is_some() {
@@ -1243,15 +1668,13 @@ impl<'a> MacroParser<'a> {
                 )
             }
         };
-        if let Some(TokenTree::Token(
-            Token {
-                kind: TokenKind::Semi,
-                span,
-            },
-            _,
-        )) = self.iter.peek()
-        {
+        let mut has_separator = false;
+        if let Some(TokenTree::Token(Token { kind, span }, _)) = self.iter.peek() {
+            if *kind != self.separator {
+                return None;
+            }
             hi = span.hi();
+            has_separator = true;
             self.iter.next();
         }
         Some(MacroBranch {
@@ -1260,11 +1683,12 @@ impl<'a> MacroParser<'a> {
             args,
             body,
             whole_body,
+            has_separator,
         })
     }
 }
 
-// A parsed macros 2.0 macro definition.
+// A parsed `macro_rules!` or macros 2.0 definition.
 struct Macro {
     branches: Vec<MacroBranch>,
 }
@@ -1277,6 +1701,8 @@ struct MacroBranch {
     args: TokenStream,
     body: Span,
     whole_body: Span,
+    // Whether the branch is followed by a separator in the source.
+    has_separator: bool,
 }
 
 impl MacroBranch {
@@ -1323,86 +1749,24 @@ impl MacroBranch {
         // AST nodes, but also has special variables (e.g, `$foo`) which can't be
         // parsed as regular Rust code (and note that these can be escaped using
         // `$$`). We'll try and format like an AST node, but we'll substitute
-        // variables for new names with the same length first.
-
-        let (body_str, substs) =
-            replace_names(old_body).macro_error(MacroErrorKind::ReplaceMacroVariable, self.span)?;
-
-        let mut config = context.config.clone();
-        config.set().show_parse_errors(false);
+        // variables for new names with the same length first, and repetitions
+        // (e.g, `$($foo),*`) for placeholders.
 
         result += " {";
 
         let body_indent = if has_block_body {
             shape.indent
         } else {
-            shape.indent.block_indent(&config)
-        };
-        let new_width = config.max_width() - body_indent.width();
-        config.set().max_width(new_width);
-
-        // First try to format as items, then as statements.
-        let new_body_snippet = match crate::format_snippet(&body_str, &config, true) {
-            Some(new_body) => new_body,
-            None => {
-                let new_width = new_width + config.tab_spaces();
-                config.set().max_width(new_width);
-                match crate::format_code_block(&body_str, &config, true) {
-                    Some(new_body) => new_body,
-                    None => {
-                        return Err(RewriteError::MacroFailure {
-                            kind: MacroErrorKind::Unknown,
-                            span: self.span,
-                        });
-                    }
-                }
-            }
+            shape.indent.block_indent(context.config)
         };
-
-        if !filtered_str_fits(&new_body_snippet.snippet, config.max_width(), shape) {
-            return Err(RewriteError::ExceedsMaxWidth {
-                configured_width: shape.width,
-                span: self.span,
-            });
-        }
-
-        // Indent the body since it is in a block.
-        let indent_str = body_indent.to_string(&config);
-        let mut new_body = LineClasses::new(new_body_snippet.snippet.trim_end())
-            .enumerate()
-            .fold(
-                (String::new(), true),
-                |(mut s, need_indent), (i, (kind, ref l))| {
-                    if !is_empty_line(l)
-                        && need_indent
-                        && !new_body_snippet.is_line_non_formatted(i + 1)
-                    {
-                        s += &indent_str;
-                    }
-                    (s + l + "\n", indent_next_line(kind, l, &config))
-                },
-            )
-            .0;
-
-        // Undo our replacement of macro variables.
-        // FIXME: this could be *much* more efficient.
-        for (old, new) in &substs {
-            if old_body.contains(new) {
-                debug!("rewrite_macro_def: bailing matching variable: `{}`", new);
-                return Err(RewriteError::MacroFailure {
-                    kind: MacroErrorKind::ReplaceMacroVariable,
-                    span: self.span,
-                });
-            }
-            new_body = new_body.replace(new, old);
-        }
+        let new_body = format_macro_body(context, old_body, body_indent, shape, self.span)?;
 
         if has_block_body {
             result += new_body.trim();
         } else if !new_body.is_empty() {
             result += "\n";
             result += &new_body;
-            result += &shape.indent.to_string(&config);
+            result += &shape.indent.to_string(context.config);
         }
 
         result += "}";
@@ -1411,6 +1775,273 @@ impl MacroBranch {
     }
 }
 
+/// Formats `body`, the transcriber of a macro branch or the contents of one of its
+/// repetitions, as items or statements indented with `body_indent`. Each line of the result
+/// ends with a newline.
+fn format_macro_body(
+    context: &RewriteContext<'_>,
+    body: &str,
+    body_indent: Indent,
+    shape: Shape,
+    span: Span,
+) -> RewriteResult {
+    let with_repetitions = context.config.style_edition() >= StyleEdition::Edition2027;
+    let (body_str, substs) = replace_names(body, with_repetitions)
+        .macro_error(MacroErrorKind::ReplaceMacroVariable, span)?;
+
+    let mut config = context.config.clone();
+    config.set().show_parse_errors(false);
+
+    let new_width = config.max_width() - body_indent.width();
+    config.set().max_width(new_width);
+
+    // First try to format as items, then as statements.
+    let new_body_snippet = match crate::format_snippet(&body_str, &config, true) {
+        Some(new_body) => new_body,
+        None => {
+            let new_width = new_width + config.tab_spaces();
+            config.set().max_width(new_width);
+            match crate::format_code_block(&body_str, &config, true) {
+                Some(new_body) => new_body,
+                None => {
+                    return Err(RewriteError::MacroFailure {
+                        kind: MacroErrorKind::Unknown,
+                        span,
+                    });
+                }
+            }
+        }
+    };
+
+    if !filtered_str_fits(&new_body_snippet.snippet, config.max_width(), shape) {
+        return Err(RewriteError::ExceedsMaxWidth {
+            configured_width: shape.width,
+            span,
+        });
+    }
+
+    // Indent the body since it is in a block.
+    let mut new_body = indent_formatted_snippet(&new_body_snippet, body_indent, &config);
+
+    // Undo our replacement of macro variables, longest names first so that none of them is
+    // replaced within another.
+    // FIXME: this could be *much* more efficient.
+    let mut substs: Vec<_> = substs.into_iter().collect();
+    substs.sort_by_key(|(_, new)| std::cmp::Reverse(new.len()));
+    let mut has_repetitions = false;
+    for (old, new) in &substs {
+        if body.contains(new.as_str()) {
+            debug!("rewrite_macro_def: bailing matching variable: `{}`", new);
+            return Err(RewriteError::MacroFailure {
+                kind: MacroErrorKind::ReplaceMacroVariable,
+                span,
+            });
+        }
+        new_body = if old.starts_with("$(") {
+            has_repetitions = true;
+            restore_repetition(context, &new_body, old, new, shape, span)?
+        } else {
+            new_body.replace(new.as_str(), old)
+        };
+    }
+
+    // The placeholders of repetitions are shorter than the repetitions themselves, so the
+    // restored body may no longer fit.
+    if has_repetitions
+        && !filtered_str_fits(
+            &new_body,
+            context.config.max_width(),
+            Shape::indented(Indent::empty(), context.config),
+        )
+    {
+        return Err(RewriteError::ExceedsMaxWidth {
+            configured_width: shape.width,
+            span,
+        });
+    }
+
+    Ok(new_body)
+}
+
+/// Puts `repetition` back in place of each `placeholder` in `body`, formatted at the
+/// indentation of the line of the placeholder.
+fn restore_repetition(
+    context: &RewriteContext<'_>,
+    body: &str,
+    repetition: &str,
+    placeholder: &str,
+    shape: Shape,
+    span: Span,
+) -> RewriteResult {
+    let mut result = String::with_capacity(body.len() + repetition.len());
+    let mut last = 0;
+    for (pos, _) in body.match_indices(placeholder) {
+        let line_start = body[..pos].rfind('\n').map_or(0, |i| i + 1);
+        let indent_width = body[line_start..pos]
+            .chars()
+            .take_while(|c| c.is_whitespace())
+            .map(|c| match c {
+                '\t' => context.config.tab_spaces(),
+                _ => 1,
+            })
+            .sum();
+        let indent = Indent::from_width(context.config, indent_width);
+        result.push_str(&body[last..pos]);
+        result.push_str(&rewrite_repetition(
+            context, repetition, indent, shape, span,
+        )?);
+        last = pos + placeholder.len();
+    }
+    result.push_str(&body[last..]);
+    Ok(result)
+}
+
+/// Formats the contents of `repetition`, e.g. `$(let $x = $e;)*`, keeping its separator and
+/// operator.
+fn rewrite_repetition(
+    context: &RewriteContext<'_>,
+    repetition: &str,
+    indent: Indent,
+    shape: Shape,
+    span: Span,
+) -> RewriteResult {
+    let close = repetition
+        .rfind(')')
+        .macro_error(MacroErrorKind::ReplaceMacroVariable, span)?;
+    let (contents, kleene) = (&repetition[2..close], &repetition[close + 1..]);
+    let contents_indent = indent.block_indent(context.config);
+    let contents = format_macro_body(context, contents.trim(), contents_indent, shape, span)
+        .map_err(|_| RewriteError::MacroFailure {
+            kind: MacroErrorKind::ReplaceMacroVariable,
+            span,
+        })?;
+    if !repetition.contains('\n') && !contents.trim().contains('\n') {
+        Ok(format!("$({}){kleene}", contents.trim()))
+    } else {
+        Ok(format!(
+            "$(\n{contents}{}){kleene}",
+            indent.to_string(context.config)
+        ))
+    }
+}
+
+/// Indents the lines of `formatted` which were formatted, each followed by a newline.
+fn indent_formatted_snippet(
+    formatted: &FormattedSnippet,
+    indent: Indent,
+    config: &Config,
+) -> String {
+    let indent_str = indent.to_string(config);
+    LineClasses::new(formatted.snippet.trim_end())
+        .enumerate()
+        .fold(
+            (String::new(), true),
+            |(mut s, need_indent), (i, (kind, ref l))| {
+                if !is_empty_line(l) && need_indent && !formatted.is_line_non_formatted(i + 1) {
+                    s += &indent_str;
+                }
+                (s + l + "\n", indent_next_line(kind, l, config))
+            },
+        )
+        .0
+}
+
+/// Format the arguments of `quote!` and `parse_quote!` from <https://crates.io/crates/quote>
+/// and <https://crates.io/crates/syn> as items, statements or a type.
+///
+/// The interpolations, `#var` and `#(...)*`, are replaced with identifiers while formatting, the
+/// way `macro_rules!` bodies are formatted.
+fn format_quote(
+    context: &RewriteContext<'_>,
+    shape: Shape,
+    mac: &ast::MacCall,
+    macro_name: &str,
+    style: Delimiter,
+    position: MacroPosition,
+) -> RewriteResult {
+    let span = mac.span();
+    let dspan = mac.args.dspan;
+    let old_body = context
+        .snippet(mk_sp(dspan.open.hi(), dspan.close.lo()))
+        .trim();
+    let (body_str, substs) =
+        replace_interpolations(old_body).macro_error(MacroErrorKind::ReplaceMacroVariable, span)?;
+    if substs.values().any(|new| old_body.contains(new.as_str())) {
+        return Err(RewriteError::MacroFailure {
+            kind: MacroErrorKind::ReplaceMacroVariable,
+            span,
+        });
+    }
+
+    let mut config = context.config.clone();
+    config.set().show_parse_errors(false);
+    let body_indent = shape.indent.block_indent(&config);
+    let new_width = config.max_width().saturating_sub(body_indent.width());
+    config.set().max_width(new_width);
+
+    // First try to format as items, then as statements, then as a type.
+    const TYPE_PREFIX: &str = "type zquote = ";
+    let new_body_snippet = crate::format_snippet(&body_str, &config, true)
+        .or_else(|| {
+            let mut config = config.clone();
+            let new_width = new_width + config.tab_spaces();
+            config.set().max_width(new_width);
+            crate::format_code_block(&body_str, &config, true)
+        })
+        .or_else(|| {
+            let mut ty =
+                crate::format_snippet(&format!("{TYPE_PREFIX}{body_str};"), &config, true)?;
+            ty.snippet = ty
+                .snippet
+                .trim_end()
+                .strip_prefix(TYPE_PREFIX)?
+                .strip_suffix(';')?
+                .to_owned();
+            Some(ty)
+        })
+        .macro_error(MacroErrorKind::ParseFailure, span)?;
+
+    if !filtered_str_fits(&new_body_snippet.snippet, config.max_width(), shape) {
+        return Err(RewriteError::ExceedsMaxWidth {
+            configured_width: shape.width,
+            span,
+        });
+    }
+
+    let mut new_body = indent_formatted_snippet(&new_body_snippet, body_indent, &config);
+    // Undo our replacement of the interpolations, longest first so that none of them is
+    // replaced within another.
+    let mut substs: Vec<_> = substs.into_iter().collect();
+    substs.sort_by_key(|(_, new)| std::cmp::Reverse(new.len()));
+    for (old, new) in &substs {
+        new_body = new_body.replace(new.as_str(), old);
+    }
+
+    let (opener, closer) = match style {
+        Delimiter::Parenthesis => ("(", ")"),
+        Delimiter::Bracket => ("[", "]"),
+        _ => (" {", "}"),
+    };
+    let semicolon = match style {
+        Delimiter::Parenthesis | Delimiter::Bracket if position == MacroPosition::Item => ";",
+        _ => "",
+    };
+    let one_line_body = new_body.trim();
+    if !one_line_body.contains('\n') {
+        let one_line = match style {
+            Delimiter::Brace => format!("{macro_name} {{ {one_line_body} }}"),
+            _ => format!("{macro_name}{opener}{one_line_body}{closer}{semicolon}"),
+        };
+        if one_line.len() <= shape.width {
+            return Ok(one_line);
+        }
+    }
+    Ok(format!(
+        "{macro_name}{opener}\n{new_body}{}{closer}{semicolon}",
+        shape.indent.to_string(context.config),
+    ))
+}
+
 /// Format `lazy_static!` and `lazy_static::lazy_static!`
 /// from <https://crates.io/crates/lazy_static>.
 ///
@@ -1479,6 +2110,197 @@ fn format_lazy_static(
     Ok(result)
 }
 
+/// Returns the kind of inline or global assembly macro called through `path`, if any.
+fn asm_macro_kind(path: &ast::Path) -> Option<ast::AsmMacro> {
+    match path.segments.last()?.ident.as_str() {
+        "asm" => Some(ast::AsmMacro::Asm),
+        "global_asm" => Some(ast::AsmMacro::GlobalAsm),
+        "naked_asm" => Some(ast::AsmMacro::NakedAsm),
+        _ => None,
+    }
+}
+
+/// Format `asm!`, `global_asm!` and `naked_asm!`. Invocations which don't fit on a single line,
+/// or whose arguments are wider than `fn_call_width`, get one template string or operand per
+/// line.
+///
+/// # Expected syntax
+///
+/// ```text
+/// asm!(
+///     TEMPLATE_1,
+///     ...
+///     TEMPLATE_N,
+///     [NAME =] OPERAND_1,
+///     ...
+///     [NAME =] OPERAND_N,
+///     clobber_abi(ABI, ...),
+///     options(OPTION, ...),
+/// )
+/// ```
+fn format_asm(
+    context: &RewriteContext<'_>,
+    shape: Shape,
+    mac: &ast::MacCall,
+    asm_macro: ast::AsmMacro,
+    macro_name: &str,
+    position: MacroPosition,
+) -> RewriteResult {
+    let span = mac.span();
+    let args =
+        parse_asm(context, mac, asm_macro).macro_error(MacroErrorKind::ParseFailure, span)?;
+    // Attributes on arguments are not supported yet.
+    if args.iter().any(|arg| !arg.attributes.is_empty()) {
+        return Err(RewriteError::MacroFailure {
+            kind: MacroErrorKind::ParseFailure,
+            span,
+        });
+    }
+    let semicolon = match position {
+        MacroPosition::Item => ";",
+        _ => "",
+    };
+
+    // 2 = `()`
+    let one_line_shape = shape.offset_left(macro_name.len() + 2 + semicolon.len(), span);
+    if let Ok(one_line_shape) = one_line_shape {
+        let one_line = args
+            .iter()
+            .map(|arg| rewrite_asm_arg(context, arg, one_line_shape))
+            .collect::<Result<Vec<_>, _>>()
+            .map(|args| args.join(", "));
+        let one_line_width = one_line_shape.width.min(context.config.fn_call_width());
+        if let Ok(one_line) = one_line {
+            if !one_line.contains('\n') && one_line.len() <= one_line_width {
+                return Ok(format!("{macro_name}({one_line}){semicolon}"));
+            }
+        }
+    }
+
+    let nested_shape = shape
+        .block_indent(context.config.tab_spaces())
+        .with_max_width(context.config);
+    // 1 = `,`
+    let arg_shape = nested_shape.sub_width(1, span)?;
+    let mut result = String::with_capacity(256);
+    result.push_str(macro_name);
+    result.push('(');
+    for (i, arg) in args.iter().enumerate() {
+        result.push_str(&nested_shape.indent.to_string_with_newline(context.config));
+        result.push_str(&rewrite_asm_arg(context, arg, arg_shape)?);
+        if i + 1 < args.len() || context.config.trailing_comma() != SeparatorTactic::Never {
+            result.push(',');
+        }
+    }
+    result.push_str(&shape.indent.to_string_with_newline(context.config));
+    result.push(')');
+    result.push_str(semicolon);
+
+    Ok(result)
+}
+
+fn rewrite_asm_arg(context: &RewriteContext<'_>, arg: &AsmArg, shape: Shape) -> RewriteResult {
+    match &arg.kind {
+        AsmArgKind::Template(expr) => expr.rewrite_result(context, shape),
+        AsmArgKind::Operand(name, operand) => {
+            let name = name.map_or(String::new(), |name| format!("{name} = "));
+            let operand_shape = shape.offset_left(name.len(), arg.span)?;
+            Ok(name + &rewrite_asm_operand(context, operand, operand_shape, arg.span)?)
+        }
+        // The arguments of `options(...)` and `clobber_abi(...)` are single tokens, only the
+        // spaces between them are normalized.
+        AsmArgKind::Options(_) | AsmArgKind::ClobberAbi(_) => {
+            let snippet = context.snippet(arg.span);
+            let (name, args) = snippet
+                .split_once('(')
+                .and_then(|(name, args)| Some((name.trim_end(), args.strip_suffix(')')?)))
+                .macro_error(MacroErrorKind::ParseFailure, arg.span)?;
+            let args = args
+                .split(',')
+                .map(str::trim)
+                .filter(|arg| !arg.is_empty())
+                .collect::<Vec<_>>()
+                .join(", ");
+            let result = format!("{name}({args})");
+            if result.len() > shape.width {
+                return Err(RewriteError::ExceedsMaxWidth {
+                    configured_width: shape.width,
+                    span: arg.span,
+                });
+            }
+            Ok(result)
+        }
+    }
+}
+
+fn rewrite_asm_operand(
+    context: &RewriteContext<'_>,
+    operand: &ast::InlineAsmOperand,
+    shape: Shape,
+    span: Span,
+) -> RewriteResult {
+    let reg = |reg: &ast::InlineAsmRegOrRegClass| match reg {
+        ast::InlineAsmRegOrRegClass::Reg(reg) => format!("\"{reg}\""),
+        ast::InlineAsmRegOrRegClass::RegClass(class) => class.to_string(),
+    };
+    let out_expr = |expr: Option<&ast::Expr>, shape: Shape| match expr {
+        Some(expr) => expr.rewrite_result(context, shape),
+        None => Ok(String::from("_")),
+    };
+    let with_prefix = |prefix: String, rewrite: &dyn Fn(Shape) -> RewriteResult| {
+        let shape = shape.offset_left(prefix.len(), span)?;
+        rewrite(shape).map(|rw| prefix + &rw)
+    };
+
+    match operand {
+        ast::InlineAsmOperand::In { reg: r, expr } => {
+            with_prefix(format!("in({}) ", reg(r)), &|shape| {
+                expr.rewrite_result(context, shape)
+            })
+        }
+        ast::InlineAsmOperand::Out { reg: r, late, expr } => {
+            let direction = if *late { "lateout" } else { "out" };
+            with_prefix(format!("{direction}({}) ", reg(r)), &|shape| {
+                out_expr(expr.as_deref(), shape)
+            })
+        }
+        ast::InlineAsmOperand::InOut { reg: r, late, expr } => {
+            let direction = if *late { "inlateout" } else { "inout" };
+            with_prefix(format!("{direction}({}) ", reg(r)), &|shape| {
+                expr.rewrite_result(context, shape)
+            })
+        }
+        ast::InlineAsmOperand::SplitInOut {
+            reg: r,
+            late,
+            in_expr,
+            out_expr: out,
+        } => {
+            let direction = if *late { "inlateout" } else { "inout" };
+            let in_expr = with_prefix(format!("{direction}({}) ", reg(r)), &|shape| {
+                in_expr.rewrite_result(context, shape)
+            })?;
+            // 4 = ` => `
+            let out_shape = shape.offset_left(last_line_width(&in_expr) + 4, span)?;
+            Ok(format!(
+                "{in_expr} => {}",
+                out_expr(out.as_deref(), out_shape)?
+            ))
+        }
+        ast::InlineAsmOperand::Const { anon_const } => {
+            with_prefix(String::from("const "), &|shape| {
+                anon_const.value.rewrite_result(context, shape)
+            })
+        }
+        ast::InlineAsmOperand::Sym { sym } => with_prefix(String::from("sym "), &|shape| {
+            rewrite_path(context, PathContext::Expr, &sym.qself, &sym.path, shape)
+        }),
+        ast::InlineAsmOperand::Label { block } => with_prefix(String::from("label "), &|shape| {
+            block.rewrite_result(context, shape)
+        }),
+    }
+}
+
 fn rewrite_macro_with_items(
     context: &RewriteContext<'_>,
     items: &[MacroArg],
@@ -0,0 +1,175 @@
+//! Inlining of the arguments of format strings for `inline_format_args`, e.g.
+//! `format!("{} {:?}", a, b)` into `format!("{a} {b:?}")`.
+
+use rustc_ast::{ast, token};
+
+use super::MacroArg;
+use crate::config::Edition;
+use crate::rewrite::RewriteContext;
+use crate::string::split_str_lit;
+
+/// Inlines the trailing arguments of a format-like macro which are plain identifiers into its
+/// format string, the argument at `format_str_index`. Only trailing arguments are inlined, since
+/// the inlined ones are evaluated after the others.
+///
+/// Returns the new format string and the number of arguments to keep, or `None` if no argument
+/// can be inlined.
+pub(super) fn inline_format_args(
+    context: &RewriteContext<'_>,
+    args: &[MacroArg],
+    format_str_index: usize,
+) -> Option<(String, usize)> {
+    let Some(MacroArg::Expr(format_str)) = args.get(format_str_index) else {
+        return None;
+    };
+    match format_str.kind {
+        ast::ExprKind::Lit(token::Lit {
+            kind: token::LitKind::Str | token::LitKind::StrRaw(_),
+            suffix: None,
+            ..
+        }) => {}
+        _ => return None,
+    }
+
+    let positional = &args[format_str_index + 1..];
+    let mut names = Vec::with_capacity(positional.len());
+    for arg in positional {
+        match arg {
+            // Named arguments could shadow the identifiers we inline.
+            MacroArg::Expr(expr) if matches!(expr.kind, ast::ExprKind::Assign(..)) => return None,
+            MacroArg::Expr(expr) => names.push(plain_ident(context, expr)),
+            _ => return None,
+        }
+    }
+    let num_inlined = names.iter().rev().take_while(|name| name.is_some()).count();
+    let num_kept = names.len() - num_inlined;
+    // Before the 2021 edition, `panic!("{x}")` doesn't format its only argument.
+    if num_inlined == 0 || (num_kept == 0 && context.config.edition() < Edition::Edition2021) {
+        return None;
+    }
+    names[..num_kept].fill(None);
+
+    let new_format_str = inline_args(context.snippet(format_str.span), &names)?;
+    Some((new_format_str, format_str_index + 1 + num_kept))
+}
+
+/// Returns the identifier `expr` consists of, unless it is a keyword or a raw identifier which
+/// can't be used in a format string.
+fn plain_ident<'a>(context: &'a RewriteContext<'_>, expr: &ast::Expr) -> Option<&'a str> {
+    match expr.kind {
+        ast::ExprKind::Path(None, ref path)
+            if expr.attrs.is_empty()
+                && path.segments.len() == 1
+                && path.segments[0].args.is_none()
+                && !path.segments[0].ident.is_reserved() =>
+        {
+            let snippet = context.snippet(expr.span);
+            (snippet == path.segments[0].ident.as_str()).then_some(snippet)
+        }
+        _ => None,
+    }
+}
+
+/// Puts the names in `args` into the implicit placeholders of the format string literal `lit`,
+/// e.g. `{}` or `{:?}`, one per positional argument. Placeholders whose argument is `None` are
+/// left as they are.
+///
+/// Returns `None` if the placeholders don't match the arguments, or refer to them by position
+/// or in their format specs, as inlining would shift these positions.
+pub(crate) fn inline_args(lit: &str, args: &[Option<&str>]) -> Option<String> {
+    let (opening, contents, closing) = split_str_lit(lit)?;
+    let is_raw = opening.starts_with('r');
+    let mut result = String::with_capacity(lit.len() + 16);
+    let mut next_arg = 0;
+    let mut chars = contents.char_indices().peekable();
+
+    result.push_str(opening);
+    while let Some((i, c)) = chars.next() {
+        let next = chars.peek().map(|&(_, c)| c);
+        match c {
+            '\\' if !is_raw => {
+                let (_, escaped) = chars.next()?;
+                // `\u{7b}` and `\x7b` are braces too.
+                if escaped == 'u' || escaped == 'x' {
+                    return None;
+                }
+                result.push(c);
+                result.push(escaped);
+            }
+            '{' | '}' if next == Some(c) => {
+                chars.next();
+                result.push(c);
+                result.push(c);
+            }
+            '{' => {
+                let placeholder = &contents[i + 1..i + 1 + contents[i + 1..].find('}')?];
+                chars.nth(placeholder.chars().count());
+                let (arg, spec) = match placeholder.split_once(':') {
+                    Some((arg, spec)) => (arg, Some(spec)),
+                    None => (placeholder, None),
+                };
+                if spec.map_or(false, |spec| spec.contains(['$', '*']))
+                    || arg.chars().next().map_or(false, |c| c.is_ascii_digit())
+                {
+                    return None;
+                }
+                result.push('{');
+                if arg.is_empty() {
+                    if let Some(name) = *args.get(next_arg)? {
+                        result.push_str(name);
+                    }
+                    next_arg += 1;
+                }
+                result.push_str(placeholder);
+                result.push('}');
+            }
+            '}' => return None,
+            _ => result.push(c),
+        }
+    }
+    result.push_str(closing);
+
+    (next_arg == args.len()).then_some(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn inlines_implicit_placeholders() {
+        assert_eq!(
+            inline_args(r#""{} {:?} {:>8.3}""#, &[Some("a"), Some("b"), Some("c")]),
+            Some(r#""{a} {b:?} {c:>8.3}""#.to_owned())
+        );
+        assert_eq!(
+            inline_args(r#""{}, {named} and {}""#, &[None, Some("b")]),
+            Some(r#""{}, {named} and {b}""#.to_owned())
+        );
+    }
+
+    #[test]
+    fn keeps_escapes_and_raw_strings() {
+        assert_eq!(
+            inline_args(r#""{{{}}} \"{}\"\n""#, &[Some("a"), Some("b")]),
+            Some(r#""{{{a}}} \"{b}\"\n""#.to_owned())
+        );
+        assert_eq!(
+            inline_args(r##"r#""{}""#"##, &[Some("a")]),
+            Some(r##"r#""{a}""#"##.to_owned())
+        );
+    }
+
+    #[test]
+    fn rejects_what_cannot_be_inlined() {
+        // Explicit positions, and positions or counts in specs.
+        assert_eq!(inline_args(r#""{0} {}""#, &[Some("a")]), None);
+        assert_eq!(inline_args(r#""{:1$}""#, &[Some("a"), None]), None);
+        assert_eq!(inline_args(r#""{:.*}""#, &[None, Some("a")]), None);
+        // Escaped braces.
+        assert_eq!(inline_args(r#""\u{7b}{}""#, &[Some("a")]), None);
+        // Mismatched arguments.
+        assert_eq!(inline_args(r#""{} {}""#, &[Some("a")]), None);
+        assert_eq!(inline_args(r#""{}""#, &[Some("a"), Some("b")]), None);
+    }
+}
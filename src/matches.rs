@@ -6,7 +6,10 @@ use rustc_ast::{MatchKind, ast};
 use rustc_span::{BytePos, Span};
 use tracing::debug;
 
-use crate::comment::{FindUncommented, combine_strs_with_missing_comments, rewrite_comment};
+use crate::comment::{
+    FindUncommented, combine_strs_with_missing_comments, contains_comment, rewrite_comment,
+    rewrite_missing_comment,
+};
 use crate::config::lists::*;
 use crate::config::{Config, ControlBraceStyle, IndentStyle, MatchArmLeadingPipe, StyleEdition};
 use crate::expr::{
@@ -31,14 +34,23 @@ struct ArmWrapper<'a> {
     is_last: bool,
     /// Holds a byte position of `|` at the beginning of the arm pattern, if available.
     beginning_vert: Option<BytePos>,
+    /// Whether the arm is followed by a comma in the source, if the commas must be kept as they
+    /// are, as in the body of a macro whose matcher may depend on them.
+    source_comma: Option<bool>,
 }
 
 impl<'a> ArmWrapper<'a> {
-    fn new(arm: &'a ast::Arm, is_last: bool, beginning_vert: Option<BytePos>) -> ArmWrapper<'a> {
+    fn new(
+        arm: &'a ast::Arm,
+        is_last: bool,
+        beginning_vert: Option<BytePos>,
+        source_comma: Option<bool>,
+    ) -> ArmWrapper<'a> {
         ArmWrapper {
             arm,
             is_last,
             beginning_vert,
+            source_comma,
         }
     }
 }
@@ -60,13 +72,20 @@ impl<'a> Rewrite for ArmWrapper<'a> {
     }
 
     fn rewrite_result(&self, context: &RewriteContext<'_>, shape: Shape) -> RewriteResult {
-        rewrite_match_arm(
+        let rewrite = rewrite_match_arm(
             context,
             self.arm,
             shape,
             self.is_last,
             self.beginning_vert.is_some(),
-        )
+        )?;
+        match self.source_comma {
+            Some(has_comma) => {
+                let rewrite = rewrite.strip_suffix(',').unwrap_or(&rewrite);
+                Ok(format!("{rewrite}{}", if has_comma { "," } else { "" }))
+            }
+            None => Ok(rewrite),
+        }
     }
 }
 
@@ -155,7 +174,7 @@ pub(crate) fn rewrite_match(
                 block_sep,
                 inner_attrs_str,
                 nested_indent_str,
-                rewrite_match_arms(context, arms, shape, span_after_cond, open_brace_pos)?,
+                rewrite_match_arms(context, arms, shape, span_after_cond, open_brace_pos, false)?,
                 shape.indent.to_string(context.config),
             )),
             MatchKind::Postfix => Ok(format!(
@@ -164,13 +183,38 @@ pub(crate) fn rewrite_match(
                 block_sep,
                 inner_attrs_str,
                 nested_indent_str,
-                rewrite_match_arms(context, arms, shape, span_after_cond, open_brace_pos)?,
+                rewrite_match_arms(context, arms, shape, span_after_cond, open_brace_pos, false)?,
                 shape.indent.to_string(context.config),
             )),
         }
     }
 }
 
+/// Rewrites the body of a macro invoked with braces whose arguments parse as match arms.
+/// `span` covers the braces.
+///
+/// Unlike in a `match`, the commas after the arms are kept as they are, and the blocks of the
+/// arm bodies aren't flattened, since the macro sees their tokens.
+pub(crate) fn rewrite_macro_arms(
+    context: &RewriteContext<'_>,
+    arms: &[ast::Arm],
+    shape: Shape,
+    span: Span,
+) -> RewriteResult {
+    let nested_indent = if context.config.match_arm_indent() {
+        shape.indent.block_indent(context.config)
+    } else {
+        shape.indent
+    };
+    let open_brace_pos = span.lo() + BytePos(1);
+    Ok(format!(
+        "{{\n{}{}\n{}}}",
+        nested_indent.to_string(context.config),
+        rewrite_match_arms(context, arms, shape, span, open_brace_pos, true)?,
+        shape.indent.to_string(context.config),
+    ))
+}
+
 fn arm_comma(config: &Config, body: &ast::Expr, is_last: bool) -> &'static str {
     if is_last && config.trailing_comma() == SeparatorTactic::Never {
         ""
@@ -208,6 +252,7 @@ fn rewrite_match_arms(
     shape: Shape,
     span: Span,
     open_brace_pos: BytePos,
+    keep_commas: bool,
 ) -> RewriteResult {
     let arm_shape = if context.config.match_arm_indent() {
         shape.block_indent(context.config.tab_spaces())
@@ -221,12 +266,24 @@ fn rewrite_match_arms(
         .take(arm_len.saturating_sub(1))
         .chain(repeat(true));
     let beginning_verts = collect_beginning_verts(context, arms);
+    let source_commas = arms.iter().enumerate().map(|(i, arm)| {
+        keep_commas.then(|| {
+            let next_lo = arms.get(i + 1).map_or(span.hi(), |next| next.span().lo());
+            context
+                .snippet_provider
+                .opt_span_after(mk_sp(arm.span().hi(), next_lo), ",")
+                .is_some()
+        })
+    });
     let items = itemize_list(
         context.snippet_provider,
         arms.iter()
             .zip(is_last_iter)
             .zip(beginning_verts.into_iter())
-            .map(|((arm, is_last), beginning_vert)| ArmWrapper::new(arm, is_last, beginning_vert)),
+            .zip(source_commas)
+            .map(|(((arm, is_last), beginning_vert), source_comma)| {
+                ArmWrapper::new(arm, is_last, beginning_vert, source_comma)
+            }),
         "}",
         "|",
         |arm| arm.span().lo(),
@@ -304,21 +361,41 @@ fn rewrite_match_arm(
         shape,
         trimmed_last_line_width(&pats_str),
         new_line_guard,
+        arm.pat.span.hi(),
     )?;
 
+    // Comments between the pattern or the guard and `=>`.
+    let arrow_lo = arm
+        .guard
+        .as_ref()
+        .map_or(arm.pat.span.hi(), |guard| guard.cond.span.hi());
+    let arrow_span = mk_sp(arrow_lo, arm.body.as_ref().unknown_error()?.span().lo());
+    let arrow_pos = context.snippet_provider.span_before(arrow_span, "=>");
+    let pre_arrow_comment = rewrite_missing_comment(mk_sp(arrow_lo, arrow_pos), shape, context)?;
+    let pre_arrow_comment = if pre_arrow_comment.is_empty() {
+        pre_arrow_comment
+    } else if pre_arrow_comment.starts_with("//") || pre_arrow_comment.contains('\n') {
+        // `=>` goes on the next line after a line comment.
+        format!(
+            " {pre_arrow_comment}{}",
+            shape
+                .indent
+                .block_indent(context.config)
+                .to_string_with_newline(context.config)
+        )
+    } else {
+        format!(" {pre_arrow_comment}")
+    };
+
     let lhs_str = combine_strs_with_missing_comments(
         context,
         &attrs_str,
-        &format!("{pipe_str}{pats_str}{guard_str}"),
+        &format!("{pipe_str}{pats_str}{guard_str}{pre_arrow_comment}"),
         missing_span,
         shape,
         false,
     )?;
 
-    let arrow_span = mk_sp(
-        arm.pat.span.hi(),
-        arm.body.as_ref().unknown_error()?.span().lo(),
-    );
     rewrite_match_body(
         context,
         arm.body.as_ref().unknown_error()?,
@@ -416,6 +493,12 @@ fn rewrite_match_body(
 
     let comma = arm_comma(context.config, body, is_last);
     let alt_block_sep = &shape.indent.to_string_with_newline(context.config);
+    // `pats_str` ends with the indentation of `=>` if a comment pushed it to the next line.
+    let arrow = if trimmed_last_line_width(pats_str) == 0 {
+        "=>"
+    } else {
+        " =>"
+    };
 
     let combine_orig_body = |body_str: &str| {
         let block_sep = match context.config.control_brace_style() {
@@ -423,7 +506,7 @@ fn rewrite_match_body(
             _ => " ",
         };
 
-        Ok(format!("{} =>{}{}{}", pats_str, block_sep, body_str, comma))
+        Ok(format!("{pats_str}{arrow}{block_sep}{body_str}{comma}"))
     };
 
     let next_line_indent = if !is_block || is_empty_block {
@@ -459,7 +542,7 @@ fn rewrite_match_body(
 
         if is_block {
             let mut result = pats_str.to_owned();
-            result.push_str(" =>");
+            result.push_str(arrow);
             if !arrow_comment.is_empty() {
                 result.push_str(&nested_indent_str);
                 result.push_str(&arrow_comment);
@@ -502,7 +585,7 @@ fn rewrite_match_body(
         } + &nested_indent_str;
 
         let mut result = pats_str.to_owned();
-        result.push_str(" =>");
+        result.push_str(arrow);
         if !arrow_comment.is_empty() {
             result.push_str(&indent_str);
             result.push_str(&arrow_comment);
@@ -577,32 +660,57 @@ fn rewrite_guard(
     // the arm (excludes offset).
     pattern_width: usize,
     multiline_pattern: bool,
+    pat_hi: BytePos,
 ) -> RewriteResult {
     if let Some(ref guard) = *guard {
+        let (comment, keyword) = rewrite_guard_keyword(context, guard, pat_hi, shape)?;
+        let comment = if comment.is_empty() {
+            comment
+        } else {
+            format!(" {comment}")
+        };
+        // A line comment after the pattern pushes the guard to the next line.
+        let multiline_pattern =
+            multiline_pattern || comment.starts_with(" //") || comment.contains('\n');
+        let pattern_width = pattern_width + comment.len();
+        // A line comment after `if` pushes the condition to the next line.
+        let cond_indent = shape.indent.block_indent(context.config);
+        let keyword_width = if keyword.contains('\n') {
+            0
+        } else {
+            keyword.len()
+        };
+
         // First try to fit the guard string on the same line as the pattern.
-        // 4 = ` if `, 5 = ` => {`
-        let cond_shape = shape
-            .offset_left_opt(pattern_width + 4)
-            .and_then(|s| s.sub_width_opt(5));
+        // 1 = ` `, 5 = ` => {`
+        let cond_shape = if keyword.contains('\n') {
+            Shape::indented(cond_indent, context.config).sub_width_opt(5)
+        } else {
+            shape
+                .offset_left_opt(pattern_width + 1 + keyword.len())
+                .and_then(|s| s.sub_width_opt(5))
+        };
         if !multiline_pattern {
             if let Some(cond_shape) = cond_shape {
                 if let Ok(cond_str) = guard.cond.rewrite_result(context, cond_shape) {
                     if !cond_str.contains('\n') || pattern_width <= context.config.tab_spaces() {
-                        return Ok(format!(" if {cond_str}"));
+                        return Ok(format!("{comment} {keyword}{cond_str}"));
                     }
                 }
             }
         }
 
         // Not enough space to put the guard after the pattern, try a newline.
-        // 3 = `if `, 5 = ` => {`
-        let cond_shape = Shape::indented(shape.indent.block_indent(context.config), context.config)
-            .offset_left(3, guard.span())?
+        // 5 = ` => {`
+        let cond_shape = Shape::indented(cond_indent, context.config)
+            .offset_left(keyword_width, guard.span())?
             .sub_width(5, guard.span())?;
         let cond_str = guard.cond.rewrite_result(context, cond_shape)?;
         Ok(format!(
-            "{}if {}",
+            "{}{}{}{}",
+            comment,
             cond_shape.indent.to_string_with_newline(context.config),
+            keyword,
             cond_str
         ))
     } else {
@@ -610,6 +718,36 @@ fn rewrite_guard(
     }
 }
 
+/// Returns the comment between the pattern of an arm and the `if` of its guard, and the `if`
+/// keyword with the comment following it.
+fn rewrite_guard_keyword(
+    context: &RewriteContext<'_>,
+    guard: &ast::Guard,
+    pat_hi: BytePos,
+    shape: Shape,
+) -> Result<(String, String), RewriteError> {
+    let span = mk_sp(pat_hi, guard.cond.span.lo());
+    if !contains_comment(context.snippet(span)) {
+        return Ok((String::new(), "if ".to_owned()));
+    }
+    let if_hi = context.snippet_provider.span_after_last(span, "if");
+    // 2 = `if`
+    let before = rewrite_missing_comment(mk_sp(pat_hi, if_hi - BytePos(2)), shape, context)?;
+    let after = rewrite_missing_comment(mk_sp(if_hi, span.hi()), shape, context)?;
+    let keyword = if after.is_empty() {
+        "if ".to_owned()
+    } else if after.starts_with("//") || after.contains('\n') {
+        let cond_indent = shape.indent.block_indent(context.config);
+        format!(
+            "if {after}{}",
+            cond_indent.to_string_with_newline(context.config)
+        )
+    } else {
+        format!("if {after} ")
+    };
+    Ok((before, keyword))
+}
+
 fn nop_block_collapse(block_str: RewriteResult, budget: usize) -> RewriteResult {
     debug!("nop_block_collapse {:?} {}", block_str, budget);
     block_str.map(|block_str| {
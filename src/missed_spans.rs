@@ -98,6 +98,20 @@ impl<'a> FmtVisitor<'a> {
             self.psess.span_to_debug_info(mk_sp(start, end)),
         );
 
+        // Copy the regions disabled with `// rustfmt::off` as they are.
+        if let Some(region) = self.skip_context.off_regions.overlapping(mk_sp(start, end)) {
+            if start < region.lo() {
+                self.format_missing_with_indent(region.lo());
+            }
+            let hi = region.hi().min(end);
+            self.push_str(self.snippet(mk_sp(self.last_pos, hi)));
+            self.last_pos = hi;
+            if hi < end {
+                self.format_missing_inner(end, process_last_snippet);
+            }
+            return;
+        }
+
         self.last_pos = end;
         let span = mk_sp(start, end);
         let snippet = self.snippet(span);
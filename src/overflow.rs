@@ -8,9 +8,10 @@ use rustc_ast::token::Delimiter;
 use rustc_span::Span;
 use tracing::debug;
 
+use crate::attr::AttrArg;
 use crate::closures;
 use crate::config::StyleEdition;
-use crate::config::{Config, lists::*};
+use crate::config::{Config, MacroShape, lists::*};
 use crate::expr::{
     can_be_overflowed_expr, is_every_expr_simple, is_method_call, is_nested_call, is_simple_expr,
     rewrite_cond,
@@ -18,7 +19,7 @@ use crate::expr::{
 use crate::lists::{
     ListFormatting, ListItem, Separator, definitive_tactic, itemize_list, write_list,
 };
-use crate::macros::MacroArg;
+use crate::macros::{MacroArg, macro_shape};
 use crate::patterns::{TuplePatField, can_be_overflowed_pat};
 use crate::rewrite::{Rewrite, RewriteContext, RewriteError, RewriteErrorExt, RewriteResult};
 use crate::shape::Shape;
@@ -27,47 +28,9 @@ use crate::spanned::Spanned;
 use crate::types::{SegmentParam, can_be_overflowed_type};
 use crate::utils::{count_newlines, extra_offset, first_line_width, last_line_width, mk_sp};
 
-/// A list of `format!`-like macros, that take a long format string and a list of arguments to
-/// format.
-///
-/// Organized as a list of `(&str, usize)` tuples, giving the name of the macro and the number of
-/// arguments before the format string (none for `format!("format", ...)`, one for `assert!(result,
-/// "format", ...)`, two for `assert_eq!(left, right, "format", ...)`).
-const SPECIAL_CASE_MACROS: &[(&str, usize)] = &[
-    // format! like macros
-    // From the Rust Standard Library.
-    ("eprint!", 0),
-    ("eprintln!", 0),
-    ("format!", 0),
-    ("format_args!", 0),
-    ("print!", 0),
-    ("println!", 0),
-    ("panic!", 0),
-    ("unreachable!", 0),
-    // From the `log` crate.
-    ("debug!", 0),
-    ("error!", 0),
-    ("info!", 0),
-    ("warn!", 0),
-    // write! like macros
-    ("assert!", 1),
-    ("debug_assert!", 1),
-    ("write!", 1),
-    ("writeln!", 1),
-    // assert_eq! like macros
-    ("assert_eq!", 2),
-    ("assert_ne!", 2),
-    ("debug_assert_eq!", 2),
-    ("debug_assert_ne!", 2),
-];
-
-/// Additional special case macros for version 2; these are separated to avoid breaking changes in
-/// version 1.
-const SPECIAL_CASE_MACROS_V2: &[(&str, usize)] = &[
-    // From the `log` crate.
-    ("trace!", 0),
-];
-
+/// A list of `format!`-like attributes, given as `(&str, usize)` tuples of the name of the
+/// attribute and the number of arguments before the format string. The `format!`-like macros are
+/// found with `macro_shape`.
 const SPECIAL_CASE_ATTR: &[(&str, usize)] = &[
     // From the `failure` crate.
     ("fail", 0),
@@ -79,6 +42,7 @@ pub(crate) enum OverflowableItem<'a> {
     GenericParam(&'a ast::GenericParam),
     MacroArg(&'a MacroArg),
     MetaItemInner(&'a ast::MetaItemInner),
+    AttrArg(&'a AttrArg),
     SegmentParam(&'a SegmentParam<'a>),
     FieldDef(&'a ast::FieldDef),
     TuplePatField(&'a TuplePatField<'a>),
@@ -124,6 +88,7 @@ impl<'a> OverflowableItem<'a> {
             OverflowableItem::GenericParam(gp) => f(*gp),
             OverflowableItem::MacroArg(macro_arg) => f(*macro_arg),
             OverflowableItem::MetaItemInner(nmi) => f(*nmi),
+            OverflowableItem::AttrArg(arg) => f(*arg),
             OverflowableItem::SegmentParam(sp) => f(*sp),
             OverflowableItem::FieldDef(sf) => f(*sf),
             OverflowableItem::TuplePatField(pat) => f(*pat),
@@ -136,7 +101,7 @@ impl<'a> OverflowableItem<'a> {
     pub(crate) fn is_simple(&self) -> bool {
         match self {
             OverflowableItem::Expr(expr) => is_simple_expr(expr),
-            OverflowableItem::MacroArg(MacroArg::Keyword(..)) => true,
+            OverflowableItem::MacroArg(MacroArg::Keyword(..) | MacroArg::FormatStr(..)) => true,
             OverflowableItem::MacroArg(MacroArg::Expr(expr)) => is_simple_expr(expr),
             OverflowableItem::MetaItemInner(meta_item_inner) => match meta_item_inner {
                 ast::MetaItemInner::Lit(..) => true,
@@ -144,6 +109,7 @@ impl<'a> OverflowableItem<'a> {
                     matches!(meta_item.kind, ast::MetaItemKind::Word)
                 }
             },
+            OverflowableItem::AttrArg(AttrArg::Value(_, expr, _)) => is_simple_expr(expr),
             // FIXME: Why don't we consider `SegmentParam` to be simple?
             // FIXME: If we also fix `SegmentParam`, then we should apply the same
             // heuristic to `PreciseCapturingArg`.
@@ -182,12 +148,17 @@ impl<'a> OverflowableItem<'a> {
                 MacroArg::Ty(ref ty) => can_be_overflowed_type(context, ty, len),
                 MacroArg::Pat(..) => false,
                 MacroArg::Item(..) => len == 1,
-                MacroArg::Keyword(..) => false,
+                MacroArg::Keyword(..) | MacroArg::FormatStr(..) => false,
             },
             OverflowableItem::MetaItemInner(meta_item_inner) if len == 1 => match meta_item_inner {
                 ast::MetaItemInner::Lit(..) => false,
                 ast::MetaItemInner::MetaItem(..) => true,
             },
+            OverflowableItem::AttrArg(attr_arg) if len == 1 => match attr_arg {
+                AttrArg::List(..) => true,
+                AttrArg::KeyValue(..) => false,
+                AttrArg::Value(_, expr, _) => can_be_overflowed_expr(context, expr, len),
+            },
             OverflowableItem::SegmentParam(SegmentParam::Type(ty)) => {
                 can_be_overflowed_type(context, ty, len)
             }
@@ -197,21 +168,14 @@ impl<'a> OverflowableItem<'a> {
         }
     }
 
-    fn special_cases(&self, config: &Config) -> impl Iterator<Item = &(&'static str, usize)> {
-        let base_cases = match self {
-            OverflowableItem::MacroArg(..) => SPECIAL_CASE_MACROS,
-            OverflowableItem::MetaItemInner(..) => SPECIAL_CASE_ATTR,
-            _ => &[],
-        };
-        let additional_cases = match self {
-            OverflowableItem::MacroArg(..)
-                if config.style_edition() >= StyleEdition::Edition2024 =>
-            {
-                SPECIAL_CASE_MACROS_V2
+    fn special_cases(&self) -> impl Iterator<Item = &(&'static str, usize)> {
+        let cases = match self {
+            OverflowableItem::MetaItemInner(..) | OverflowableItem::AttrArg(..) => {
+                SPECIAL_CASE_ATTR
             }
             _ => &[],
         };
-        base_cases.iter().chain(additional_cases)
+        cases.iter()
     }
 }
 
@@ -265,7 +229,7 @@ impl_into_overflowable_item_for_ast_node!(
     Pat,
     PreciseCapturingArg
 );
-impl_into_overflowable_item_for_rustfmt_types!([MacroArg], [SegmentParam, TuplePatField]);
+impl_into_overflowable_item_for_rustfmt_types!([MacroArg, AttrArg], [SegmentParam, TuplePatField]);
 
 pub(crate) fn into_overflowable_list<'a, T>(
     iter: impl Iterator<Item = &'a T>,
@@ -807,11 +771,18 @@ pub(crate) fn maybe_get_args_offset(
     args: &[OverflowableItem<'_>],
     config: &Config,
 ) -> Option<(bool, usize)> {
-    if let Some(&(_, num_args_before)) = args
-        .get(0)?
-        .special_cases(config)
-        .find(|&&(s, _)| s == callee_str)
-    {
+    let num_args_before = match args.get(0)? {
+        // The format-like macros are configured with `macro_shapes`.
+        OverflowableItem::MacroArg(..) => match macro_shape(config, callee_str) {
+            Some(MacroShape::Format(num_args_before)) => Some(num_args_before),
+            _ => None,
+        },
+        item => item
+            .special_cases()
+            .find(|&&(s, _)| s == callee_str)
+            .map(|&(_, num_args_before)| num_args_before),
+    };
+    if let Some(num_args_before) = num_args_before {
         let all_simple = args.len() > num_args_before
             && is_every_expr_simple(&args[0..num_args_before])
             && is_every_expr_simple(&args[num_args_before + 1..]);
@@ -1,13 +1,14 @@
 use rustc_ast::{ast, token};
-use rustc_span::Span;
+use rustc_span::{BytePos, Span};
 
+use crate::comment::rewrite_missing_comment;
 use crate::config::IndentStyle;
 use crate::config::lists::*;
-use crate::rewrite::{Rewrite, RewriteContext, RewriteErrorExt, RewriteResult};
+use crate::rewrite::{Rewrite, RewriteContext, RewriteError, RewriteErrorExt, RewriteResult};
 use crate::shape::Shape;
 use crate::spanned::Spanned;
 use crate::utils::{
-    first_line_width, is_single_line, last_line_width, trimmed_last_line_width, wrap_str,
+    first_line_width, is_single_line, last_line_width, mk_sp, trimmed_last_line_width, wrap_str,
 };
 
 /// Sigils that decorate a binop pair.
@@ -69,16 +70,32 @@ fn rewrite_pairs_one_line<T: Rewrite>(
     let mut result = String::new();
     let base_shape = shape.block();
 
-    for ((_, rewrite), s) in list.list.iter().zip(list.separators.iter()) {
+    for (i, ((_, rewrite), s)) in list.list.iter().zip(list.separators.iter()).enumerate() {
         if let Ok(rewrite) = rewrite {
             if !is_single_line(rewrite) || result.len() > shape.width {
                 return None;
             }
+            let (pre_comment, post_comment) = list.separator_comments(i, context, shape).ok()?;
+            // Only block comments fitting on the line can be kept on it.
+            if [&pre_comment, &post_comment]
+                .iter()
+                .any(|comment| comment.starts_with("//") || !is_single_line(comment))
+            {
+                return None;
+            }
 
             result.push_str(rewrite);
+            if !pre_comment.is_empty() {
+                result.push(' ');
+                result.push_str(&pre_comment);
+            }
             result.push(' ');
             result.push_str(s);
             result.push(' ');
+            if !post_comment.is_empty() {
+                result.push_str(&post_comment);
+                result.push(' ');
+            }
         } else {
             return None;
         }
@@ -123,7 +140,14 @@ fn rewrite_pairs_multiline<T: Rewrite>(
 
     result.push_str(list.list[0].1.as_ref().map_err(|err| err.clone())?);
 
-    for ((e, default_rw), s) in list.list[1..].iter().zip(list.separators.iter()) {
+    for (i, ((e, default_rw), s)) in list.list[1..]
+        .iter()
+        .zip(list.separators.iter())
+        .enumerate()
+    {
+        let (pre_comment, post_comment) = list.separator_comments(i, context, nested_shape)?;
+        let has_comment = !pre_comment.is_empty() || !post_comment.is_empty();
+
         // The following test checks if we should keep two subexprs on the same
         // line. We do this if not doing so would create an orphan and there is
         // enough space to do so.
@@ -132,7 +156,7 @@ fn rewrite_pairs_multiline<T: Rewrite>(
         } else {
             shape.used_width()
         };
-        if last_line_width(&result) + offset <= nested_shape.used_width() {
+        if !has_comment && last_line_width(&result) + offset <= nested_shape.used_width() {
             // We must snuggle the next line onto the previous line to avoid an orphan.
             if let Some(line_shape) =
                 shape.offset_left_opt(s.len() + 2 + trimmed_last_line_width(&result))
@@ -147,16 +171,41 @@ fn rewrite_pairs_multiline<T: Rewrite>(
             }
         }
 
-        match context.config.binop_separator() {
+        if !pre_comment.is_empty() {
+            result.push(' ');
+            result.push_str(&pre_comment);
+        }
+        // A line comment before the separator pushes it to the next line, and one after the
+        // separator keeps it at the end of the line.
+        let separator_place = if pre_comment.starts_with("//") {
+            SeparatorPlace::Front
+        } else if post_comment.starts_with("//") {
+            SeparatorPlace::Back
+        } else {
+            context.config.binop_separator()
+        };
+        match separator_place {
             SeparatorPlace::Back => {
                 result.push(' ');
                 result.push_str(s);
+                if !post_comment.is_empty() {
+                    result.push(' ');
+                    result.push_str(&post_comment);
+                }
                 result.push_str(&indent_str);
             }
             SeparatorPlace::Front => {
                 result.push_str(&indent_str);
                 result.push_str(s);
                 result.push(' ');
+                if !post_comment.is_empty() {
+                    result.push_str(&post_comment);
+                    if post_comment.starts_with("//") {
+                        result.push_str(&indent_str);
+                    } else {
+                        result.push(' ');
+                    }
+                }
             }
         }
 
@@ -263,9 +312,27 @@ trait FlattenPair: Rewrite + Sized {
 struct PairList<'a, 'b, T: Rewrite> {
     list: Vec<(&'b T, RewriteResult)>,
     separators: Vec<&'a str>,
+    // The spans before and after each separator, which may contain comments.
+    comment_spans: Vec<(Span, Span)>,
     span: Span,
 }
 
+impl<'a, 'b, T: Rewrite> PairList<'a, 'b, T> {
+    /// Rewrites the comments before and after the separator at `index`.
+    fn separator_comments(
+        &self,
+        index: usize,
+        context: &RewriteContext<'_>,
+        shape: Shape,
+    ) -> Result<(String, String), RewriteError> {
+        let (before, after) = self.comment_spans[index];
+        Ok((
+            rewrite_missing_comment(before, shape, context)?,
+            rewrite_missing_comment(after, shape, context)?,
+        ))
+    }
+}
+
 fn is_ident_or_bool_lit(expr: &ast::Expr) -> bool {
     match &expr.kind {
         ast::ExprKind::Path(None, path) if path.segments.len() == 1 => true,
@@ -331,11 +398,15 @@ impl FlattenPair for ast::Expr {
             node.rewrite_result(context, default_shape)
         };
 
+        // Spans may be out of order in macros, in which case no comment is looked for.
+        let comment_span = |lo: BytePos, hi: BytePos| mk_sp(lo, hi.max(lo));
+
         // Turn a tree of binop expressions into a list using a depth-first,
         // in-order traversal.
         let mut stack = vec![];
         let mut list = vec![];
         let mut separators = vec![];
+        let mut comment_spans = vec![];
         let mut node = self;
         let span = self.span();
         loop {
@@ -350,8 +421,12 @@ impl FlattenPair for ast::Expr {
                     list.push((node, rw));
                     if let Some(pop) = stack.pop() {
                         match pop.kind {
-                            ast::ExprKind::Binary(op, _, ref rhs) => {
+                            ast::ExprKind::Binary(op, ref lhs, ref rhs) => {
                                 separators.push(op.node.as_str());
+                                comment_spans.push((
+                                    comment_span(lhs.span.hi(), op.span.lo()),
+                                    comment_span(op.span.hi(), rhs.span.lo()),
+                                ));
                                 node = rhs;
                             }
                             _ => unreachable!(),
@@ -367,6 +442,7 @@ impl FlattenPair for ast::Expr {
         Some(PairList {
             list,
             separators,
+            comment_spans,
             span,
         })
     }
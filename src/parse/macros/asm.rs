@@ -3,9 +3,23 @@ use rustc_parse::parser::asm::{AsmArg, parse_asm_args};
 
 use crate::rewrite::RewriteContext;
 
-#[allow(dead_code)]
-pub(crate) fn parse_asm(context: &RewriteContext<'_>, mac: &ast::MacCall) -> Option<Vec<AsmArg>> {
+pub(crate) fn parse_asm(
+    context: &RewriteContext<'_>,
+    mac: &ast::MacCall,
+    asm_macro: ast::AsmMacro,
+) -> Option<Vec<AsmArg>> {
     let ts = mac.args.tokens.clone();
     let mut parser = super::build_parser(context, ts);
-    parse_asm_args(&mut parser, mac.span(), ast::AsmMacro::Asm).ok()
+    match parse_asm_args(&mut parser, mac.span(), asm_macro) {
+        Ok(args) if parser.psess.dcx().has_errors().is_none() => Some(args),
+        Ok(_) => {
+            parser.psess.dcx().reset_err_count();
+            None
+        }
+        Err(err) => {
+            err.cancel();
+            parser.psess.dcx().reset_err_count();
+            None
+        }
+    }
 }
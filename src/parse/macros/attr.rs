@@ -0,0 +1,108 @@
+//! Parsing of the arguments of attributes which aren't meta items, e.g. of attribute macros like
+//! `#[tracing::instrument(skip(self), fields(id = %req.id))]`.
+
+use rustc_ast::ast;
+use rustc_ast::token::{Delimiter, Token, TokenKind};
+use rustc_ast::tokenstream::{TokenStream, TokenTree};
+use rustc_ast_pretty::pprust;
+use rustc_parse::exp;
+use rustc_parse::parser::Parser;
+
+use crate::attr::AttrArg;
+use crate::rewrite::RewriteContext;
+use crate::utils::mk_sp;
+
+/// Parses the tokens between the parentheses of an attribute as a comma-separated list of
+/// `path(...)`, `path = value` or plain values. Returns `None` if any argument can't be parsed.
+pub(crate) fn parse_attr_args(
+    context: &RewriteContext<'_>,
+    tokens: TokenStream,
+) -> Option<Vec<AttrArg>> {
+    let mut parser = super::build_parser(context, tokens);
+    let mut args = Vec::new();
+    while parser.token.kind != TokenKind::Eof {
+        args.push(parse_attr_arg(context, &mut parser)?);
+        if !parser.eat(exp!(Comma)) && parser.token.kind != TokenKind::Eof {
+            return None;
+        }
+    }
+    Some(args)
+}
+
+fn parse_attr_arg(context: &RewriteContext<'_>, parser: &mut Parser<'_>) -> Option<AttrArg> {
+    let lo = parser.token.span.lo();
+    if let Some(path_len) = path_len(parser) {
+        if parser.look_ahead(path_len, |t| *t == TokenKind::OpenParen) {
+            // `path(...)` is only a list if it isn't followed by the rest of an expression,
+            // e.g. `path(...).method()`.
+            let mut cloned_parser = parser.clone();
+            let path = take_path(&mut cloned_parser, path_len);
+            if let TokenTree::Delimited(_, _, Delimiter::Parenthesis, tokens) =
+                cloned_parser.parse_token_tree()
+            {
+                if matches!(cloned_parser.token.kind, TokenKind::Comma | TokenKind::Eof) {
+                    let args = parse_attr_args(context, tokens)?;
+                    *parser = cloned_parser;
+                    let span = mk_sp(lo, parser.prev_token.span.hi());
+                    return Some(AttrArg::List(path, args, span));
+                }
+            }
+        } else if parser.look_ahead(path_len, |t| *t == TokenKind::Eq) {
+            let key = take_path(parser, path_len);
+            parser.bump();
+            let (sigil, value) = parse_value(parser)?;
+            let span = mk_sp(lo, value.span.hi());
+            return Some(AttrArg::KeyValue(key, sigil, value, span));
+        }
+    }
+    let (sigil, value) = parse_value(parser)?;
+    let span = mk_sp(lo, value.span.hi());
+    Some(AttrArg::Value(sigil, value, span))
+}
+
+/// Returns the number of tokens of the path at the start of `parser`, whose segments are
+/// separated by `::`, or by `.` as in the field names of `tracing`.
+fn path_len(parser: &Parser<'_>) -> Option<usize> {
+    if !parser.token.is_ident() {
+        return None;
+    }
+    let is_separator = |t: &Token| matches!(t.kind, TokenKind::PathSep | TokenKind::Dot);
+    let mut len = 1;
+    while parser.look_ahead(len, is_separator) && parser.look_ahead(len + 1, |t| t.is_ident()) {
+        len += 2;
+    }
+    Some(len)
+}
+
+fn take_path(parser: &mut Parser<'_>, path_len: usize) -> String {
+    let mut path = String::new();
+    for _ in 0..path_len {
+        path.push_str(&pprust::token_to_string(&parser.token));
+        parser.bump();
+    }
+    path
+}
+
+/// Parses an expression, which may be preceded by the `%` or `?` sigils of `tracing`.
+fn parse_value(parser: &mut Parser<'_>) -> Option<(&'static str, Box<ast::Expr>)> {
+    let sigil = match parser.token.kind {
+        TokenKind::Percent => "%",
+        TokenKind::Question => "?",
+        _ => "",
+    };
+    if !sigil.is_empty() {
+        parser.bump();
+    }
+    match parser.parse_expr() {
+        Ok(expr) if parser.psess.dcx().has_errors().is_none() => Some((sigil, expr)),
+        Ok(_) => {
+            parser.psess.dcx().reset_err_count();
+            None
+        }
+        Err(e) => {
+            e.cancel();
+            parser.psess.dcx().reset_err_count();
+            None
+        }
+    }
+}
@@ -1,14 +1,19 @@
 use rustc_ast::ast;
-use rustc_ast::token::{Delimiter, NonterminalKind, NtExprKind::*, NtPatKind::*, TokenKind};
-use rustc_ast::tokenstream::TokenStream;
+use rustc_ast::token::{
+    Delimiter, IdentIsRaw, NonterminalKind, NtExprKind::*, NtPatKind::*, TokenKind,
+};
+use rustc_ast::tokenstream::{DelimSpacing, DelimSpan, Spacing, TokenStream, TokenTree};
 use rustc_parse::MACRO_ARGUMENTS;
 use rustc_parse::parser::{AllowConstBlockItems, ForceCollect, Parser, Recovery};
 use rustc_session::parse::ParseSess;
-use rustc_span::symbol;
+use rustc_span::symbol::{self, kw};
+use thin_vec::ThinVec;
 
 use crate::macros::MacroArg;
 use crate::rewrite::RewriteContext;
 
+pub(crate) mod asm;
+pub(crate) mod attr;
 pub(crate) mod cfg_if;
 pub(crate) mod cfg_match;
 pub(crate) mod lazy_static;
@@ -170,3 +175,100 @@ pub(crate) fn parse_expr(
     let mut parser = build_parser(context, tokens);
     parser.parse_expr().ok()
 }
+
+/// Parses the arguments of a macro as a list of items.
+pub(crate) fn parse_macro_items(
+    context: &RewriteContext<'_>,
+    tokens: TokenStream,
+) -> Option<Vec<MacroArg>> {
+    let mut parser = build_parser(context, tokens);
+    let mut items = Vec::new();
+    while parser.token.kind != TokenKind::Eof {
+        match parser.parse_item(ForceCollect::No, AllowConstBlockItems::Yes) {
+            Ok(Some(item)) if parser.psess.dcx().has_errors().is_none() => {
+                items.push(MacroArg::Item(item));
+            }
+            Ok(_) => {
+                parser.psess.dcx().reset_err_count();
+                return None;
+            }
+            Err(e) => {
+                e.cancel();
+                parser.psess.dcx().reset_err_count();
+                return None;
+            }
+        }
+    }
+    Some(items)
+}
+
+/// Parses the arguments of a macro invoked with braces as a block of statements.
+pub(crate) fn parse_macro_block(
+    context: &RewriteContext<'_>,
+    args: &ast::DelimArgs,
+) -> Option<Box<ast::Expr>> {
+    let block = TokenTree::Delimited(
+        args.dspan,
+        DelimSpacing::new(Spacing::Alone, Spacing::Alone),
+        Delimiter::Brace,
+        args.tokens.clone(),
+    );
+    let mut parser = build_parser(context, TokenStream::new(vec![block]));
+    match parser.parse_expr() {
+        Ok(expr)
+            if matches!(expr.kind, ast::ExprKind::Block(..))
+                && parser.token.kind == TokenKind::Eof
+                && parser.psess.dcx().has_errors().is_none() =>
+        {
+            Some(expr)
+        }
+        Ok(_) => {
+            parser.psess.dcx().reset_err_count();
+            None
+        }
+        Err(e) => {
+            e.cancel();
+            parser.psess.dcx().reset_err_count();
+            None
+        }
+    }
+}
+
+/// Parses the arguments of a macro invoked with braces as the arms of a `match` expression,
+/// e.g. `Foo(x) => x + 1, _ => 0`.
+pub(crate) fn parse_macro_arms(
+    context: &RewriteContext<'_>,
+    args: &ast::DelimArgs,
+) -> Option<ThinVec<ast::Arm>> {
+    let spacing = DelimSpacing::new(Spacing::Alone, Spacing::Alone);
+    let tokens = TokenStream::new(vec![
+        TokenTree::token_alone(TokenKind::Ident(kw::Match, IdentIsRaw::No), args.dspan.open),
+        TokenTree::Delimited(
+            DelimSpan::from_single(args.dspan.open),
+            spacing,
+            Delimiter::Parenthesis,
+            TokenStream::new(vec![]),
+        ),
+        TokenTree::Delimited(args.dspan, spacing, Delimiter::Brace, args.tokens.clone()),
+    ]);
+    let mut parser = build_parser(context, tokens);
+    match parser.parse_expr() {
+        Ok(expr)
+            if parser.token.kind == TokenKind::Eof && parser.psess.dcx().has_errors().is_none() =>
+        {
+            match expr.kind {
+                ast::ExprKind::Match(_, arms, _) if !arms.is_empty() => Some(arms),
+                _ => None,
+            }
+        }
+        Ok(_) => {
+            parser.psess.dcx().reset_err_count();
+            None
+        }
+        Err(e) => {
+            e.cancel();
+            parser.psess.dcx().reset_err_count();
+            None
+        }
+    }
+}
@@ -2,6 +2,7 @@ use std::path::Path;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 
+use rustc_ast::tokenstream::TokenStream;
 use rustc_data_structures::sync::IntoDynSyncSend;
 use rustc_errors::annotate_snippet_emitter_writer::AnnotateSnippetEmitter;
 use rustc_errors::emitter::{DynEmitter, Emitter, SilentEmitter, stderr_destination};
@@ -229,6 +230,30 @@ impl ParseSess {
         self.raw_psess.source_map().span_to_diagnostic_string(span)
     }
 
+    /// Returns the offset of `pos` from the start of its source file.
+    pub(crate) fn byte_offset(&self, pos: BytePos) -> usize {
+        self.raw_psess
+            .source_map()
+            .lookup_byte_offset(pos)
+            .pos
+            .to_usize()
+    }
+
+    /// Lexes `src` into a token stream, or returns `None` if it can't be lexed. `name` must be
+    /// unique within this session.
+    pub(crate) fn lex_str(&self, name: String, src: String) -> Option<TokenStream> {
+        let file_name = rustc_span::FileName::Custom(name);
+        match rustc_parse::source_str_to_stream(&self.raw_psess, file_name, src, None) {
+            Ok(stream) => Some(stream),
+            Err(diagnostics) => {
+                diagnostics
+                    .into_iter()
+                    .for_each(|diagnostic| diagnostic.cancel());
+                None
+            }
+        }
+    }
+
     pub(crate) fn inner(&self) -> &RawParseSess {
         &self.raw_psess
     }
@@ -243,7 +268,24 @@ impl ParseSess {
     }
 
     pub(crate) fn get_original_snippet(&self, filename: &FileName) -> Option<Arc<String>> {
-        let rustc_filename = match filename {
+        self.raw_psess
+            .source_map()
+            .get_source_file(&self.to_rustc_filename(filename))
+            .and_then(|source_file| source_file.src.clone())
+    }
+
+    /// Adds a file which is not parsed as Rust, e.g. a Markdown file, to the source map, so that
+    /// its original text can be retrieved with `get_original_snippet`. Returns that text.
+    pub(crate) fn add_source_file(&self, filename: &FileName, src: String) -> Arc<String> {
+        let source_file = self
+            .raw_psess
+            .source_map()
+            .new_source_file(self.to_rustc_filename(filename), src);
+        Arc::clone(source_file.src.as_ref().unwrap())
+    }
+
+    fn to_rustc_filename(&self, filename: &FileName) -> rustc_span::FileName {
+        match filename {
             FileName::Real(path) => rustc_span::FileName::Real(
                 self.raw_psess
                     .source_map()
@@ -251,12 +293,7 @@ impl ParseSess {
                     .to_real_filename(self.raw_psess.source_map().working_dir(), path),
             ),
             FileName::Stdin => rustc_span::FileName::Custom("stdin".to_owned()),
-        };
-
-        self.raw_psess
-            .source_map()
-            .get_source_file(&rustc_filename)
-            .and_then(|source_file| source_file.src.clone())
+        }
     }
 }
 
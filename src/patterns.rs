@@ -1,7 +1,7 @@
 use rustc_ast::ast::{self, BindingMode, ByRef, Pat, PatField, PatKind};
 use rustc_span::{BytePos, Span};
 
-use crate::comment::{FindUncommented, combine_strs_with_missing_comments};
+use crate::comment::{FindUncommented, combine_strs_with_missing_comments, contains_comment};
 use crate::config::StyleEdition;
 use crate::config::lists::*;
 use crate::expr::{can_be_overflowed_expr, rewrite_unary_prefix, wrap_struct_field};
@@ -92,11 +92,32 @@ impl Rewrite for Pat {
                     .map(|p| p.rewrite_result(context, shape))
                     .collect::<Result<Vec<_>, RewriteError>>()?;
 
-                let use_mixed_layout = pats
-                    .iter()
-                    .zip(pat_strs.iter())
-                    .all(|(pat, pat_str)| is_short_pattern(context, pat, pat_str));
-                let items: Vec<_> = pat_strs.into_iter().map(ListItem::from_str).collect();
+                let has_comment = pats.windows(2).any(|pair| {
+                    contains_comment(context.snippet(mk_sp(pair[0].span.hi(), pair[1].span.lo())))
+                });
+                let use_mixed_layout = !has_comment
+                    && pats
+                        .iter()
+                        .zip(pat_strs.iter())
+                        .all(|(pat, pat_str)| is_short_pattern(context, pat, pat_str));
+                let items: Vec<_> = if has_comment {
+                    // Itemize the alternatives to keep the comments between them.
+                    itemize_list(
+                        context.snippet_provider,
+                        pats.iter().zip(pat_strs),
+                        "",
+                        "|",
+                        |(pat, _)| pat.span.lo(),
+                        |(pat, _)| pat.span.hi(),
+                        |(_, pat_str)| Ok(pat_str.clone()),
+                        self.span.lo(),
+                        self.span.hi(),
+                        false,
+                    )
+                    .collect()
+                } else {
+                    pat_strs.into_iter().map(ListItem::from_str).collect()
+                };
                 let tactic = if use_mixed_layout {
                     DefinitiveListTactic::Mixed
                 } else {
@@ -292,6 +292,11 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
             .iter()
             .take_while(|ppi| {
                 item_kind.is_same_item_kind(&***ppi)
+                    && self
+                        .skip_context
+                        .off_regions
+                        .overlapping(ppi.span())
+                        .is_none()
                     && (!in_group || {
                         let current = self.psess.lookup_line_range(ppi.span());
                         let in_same_group = current.lo < last.hi + 2;
@@ -316,10 +321,10 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
                 self.shape(),
                 span,
             );
-            self.push_rewrite(span, rw.ok());
+            self.push_rewrite_result(span, rw);
         } else {
             for item in items {
-                self.push_rewrite(item.span, None);
+                self.push_out_of_file_lines(item.span);
             }
         }
 
@@ -334,7 +339,15 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
             // subsequent items that have the same item kind to be reordered within
             // `walk_reorderable_items`. Otherwise, just format the next item for output.
             let item_kind = ReorderableItemKind::from(items[0]);
-            if item_kind.is_reorderable(self.config) || item_kind.is_regroupable(self.config) {
+            // Items in regions disabled with `// rustfmt::off` are not moved.
+            let in_off_region = self
+                .skip_context
+                .off_regions
+                .overlapping(items[0].span())
+                .is_some();
+            if !in_off_region
+                && (item_kind.is_reorderable(self.config) || item_kind.is_regroupable(self.config))
+            {
                 let visited_items_num = self.walk_reorderable_or_regroupable_items(
                     items,
                     item_kind,
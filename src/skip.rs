@@ -2,7 +2,12 @@
 
 use rustc_ast::ast;
 use rustc_ast_pretty::pprust;
+use rustc_span::{BytePos, Pos, Span};
 use std::collections::HashSet;
+use std::rc::Rc;
+
+use crate::comment::{CharClasses, FullCodeCharKind};
+use crate::utils::mk_sp;
 
 /// Track which blocks of code are to be skipped when formatting.
 ///
@@ -16,6 +21,7 @@ use std::collections::HashSet;
 pub(crate) struct SkipContext {
     pub(crate) macros: SkipNameContext,
     pub(crate) attributes: SkipNameContext,
+    pub(crate) off_regions: OffRegions,
 }
 
 impl SkipContext {
@@ -25,9 +31,142 @@ impl SkipContext {
     }
 
     pub(crate) fn update(&mut self, other: SkipContext) {
-        let SkipContext { macros, attributes } = other;
+        let SkipContext {
+            macros,
+            attributes,
+            off_regions,
+        } = other;
         self.macros.update(macros);
         self.attributes.update(attributes);
+        self.off_regions = off_regions;
+    }
+}
+
+/// The regions of a file enclosed by `// rustfmt::off` and `// rustfmt::on` comments, which
+/// are copied verbatim. Each region starts at its `// rustfmt::off` comment and ends after its
+/// `// rustfmt::on` comment.
+#[derive(Default, Clone)]
+pub(crate) struct OffRegions(Rc<Vec<Span>>);
+
+impl OffRegions {
+    /// Finds the regions of `src`, which starts at `start_pos`. Also returns the spans of the
+    /// markers which are not paired with another marker in the same block, and are ignored.
+    pub(crate) fn from_source(src: &str, start_pos: BytePos) -> (OffRegions, Vec<Span>) {
+        let span = |lo: usize, hi: usize| {
+            mk_sp(
+                start_pos + BytePos::from_usize(lo),
+                start_pos + BytePos::from_usize(hi),
+            )
+        };
+        let mut regions = vec![];
+        let mut unbalanced = vec![];
+        // The `// rustfmt::off` marker waiting for its `// rustfmt::on`, with its nesting depth.
+        let mut open: Option<(usize, usize, isize)> = None;
+        // The lowest nesting depth since the open marker.
+        let mut min_depth = 0;
+        let mut depth: isize = 0;
+        let mut line_start = true;
+        let mut skip_until = 0;
+
+        for (kind, (i, c)) in CharClasses::new(src.char_indices()) {
+            if c == '\n' {
+                line_start = true;
+                continue;
+            }
+            if line_start && !c.is_whitespace() {
+                line_start = false;
+                if kind == FullCodeCharKind::StartComment {
+                    let line = src[i..].lines().next().unwrap_or_default().trim_end();
+                    let hi = i + line.len();
+                    match off_marker(line) {
+                        Some(true) if open.is_none() => {
+                            open = Some((i, hi, depth));
+                            min_depth = depth;
+                        }
+                        Some(true) => unbalanced.push(span(i, hi)),
+                        Some(false) => match open.take() {
+                            Some((lo, _, open_depth))
+                                if open_depth == depth && min_depth >= depth =>
+                            {
+                                regions.push(span(lo, hi));
+                            }
+                            Some((lo, lo_hi, _)) => {
+                                unbalanced.push(span(lo, lo_hi));
+                                unbalanced.push(span(i, hi));
+                            }
+                            None => unbalanced.push(span(i, hi)),
+                        },
+                        None => {}
+                    }
+                }
+            }
+            if kind != FullCodeCharKind::Normal || i < skip_until {
+                continue;
+            }
+            match c {
+                // `CharClasses` doesn't recognize most character literals, which may hold
+                // delimiters.
+                '\'' => {
+                    if let Some(m) =
+                        static_regex!(r"^'(?:\\u\{[0-9a-fA-F_]*\}|\\.|[^'\\\n])'").find(&src[i..])
+                    {
+                        skip_until = i + m.end();
+                    }
+                }
+                '{' | '(' | '[' => depth += 1,
+                '}' | ')' | ']' => {
+                    depth -= 1;
+                    min_depth = min_depth.min(depth);
+                }
+                _ => {}
+            }
+        }
+        if let Some((lo, hi, _)) = open {
+            unbalanced.push(span(lo, hi));
+        }
+        (OffRegions(Rc::new(regions)), unbalanced)
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = Span> + '_ {
+        self.0.iter().copied()
+    }
+
+    /// Returns the region which contains `pos`.
+    pub(crate) fn containing(&self, pos: BytePos) -> Option<Span> {
+        self.0
+            .iter()
+            .find(|region| region.lo() <= pos && pos < region.hi())
+            .copied()
+    }
+
+    /// Returns the first region which overlaps `span`.
+    pub(crate) fn overlapping(&self, span: Span) -> Option<Span> {
+        self.0
+            .iter()
+            .find(|region| region.lo() < span.hi() && span.lo() < region.hi())
+            .copied()
+    }
+
+    /// Returns the regions which lie within `span`.
+    pub(crate) fn within(&self, span: Span) -> impl Iterator<Item = Span> + '_ {
+        self.0
+            .iter()
+            .filter(move |region| span.contains(**region))
+            .copied()
+    }
+}
+
+/// Returns `Some(true)` for a `// rustfmt::off` comment and `Some(false)` for a
+/// `// rustfmt::on` comment.
+fn off_marker(comment: &str) -> Option<bool> {
+    let text = comment.strip_prefix("//")?;
+    if text.starts_with(['/', '!']) {
+        return None;
+    }
+    match text.trim() {
+        "rustfmt::off" => Some(true),
+        "rustfmt::on" => Some(false),
+        _ => None,
     }
 }
 
@@ -125,3 +264,43 @@ fn get_skip_names(kind: &str, attrs: &[ast::Attribute]) -> Vec<String> {
     }
     skip_names
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn regions(src: &str) -> (Vec<(u32, u32)>, Vec<(u32, u32)>) {
+        let (regions, unbalanced) = OffRegions::from_source(src, BytePos(0));
+        let to_pairs = |spans: Vec<Span>| {
+            spans
+                .into_iter()
+                .map(|span| (span.lo().0, span.hi().0))
+                .collect()
+        };
+        (to_pairs(regions.iter().collect()), to_pairs(unbalanced))
+    }
+
+    #[test]
+    fn finds_regions_in_the_same_block() {
+        let src = "fn f() {\n    // rustfmt::off\n    let x  = 1;\n    // rustfmt::on\n}\n";
+        assert_eq!(regions(src), (vec![(13, 63)], vec![]));
+    }
+
+    #[test]
+    fn ignores_markers_in_doc_comments_and_after_code() {
+        let src = "/// rustfmt::off\nfn f() {} // rustfmt::off\n//! rustfmt::on\n";
+        assert_eq!(regions(src), (vec![], vec![]));
+    }
+
+    #[test]
+    fn ignores_delimiters_in_char_literals() {
+        let src = "// rustfmt::off\nconst C: char = '{';\n// rustfmt::on\n";
+        assert_eq!(regions(src), (vec![(0, 51)], vec![]));
+    }
+
+    #[test]
+    fn reports_unbalanced_markers() {
+        let src = "fn f() {\n    // rustfmt::off\n}\n// rustfmt::on\n// rustfmt::on\n";
+        assert_eq!(regions(src), (vec![], vec![(13, 28), (31, 45), (46, 60)]));
+    }
+}
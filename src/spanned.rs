@@ -3,6 +3,7 @@ use std::cmp::max;
 use rustc_ast::ast;
 use rustc_span::Span;
 
+use crate::attr::AttrArg;
 use crate::macros::MacroArg;
 use crate::utils::{mk_sp, outer_attributes};
 
@@ -185,7 +186,17 @@ impl Spanned for MacroArg {
             MacroArg::Ty(ref ty) => ty.span(),
             MacroArg::Pat(ref pat) => pat.span(),
             MacroArg::Item(ref item) => item.span(),
-            MacroArg::Keyword(_, span) => span,
+            MacroArg::Keyword(_, span) | MacroArg::FormatStr(_, span) => span,
+        }
+    }
+}
+
+impl Spanned for AttrArg {
+    fn span(&self) -> Span {
+        match *self {
+            AttrArg::List(_, _, span)
+            | AttrArg::KeyValue(_, _, _, span)
+            | AttrArg::Value(_, _, span) => span,
         }
     }
 }
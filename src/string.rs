@@ -1,10 +1,12 @@
 // Format string literals.
 
+use std::borrow::Cow;
+
 use regex::Regex;
 use unicode_properties::{GeneralCategory, UnicodeGeneralCategory};
 use unicode_segmentation::UnicodeSegmentation;
 
-use crate::config::Config;
+use crate::config::{Config, SeparatorTactic};
 use crate::shape::Shape;
 use crate::utils::{unicode_str_width, wrap_str};
 
@@ -71,10 +73,7 @@ pub(crate) fn rewrite_string<'a>(
     let indent_with_newline = fmt.shape.indent.to_string_with_newline(fmt.config);
     let indent_without_newline = fmt.shape.indent.to_string(fmt.config);
 
-    // Strip line breaks.
-    // With this regex applied, all remaining whitespaces are significant
-    let strip_line_breaks_re = Regex::new(r"([^\\](\\\\)*)\\[\n\r][[:space:]]*").unwrap();
-    let stripped_str = strip_line_breaks_re.replace_all(orig, "$1");
+    let stripped_str = strip_line_breaks(orig);
 
     let graphemes = UnicodeSegmentation::graphemes(&*stripped_str, false).collect::<Vec<&str>>();
 
@@ -153,6 +152,167 @@ pub(crate) fn rewrite_string<'a>(
     wrap_str(result, fmt.config.max_width(), fmt.shape)
 }
 
+/// Strips the escaped line breaks of a string literal's contents, and the whitespaces following
+/// them. All remaining whitespaces are significant.
+fn strip_line_breaks(s: &str) -> Cow<'_, str> {
+    let strip_line_breaks_re = Regex::new(r"([^\\](\\\\)*)\\[\n\r][[:space:]]*").unwrap();
+    strip_line_breaks_re.replace_all(s, "$1")
+}
+
+/// Splits a string literal, normal or raw, into its opening quote, contents and closing quote.
+pub(crate) fn split_str_lit(lit: &str) -> Option<(&str, &str, &str)> {
+    let (opening_len, closing_len) = match lit.strip_prefix('r') {
+        Some(rest) => {
+            let hashes = rest.len() - rest.trim_start_matches('#').len();
+            (hashes + 2, hashes + 1)
+        }
+        None => (1, 1),
+    };
+    if lit.len() < opening_len + closing_len {
+        return None;
+    }
+    let (opening, rest) = lit.split_at(opening_len);
+    let (contents, closing) = rest.split_at(rest.len() - closing_len);
+    Some((opening, contents, closing))
+}
+
+/// Rewrites the string literal `lit`, normal or raw, as a `concat!` invocation of pieces of it,
+/// one per line, if it doesn't fit in `shape`.
+///
+/// The pieces are never split in the middle of an escape or a `{...}` placeholder, and keep the
+/// quotes of `lit`. Returns `None` if `lit` contains line breaks or can't be split to fit.
+pub(crate) fn rewrite_string_with_concat(
+    lit: &str,
+    shape: Shape,
+    config: &Config,
+) -> Option<String> {
+    let (opening, contents, closing) = split_str_lit(lit)?;
+    let is_raw = opening.starts_with('r');
+    let contents = if is_raw {
+        Cow::Borrowed(contents)
+    } else {
+        strip_line_breaks(contents)
+    };
+    if contents.contains(['\n', '\r']) {
+        return None;
+    }
+    let single_line = format!("{opening}{contents}{closing}");
+    if unicode_str_width(&single_line) <= shape.width {
+        return Some(single_line);
+    }
+
+    let indent = shape.indent.block_only();
+    let nested_indent = indent.block_indent(config);
+    // The quotes and the comma following each piece.
+    let max_width = config
+        .max_width()
+        .checked_sub(nested_indent.width() + opening.len() + closing.len() + 1)?;
+    if max_width < MIN_STRING {
+        return None;
+    }
+
+    let mut result = String::with_capacity(lit.len() * 2);
+    result.push_str("concat!(");
+    let pieces = split_into_pieces(&string_atoms(&contents, is_raw), max_width);
+    for (i, piece) in pieces.iter().enumerate() {
+        result.push_str(&nested_indent.to_string_with_newline(config));
+        result.push_str(opening);
+        result.push_str(piece);
+        result.push_str(closing);
+        if i + 1 < pieces.len() || config.trailing_comma() != SeparatorTactic::Never {
+            result.push(',');
+        }
+    }
+    result.push_str(&indent.to_string_with_newline(config));
+    result.push(')');
+    wrap_str(result, config.max_width(), shape)
+}
+
+/// Returns whether the contents of the format string literal `lit` have a placeholder referring
+/// to an argument by name, e.g. `{name}` or `{name:?}`, which may capture a variable.
+pub(crate) fn has_named_placeholders(lit: &str) -> bool {
+    let Some((opening, contents, _)) = split_str_lit(lit) else {
+        return false;
+    };
+    string_atoms(contents, opening.starts_with('r'))
+        .iter()
+        .any(|atom| match atom.strip_prefix('{') {
+            Some(rest) => rest.starts_with(|c: char| c.is_alphabetic() || c == '_'),
+            None => false,
+        })
+}
+
+/// Splits the contents of a string literal into the graphemes, escapes and `{...}` placeholders
+/// that must not be split across lines.
+fn string_atoms(contents: &str, is_raw: bool) -> Vec<&str> {
+    let graphemes = contents.grapheme_indices(false).collect::<Vec<_>>();
+    let end_of = |i: usize| {
+        graphemes
+            .get(i)
+            .map_or(contents.len(), |&(offset, _)| offset)
+    };
+    let mut atoms = Vec::with_capacity(graphemes.len());
+    let mut i = 0;
+    while i < graphemes.len() {
+        let next = graphemes.get(i + 1).map(|&(_, g)| g);
+        let len = match graphemes[i].1 {
+            "\\" if !is_raw => match next {
+                // `\u{...}`
+                Some("u") => graphemes[i..]
+                    .iter()
+                    .position(|&(_, g)| g == "}")
+                    .map_or(2, |pos| pos + 1),
+                // `\x7f`
+                Some("x") => 4,
+                _ => 2,
+            },
+            "{" if next == Some("{") => 2,
+            "}" if next == Some("}") => 2,
+            "{" => graphemes[i + 1..]
+                .iter()
+                .position(|&(_, g)| g == "{" || g == "}")
+                .filter(|&pos| graphemes[i + 1 + pos].1 == "}")
+                .map_or(1, |pos| pos + 2),
+            _ => 1,
+        };
+        let len = len.min(graphemes.len() - i);
+        atoms.push(&contents[graphemes[i].0..end_of(i + len)]);
+        i += len;
+    }
+    atoms
+}
+
+/// Packs `atoms` into pieces of at most `max_width` columns, breaking after a whitespace or a
+/// punctuation where possible. A single atom wider than `max_width` gets a piece of its own.
+fn split_into_pieces(atoms: &[&str], max_width: usize) -> Vec<String> {
+    let mut pieces = vec![];
+    let mut start = 0;
+    let mut width = 0;
+    let mut last_whitespace = None;
+    let mut last_punctuation = None;
+    for (i, atom) in atoms.iter().enumerate() {
+        let atom_width = unicode_str_width(atom);
+        if width + atom_width > max_width && i > start {
+            let end = last_whitespace.or(last_punctuation).unwrap_or(i);
+            pieces.push(atoms[start..end].concat());
+            start = end;
+            width = graphemes_width(&atoms[start..i]);
+            last_whitespace = None;
+            last_punctuation = None;
+        }
+        width += atom_width;
+        if is_whitespace(atom) {
+            last_whitespace = Some(i + 1);
+        } else if is_punctuation(atom) {
+            last_punctuation = Some(i + 1);
+        }
+    }
+    if start < atoms.len() {
+        pieces.push(atoms[start..].concat());
+    }
+    pieces
+}
+
 /// Returns the index to the end of the URL if the split at index of the given string includes a
 /// URL or alike. Otherwise, returns `None`.
 fn detect_url(s: &[&str], index: usize) -> Option<usize> {
@@ -384,8 +544,11 @@ fn graphemes_width(graphemes: &[&str]) -> usize {
 
 #[cfg(test)]
 mod test {
-    use super::{SnippetState, StringFormat, break_string, detect_url, rewrite_string};
-    use crate::config::Config;
+    use super::{
+        SnippetState, StringFormat, break_string, detect_url, has_named_placeholders,
+        rewrite_string, rewrite_string_with_concat, string_atoms,
+    };
+    use crate::config::{Config, SeparatorTactic};
     use crate::shape::{Indent, Shape};
     use unicode_segmentation::UnicodeSegmentation;
 
@@ -705,6 +868,53 @@ mod test {
         );
     }
 
+    #[test]
+    fn string_atoms_keep_escapes_and_placeholders() {
+        assert_eq!(
+            string_atoms(r#"a\u{1F600}\x41\"{x:?}{{}}"#, false),
+            vec!["a", r"\u{1F600}", r"\x41", r#"\""#, "{x:?}", "{{", "}}"]
+        );
+        assert_eq!(string_atoms(r"\n{", true), vec!["\\", "n", "{"]);
+    }
+
+    #[test]
+    fn named_placeholders() {
+        assert!(has_named_placeholders(r#""{name:?}""#));
+        assert!(has_named_placeholders(r##"r#"{_x}"#"##));
+        assert!(!has_named_placeholders(r#""{} {0:?} {{name}}""#));
+    }
+
+    #[test]
+    fn split_with_concat() {
+        let mut config = Config::default();
+        config.set().max_width(30);
+        let shape = Shape::legacy(20, Indent::new(4, 0));
+        assert_eq!(
+            rewrite_string_with_concat(
+                r#""lorem ipsum dolor sit amet consectetur""#,
+                shape,
+                &config
+            )
+            .as_deref(),
+            Some(
+                "concat!(\n        \"lorem ipsum dolor \",\n        \"sit amet \",\n        \
+                 \"consectetur\",\n    )"
+            )
+        );
+        assert_eq!(
+            rewrite_string_with_concat(r#""lorem ipsum""#, shape, &config).as_deref(),
+            Some(r#""lorem ipsum""#)
+        );
+        assert_eq!(
+            rewrite_string_with_concat(
+                "\"lorem ipsum\ndolor sit amet consectetur\"",
+                shape,
+                &config
+            ),
+            None
+        );
+    }
+
     #[test]
     fn detect_urls() {
         let string = "aaa http://example.org something";
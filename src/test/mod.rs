@@ -10,7 +10,7 @@ use std::str::{Chars, FromStr};
 use std::thread;
 
 use crate::config::{Color, Config, EmitMode, FileName, NewlineStyle};
-use crate::formatting::{ReportedErrors, SourceFile};
+use crate::formatting::{ReportedErrors, SourceFile, idempotence_errors};
 use crate::rustfmt_diff::{DiffLine, Mismatch, ModifiedChunk, OutputWriter, make_diff, print_diff};
 use crate::source_file;
 use crate::{
@@ -376,6 +376,30 @@ fn modified_test() {
     );
 }
 
+#[test]
+fn unformatted_report_test() {
+    init_log();
+    let filename = "tests/writemode/source/unformatted_report/lib.rs";
+    let mut data = Vec::new();
+    let mut config = Config::default();
+    config.set().emit_mode(EmitMode::UnformattedReport);
+    config.set().format_generated_files(false);
+
+    {
+        let mut session = Session::new(config, Some(&mut data));
+        session.format(Input::File(filename.into())).unwrap();
+    }
+
+    assert_eq!(
+        String::from_utf8(data).unwrap(),
+        "tests/writemode/source/unformatted_report/generated.rs: detected as generated\n\
+         tests/writemode/source/unformatted_report/skipped.rs: skipped by `#[rustfmt::skip]`\n\
+         tests/writemode/source/unformatted_report/lib.rs:4-7: skipped by `#[rustfmt::skip]`\n\
+         tests/writemode/source/unformatted_report/lib.rs:10: failed to format macro (parse \
+         failure)\n",
+    );
+}
+
 // Helper function for comparing the results of rustfmt
 // to a known output file generated by one of the write modes.
 fn assert_output(source: &Path, expected_filename: &Path) {
@@ -779,6 +803,73 @@ fn format_lines_errors_are_reported_with_tabs() {
     assert!(session.has_formatting_errors());
 }
 
+#[test]
+fn unbalanced_off_markers_are_reported() {
+    init_log();
+    let input = Input::Text(String::from(
+        "fn main() {\n    // rustfmt::off\n    let x  = 1;\n}\n// rustfmt::on\n",
+    ));
+    let mut session = Session::<io::Stdout>::new(Config::default(), None);
+    let report = session.format(input).unwrap();
+    assert!(report.has_warnings());
+    assert!(session.has_check_errors());
+}
+
+#[test]
+fn verify_idempotent_accepts_stable_formatting() {
+    init_log();
+    let input = Input::Text(String::from("fn main() {\nlet v = vec![1,2,3];\n}\n"));
+    let mut config = Config::default();
+    config.set().verify_idempotent(true);
+    let mut session = Session::<io::Stdout>::new(config, None);
+    let report = session.format(input).unwrap();
+    assert!(!report.has_warnings());
+    assert!(!session.has_operational_errors());
+}
+
+#[test]
+fn idempotence_errors_contain_diff_of_both_passes() {
+    let first_pass = "fn main() {\n    foo( );\n    bar();\n    baz();\n}\n";
+    let second_pass = "fn main() {\n    foo();\n    bar();\n\n    baz();\n}\n";
+    let errors = idempotence_errors(first_pass, second_pass);
+    assert_eq!(errors.len(), 2);
+
+    assert_eq!(errors[0].line, 2);
+    assert_eq!(errors[0].line_buffer, "    foo( );");
+    assert_eq!(errors[0].highlight, Some(0..11));
+    assert_eq!(
+        errors[0].kind.to_string(),
+        "formatting is not idempotent, a second pass changes the output:\n\
+         -    foo( );\n\
+         +    foo();"
+    );
+
+    // Inserted lines are reported on the line they are inserted in front of.
+    assert_eq!(errors[1].line, 4);
+    assert_eq!(errors[1].line_buffer, "    baz();");
+    assert_eq!(errors[1].highlight, None);
+    assert_eq!(errors[1].kind.name(), "non_idempotent");
+}
+
+#[test]
+fn verify_tokens_accepts_intended_changes() {
+    init_log();
+    let input = Input::Text(String::from(
+        "use b::{c, a::{self, d}};\nuse b::e;\n#[derive(Debug, Clone,)]\nstruct S { x: u8, }\n\
+         /// Doc text\n/// wrapped.\nfn f(x: u8) -> u8 {\nlet s = S { x: x };\n\
+         match s.x { 1 => { 2 }, | 3 => 4, _ => ((5)) }\n}\n",
+    ));
+    let mut config = Config::default();
+    config.set().verify_tokens(true);
+    config.set().emit_mode(EmitMode::Stdout);
+    let mut output = Vec::new();
+    let mut session = Session::new(config, Some(&mut output));
+    let report = session.format(input).unwrap();
+    assert!(!report.has_warnings());
+    assert!(!session.has_operational_errors());
+    assert_ne!(output, b"");
+}
+
 // For each file, run rustfmt and collect the output.
 // Returns the number of files checked and the number of failures.
 fn check_files(files: Vec<PathBuf>, opt_config: &Option<PathBuf>) -> (Vec<FormatReport>, u32, u32) {
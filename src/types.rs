@@ -4,7 +4,9 @@ use rustc_ast::ast::{self, FnRetTy, Mutability, Term};
 use rustc_span::{BytePos, Pos, Span, symbol::kw};
 use tracing::debug;
 
-use crate::comment::{combine_strs_with_missing_comments, contains_comment};
+use crate::comment::{
+    combine_strs_with_missing_comments, contains_comment, rewrite_missing_comment,
+};
 use crate::config::lists::*;
 use crate::config::{IndentStyle, StyleEdition, TypeDensity};
 use crate::expr::{
@@ -222,13 +224,32 @@ impl Rewrite for ast::AssocItemConstraint {
             (Equality { .. }, TypeDensity::Wide) => " = ",
             (Equality { .. }, TypeDensity::Compressed) => "=",
         };
-        result.push_str(infix);
+        let infix_lo = self
+            .gen_args
+            .as_ref()
+            .map_or(self.ident.span.hi(), |gen_args| gen_args.span().hi());
+        let kind_lo = match self.kind {
+            Equality {
+                term: Term::Ty(ref ty),
+            } => ty.span.lo(),
+            Equality {
+                term: Term::Const(ref c),
+            } => c.value.span.lo(),
+            Bound { ref bounds } => bounds.first().map_or(self.span.hi(), |b| b.span().lo()),
+        };
+        let infix = infix_with_comments(context, infix, mk_sp(infix_lo, kind_lo), shape)?;
+        result.push_str(&infix);
 
-        let budget = shape
-            .width
-            .checked_sub(result.len())
-            .max_width_error(shape.width, self.span)?;
-        let shape = Shape::legacy(budget, shape.indent + result.len());
+        let shape = if result.contains('\n') {
+            // A line comment pushed the rest of the constraint to the next line.
+            Shape::indented(shape.indent.block_indent(context.config), context.config)
+        } else {
+            let budget = shape
+                .width
+                .checked_sub(result.len())
+                .max_width_error(shape.width, self.span)?;
+            Shape::legacy(budget, shape.indent + result.len())
+        };
         let rewrite = self.kind.rewrite_result(context, shape)?;
         result.push_str(&rewrite);
 
@@ -313,6 +334,55 @@ fn rewrite_segment(
     Ok(result)
 }
 
+/// Returns `infix` along with the comments around it in `span`, the span between the two sides
+/// of the infix. A line comment is followed by a line break.
+fn infix_with_comments(
+    context: &RewriteContext<'_>,
+    infix: &str,
+    span: Span,
+    shape: Shape,
+) -> RewriteResult {
+    if !contains_comment(context.snippet(span)) {
+        return Ok(infix.to_owned());
+    }
+    let needle = infix.trim();
+    let infix_hi = context.snippet_provider.span_after_last(span, needle);
+    let infix_lo = infix_hi - BytePos(needle.len() as u32);
+    let before = rewrite_missing_comment(mk_sp(span.lo(), infix_lo), shape, context)?;
+    let after = rewrite_missing_comment(mk_sp(infix_hi, span.hi()), shape, context)?;
+    let needs_line_break = |comment: &str| comment.starts_with("//") || comment.contains('\n');
+    let line_break = shape
+        .indent
+        .block_indent(context.config)
+        .to_string_with_newline(context.config);
+
+    let mut result = String::with_capacity(infix.len() + before.len() + after.len() + 2);
+    if infix.starts_with(' ') || !before.is_empty() {
+        result.push(' ');
+    }
+    if !before.is_empty() {
+        result.push_str(&before);
+        if needs_line_break(&before) {
+            result.push_str(&line_break);
+        } else {
+            result.push(' ');
+        }
+    }
+    result.push_str(needle);
+    if !after.is_empty() {
+        result.push(' ');
+        result.push_str(&after);
+        if needs_line_break(&after) {
+            result.push_str(&line_break);
+            return Ok(result);
+        }
+    }
+    if infix.ends_with(' ') || !after.is_empty() {
+        result.push(' ');
+    }
+    Ok(result)
+}
+
 fn format_function_type<'a, I>(
     inputs: I,
     output: &FnRetTy,
@@ -336,7 +406,11 @@ where
     let output = match *output {
         FnRetTy::Ty(ref ty) => {
             let type_str = ty.rewrite_result(context, ty_shape)?;
-            format!(" -> {type_str}")
+            let paren_hi = context
+                .snippet_provider
+                .span_after_last(mk_sp(span.lo(), ty.span.lo()), ")");
+            let arrow = infix_with_comments(context, " -> ", mk_sp(paren_hi, ty.span.lo()), shape)?;
+            format!("{arrow}{type_str}")
         }
         FnRetTy::Default(..) => String::new(),
     };
@@ -536,7 +610,7 @@ impl Rewrite for ast::GenericArg {
     }
 }
 
-fn rewrite_generic_args(
+pub(crate) fn rewrite_generic_args(
     gen_args: &ast::GenericArgs,
     context: &RewriteContext<'_>,
     shape: Shape,
@@ -434,7 +434,7 @@ macro_rules! skip_out_of_file_lines_range_err {
 macro_rules! skip_out_of_file_lines_range_visitor {
     ($self:ident, $span:expr) => {
         if out_of_file_lines_range!($self, $span) {
-            $self.push_rewrite($span, None);
+            $self.push_out_of_file_lines($span);
             return;
         }
     };
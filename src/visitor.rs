@@ -12,6 +12,7 @@ use crate::comment::{
 };
 use crate::config::{BraceStyle, Config, MacroSelector, StyleEdition};
 use crate::coverage::transform_missing_snippet;
+use crate::formatting::UnformattedReason;
 use crate::items::{
     FnBraceStyle, FnSig, ItemVisitorKind, StaticParts, StructParts, format_impl, format_trait,
     format_trait_alias, is_mod_decl, is_use_item, rewrite_extern_crate, rewrite_type_alias,
@@ -19,7 +20,7 @@ use crate::items::{
 use crate::macros::{MacroPosition, macro_style, rewrite_macro, rewrite_macro_def};
 use crate::modules::Module;
 use crate::parse::session::ParseSess;
-use crate::rewrite::{Rewrite, RewriteContext};
+use crate::rewrite::{Rewrite, RewriteContext, RewriteError, RewriteErrorExt, RewriteResult};
 use crate::shape::{Indent, Shape};
 use crate::skip::{SkipContext, is_skip_attr};
 use crate::source_map::{LineRangeUtils, SpanUtils};
@@ -118,9 +119,18 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
     fn visit_stmt(&mut self, stmt: &Stmt<'_>, include_empty_semi: bool) {
         debug!("visit_stmt: {}", self.psess.span_to_debug_info(stmt.span()));
 
+        if self.visit_off_region(stmt.span()) {
+            return;
+        }
+
         // Preserve original source snippet if the statement isn't in the selected file lines.
         if out_of_file_lines_range!(self, stmt.span()) {
             let stmt_span = source!(self, stmt.span());
+            self.report.add_unformatted_region(
+                self.psess,
+                stmt_span,
+                UnformattedReason::OutOfFileLines,
+            );
             self.push_str(self.snippet(mk_sp(self.last_pos, stmt_span.hi())));
             self.last_pos = stmt_span.hi();
             return;
@@ -171,8 +181,8 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
                     );
                 } else {
                     let shape = self.shape();
-                    let rewrite = self.with_context(|ctx| stmt.rewrite(ctx, shape));
-                    self.push_rewrite(stmt.span(), rewrite)
+                    let rewrite = self.with_context(|ctx| stmt.rewrite_result(ctx, shape));
+                    self.push_rewrite_result(stmt.span(), rewrite)
                 }
             }
             ast::StmtKind::MacCall(ref mac_stmt) => {
@@ -263,6 +273,13 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
     fn close_block(&mut self, span: Span, unindent_comment: bool) {
         let config = self.config;
 
+        // Copy the regions disabled with `// rustfmt::off` before the closing brace as they are.
+        let mut span = span;
+        while let Some(region) = self.skip_context.off_regions.overlapping(span) {
+            self.format_missing_with_indent(region.hi());
+            span = mk_sp(self.last_pos, span.hi());
+        }
+
         let mut last_hi = span.lo();
         let mut unindented = false;
         let mut prev_ends_with_newline = false;
@@ -440,6 +457,9 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
     }
 
     pub(crate) fn visit_item(&mut self, item: &ast::Item) {
+        if self.visit_off_region(item.span()) {
+            return;
+        }
         skip_out_of_file_lines_range_visitor!(self, item.span);
 
         // This is where we bail out if there is a skip attribute. This is only
@@ -499,19 +519,19 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
                 ast::ItemKind::Impl(ref iimpl) => {
                     let block_indent = self.block_indent;
                     let rw = self.with_context(|ctx| format_impl(ctx, item, iimpl, block_indent));
-                    self.push_rewrite(item.span, rw.ok());
+                    self.push_rewrite_result(item.span, rw);
                 }
                 ast::ItemKind::Trait(ref trait_kind) => {
                     let block_indent = self.block_indent;
                     let rw =
                         self.with_context(|ctx| format_trait(ctx, item, trait_kind, block_indent));
-                    self.push_rewrite(item.span, rw.ok());
+                    self.push_rewrite_result(item.span, rw);
                 }
                 ast::ItemKind::TraitAlias(ref ta) => {
                     let shape = Shape::indented(self.block_indent, self.config);
                     let rw =
                         format_trait_alias(&self.get_context(), ta, &item.vis, item.span, shape);
-                    self.push_rewrite(item.span, rw.ok());
+                    self.push_rewrite_result(item.span, rw);
                 }
                 ast::ItemKind::ExternCrate(..) => {
                     let rw = rewrite_extern_crate(&self.get_context(), item, self.shape());
@@ -520,7 +540,7 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
                     } else {
                         mk_sp(attrs[0].span.lo(), item.span.hi())
                     };
-                    self.push_rewrite(span, rw.ok());
+                    self.push_rewrite_result(span, rw);
                 }
                 ast::ItemKind::Struct(..) | ast::ItemKind::Union(..) => {
                     self.visit_struct(&StructParts::from_item(item));
@@ -591,18 +611,16 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
                         )
                     } else {
                         let indent = self.block_indent;
-                        let rewrite = self
-                            .rewrite_required_fn(
-                                indent,
-                                ident,
-                                sig,
-                                &item.vis,
-                                generics,
-                                defaultness,
-                                item.span,
-                            )
-                            .ok();
-                        self.push_rewrite(item.span, rewrite);
+                        let rewrite = self.rewrite_required_fn(
+                            indent,
+                            ident,
+                            sig,
+                            &item.vis,
+                            generics,
+                            defaultness,
+                            item.span,
+                        );
+                        self.push_rewrite_result(item.span, rewrite);
                     }
                 }
                 ast::ItemKind::TyAlias(ref ty_alias) => {
@@ -622,9 +640,8 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
                         ident,
                         &item.vis,
                         item.span,
-                    )
-                    .ok();
-                    self.push_rewrite(item.span, rewrite);
+                    );
+                    self.push_rewrite_result(item.span, rewrite);
                 }
                 ast::ItemKind::Delegation(..) | ast::ItemKind::DelegationMac(..) => {
                     // TODO: rewrite delegation items once syntax is established.
@@ -650,9 +667,8 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
             self.block_indent,
             visitor_kind,
             span,
-        )
-        .ok();
-        self.push_rewrite(span, rewrite);
+        );
+        self.push_rewrite_result(span, rewrite);
     }
 
     fn visit_assoc_item(&mut self, ai: &ast::AssocItem, visitor_kind: ItemVisitorKind) {
@@ -663,6 +679,9 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
             AssocImplItem => visit::AssocCtxt::Impl { of_trait: false },
             _ => unreachable!(),
         };
+        if self.visit_off_region(ai.span()) {
+            return;
+        }
         // TODO(calebcartwright): Not sure the skip spans are correct
         let skip_span = ai.span;
         skip_out_of_file_lines_range_visitor!(self, ai.span);
@@ -702,18 +721,16 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
                     );
                 } else {
                     let indent = self.block_indent;
-                    let rewrite = self
-                        .rewrite_required_fn(
-                            indent,
-                            fn_kind.ident,
-                            sig,
-                            &ai.vis,
-                            generics,
-                            defaultness,
-                            ai.span,
-                        )
-                        .ok();
-                    self.push_rewrite(ai.span, rewrite);
+                    let rewrite = self.rewrite_required_fn(
+                        indent,
+                        fn_kind.ident,
+                        sig,
+                        &ai.vis,
+                        generics,
+                        defaultness,
+                        ai.span,
+                    );
+                    self.push_rewrite_result(ai.span, rewrite);
                 }
             }
             (ast::AssocItemKind::Type(ref ty_alias), _) => {
@@ -743,7 +760,7 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
 
         // 1 = ;
         let shape = self.shape().saturating_sub_width(1);
-        let rewrite = self.with_context(|ctx| rewrite_macro(mac, ctx, shape, pos).ok());
+        let rewrite = self.with_context(|ctx| rewrite_macro(mac, ctx, shape, pos));
         // As of v638 of the rustc-ap-* crates, the associated span no longer includes
         // the trailing semicolon. This determines the correct span to ensure scenarios
         // with whitespace between the delimiters and trailing semi (i.e. `foo!(abc)     ;`)
@@ -765,7 +782,7 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
             _ => (mac.span(), rewrite),
         };
 
-        self.push_rewrite(span, rewrite);
+        self.push_rewrite_result(span, rewrite);
     }
 
     pub(crate) fn push_str(&mut self, s: &str) {
@@ -785,10 +802,69 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
     }
 
     pub(crate) fn push_rewrite(&mut self, span: Span, rewrite: Option<String>) {
+        self.push_rewrite_result(span, rewrite.unknown_error());
+    }
+
+    /// Pushes the rewrite of `span`, or if it failed the original snippet, recording why it
+    /// was left unformatted.
+    pub(crate) fn push_rewrite_result(&mut self, span: Span, rewrite: RewriteResult) {
+        if let Err(ref error) = rewrite {
+            if let Some(reason) = UnformattedReason::from_rewrite_error(error) {
+                self.report
+                    .add_unformatted_region(self.psess, source!(self, span), reason);
+            }
+        }
+        // Regions disabled with `// rustfmt::off` in the middle of an expression can't be
+        // copied on their own, so the whole node is left as it is.
+        let rewrite = rewrite.ok().filter(|rewrite| {
+            let keeps_off_regions = self
+                .skip_context
+                .off_regions
+                .within(source!(self, span))
+                .all(|region| rewrite.contains(self.snippet(region)));
+            if !keeps_off_regions {
+                self.report.add_unformatted_region(
+                    self.psess,
+                    source!(self, span),
+                    UnformattedReason::OffMarker,
+                );
+            }
+            keeps_off_regions
+        });
         self.format_missing_with_indent(source!(self, span).lo());
         self.push_rewrite_inner(span, rewrite);
     }
 
+    /// Copies the source of a node starting in a region disabled with `// rustfmt::off` as it
+    /// is, up to the end of the region or of the node, whichever comes last. Returns `false`
+    /// if the node doesn't start in such a region.
+    fn visit_off_region(&mut self, span: Span) -> bool {
+        let span = source!(self, span);
+        let Some(region) = self.skip_context.off_regions.containing(span.lo()) else {
+            return false;
+        };
+        if self.last_pos < region.lo() {
+            self.format_missing_with_indent(region.lo());
+        }
+        let hi = region.hi().max(span.hi());
+        if self.last_pos < hi {
+            self.push_str(self.snippet(mk_sp(self.last_pos, hi)));
+            self.last_pos = hi;
+        }
+        true
+    }
+
+    /// Pushes the original snippet of `span`, which is outside the lines selected with
+    /// `file_lines`.
+    pub(crate) fn push_out_of_file_lines(&mut self, span: Span) {
+        self.report.add_unformatted_region(
+            self.psess,
+            source!(self, span),
+            UnformattedReason::OutOfFileLines,
+        );
+        self.push_rewrite_result(span, Err(RewriteError::SkipFormatting));
+    }
+
     pub(crate) fn push_skipped_with_span(
         &mut self,
         attrs: &[ast::Attribute],
@@ -796,6 +872,11 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
         main_span: Span,
     ) {
         self.format_missing_with_indent(source!(self, item_span).lo());
+        self.report.add_unformatted_region(
+            self.psess,
+            source!(self, item_span),
+            UnformattedReason::SkipAttribute,
+        );
         // do not take into account the lines with attributes as part of the skipped range
         let attrs_end = attrs
             .iter()
@@ -1089,7 +1170,13 @@ impl<'b, 'a: 'b> FmtVisitor<'a> {
             return false;
         };
 
-        if self.is_macro_def {
+        if self.is_macro_def
+            || self
+                .skip_context
+                .off_regions
+                .containing(stmt.span.lo())
+                .is_some()
+        {
             return false;
         }
 
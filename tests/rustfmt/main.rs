@@ -1,7 +1,7 @@
 //! Integration tests for rustfmt.
 
 use std::env;
-use std::fs::{File, remove_file};
+use std::fs::{File, read_to_string, remove_file};
 use std::path::Path;
 use std::process::Command;
 
@@ -181,8 +181,19 @@ fn rustfmt_nightly_usage_text() {
             --check         Run in 'check' mode. Exits with 0 if input is
                             formatted correctly. Exits with 1 and prints a diff if
                             formatting is required.
-            --emit [files|stdout|coverage|checkstyle|json]
+            --emit [files|stdout|coverage|checkstyle|json|unformatted-report]
                             What data to emit and how
+            --summary-json  Print a JSON summary of checked, reformatted and
+                            skipped files and errors instead of the usual output.
+            --verify-idempotent 
+                            Format each file a second time and report any changes
+                            made by the second pass.
+            --verify-tokens 
+                            Compare the tokens and comments of each formatted file
+                            with the original, and leave the file unchanged if
+                            they differ.
+            --markdown      Format the Rust code blocks of the given Markdown
+                            files, leaving the rest of them untouched.
             --backup        Backup any modified files.
             --config-path [Path for the configuration file]
                             Recursively searches the given path for the
@@ -379,3 +390,43 @@ fn rustfmt_allow_not_a_dir_errors() {
     assert_eq!(stdout, "");
     assert_eq!(stderr, "");
 }
+
+#[nightly_only_test]
+#[test]
+fn rustfmt_summary_json_counts_reformatted_and_skipped_files() {
+    let args = [
+        "--check",
+        "--summary-json",
+        "tests/writemode/source/summary/lib.rs",
+    ];
+    let (stdout, _) = rustfmt(&args);
+    let summary: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    let summary = &summary[0];
+    assert_eq!(summary["files_checked"], 1);
+    assert_eq!(summary["files_reformatted"], 1);
+    assert_eq!(summary["skipped"]["skip_attribute"], 1);
+    assert_eq!(summary["errors"], serde_json::json!({}));
+}
+
+#[nightly_only_test]
+#[test]
+fn rustfmt_formats_rust_code_blocks_in_markdown() {
+    let args = [
+        "--markdown",
+        "--emit",
+        "stdout",
+        "--quiet",
+        "tests/markdown/source/chapter.md",
+    ];
+    let (stdout, stderr) = rustfmt(&args);
+    assert_eq!(stderr, "");
+    assert_eq!(
+        stdout,
+        read_to_string("tests/markdown/target/chapter.md").unwrap()
+    );
+
+    let args = ["--markdown", "--check", "tests/markdown/target/chapter.md"];
+    let (stdout, stderr) = rustfmt(&args);
+    assert_eq!(stdout, "");
+    assert_eq!(stderr, "");
+}
@@ -0,0 +1,27 @@
+// rustfmt-style_edition: 2027
+// Inline assembly is formatted with one template string or operand per line when it doesn't fit
+// on a single line.
+
+fn short() {
+    unsafe {
+        asm!( "nop" );
+        asm!("mov {0}, {1}",out(reg)x,in(reg)  y);
+        core::arch::asm!("mov {}, {}", out(reg) x, const   5);
+    }
+}
+
+fn long() {
+    unsafe {
+        asm!("mov {tmp}, {x}", "shl {tmp}, 1", "shl {x}, 2", "add {x}, {tmp}", x = inout(reg) x, tmp = out(reg) _);
+        asm!("syscall", in("rax") 60, in("rdi") code, lateout("rcx") _, lateout("r11") _, options(noreturn,nostack));
+        asm!("call {f}", inlateout("rax") value => result, f = sym callback, clobber_abi( "C","system" ));
+    }
+}
+
+global_asm!( "    .globl my_function" , "my_function:","    ret", options( att_syntax,raw ) );
+
+#[unsafe(naked)]
+extern "C" fn naked() {
+    naked_asm!("mov rax, 1",
+               "ret");
+}
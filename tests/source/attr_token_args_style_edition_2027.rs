@@ -0,0 +1,25 @@
+// rustfmt-style_edition: 2027
+
+#[tracing::instrument(skip(self),fields(id=%req.id,method=?req.method))]
+fn handle(&self, req: Request) {}
+
+#[tracing::instrument(level = "debug", skip(self, request, response), fields(http.method = %request.method, http.status = ?response.status))]
+fn respond(&self, request: &Request, response: &Response) {}
+
+#[derive(Parser)]
+struct Cli {
+    #[clap(short,long,default_value_t=Level::Info)]
+    level: Level,
+    #[clap(long, value_parser = parse_duration, default_value = "30s", help = "How long to wait for a response")]
+    timeout: Duration,
+}
+
+#[my_attr(a+b, c,)]
+fn trailing_comma() {}
+
+// Arguments which can't be parsed are left as they are.
+#[my_attr(a b,  c)]
+fn unparseable() {}
+
+#[serde(rename_all = "camelCase",  deserialize_with = "path::to")]
+struct Meta;
@@ -0,0 +1,35 @@
+// rustfmt-style_edition: 2027
+// Format the bodies of macros invoked with braces as items, statements or match arms.
+
+thread_local! {
+    static COUNTER:Cell<u32>=Cell::new(0);
+    pub static   NAMES : RefCell<Vec<String>> = RefCell::new(Vec::new());
+}
+
+my_items! { fn   foo( x:u32)->u32{x+1}   struct Bar{a:u32,b:String} }
+
+fn main() {
+    with_retries! {
+        let   conn=connect( &config )?;
+        // Keep this comment.
+        conn.send(  message  )?
+    }
+
+    let name = dispatch! {
+        Kind::A|Kind::B=>"a or b",
+        Kind::C(x) if x>0 => {"positive c"}
+        _=>"other"
+    };
+
+    routes! {
+        Get(path)=>{serve(path)},
+        Post(path,body)=>{ store(path,body) }
+    }
+
+    html! { <div class="greeting">Hello</div> }
+
+    select! {
+        v = rx.recv() => handle(v),
+        _ = timeout   => {}
+    }
+}
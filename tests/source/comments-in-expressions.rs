@@ -0,0 +1,42 @@
+// Comments inside chains, binary expressions, match arms and types are kept.
+
+fn chains() {
+    let x = foo.bar()   . /* c1 */ baz()   .qux();
+    let y = foo.bar()   .baz::</* c2 */ u32>()   .qux();
+    let z = foo.bar(). // c14
+        baz().qux();
+}
+
+fn pairs() {
+    let y = aaaa + // c3
+        bbbb   * cccc;
+    let z = aaaa // c4
+        &&   bbbb;
+    let w = aaaa /* c5 */ +   bbbb;
+}
+
+fn arms() {
+    match x {
+        A /* c6 */ | B =>   {}
+        C /* c7 */ if cond =>   {}
+        D if /* c8 */ cond =>   {}
+        E if cond /* c9 */ =>   {}
+        G if // c15
+            cond =>   {}
+        H if cond // c16
+            =>   {}
+        I // c17
+            =>   1,
+    }
+}
+
+fn types() {
+    let t: Foo<A = /* c10 */ u32> =   x;
+    let t: Foo<A /* c11 */ = u32> =   x;
+    let f: fn(u32) -> /* c12 */ u32 =   f;
+    let f: fn(u32) /* c13 */ -> u32 =   f;
+    let t: Foo<A = // c18
+        u32> =   x;
+    let f: fn(u32) -> // c19
+        u32 =   f;
+}
@@ -0,0 +1,9 @@
+// rustfmt-derive_order: Alphabetical
+
+#[derive(PartialEq, Debug, Clone)]
+#[derive(Debug, Eq)]
+pub struct Foo;
+
+#[derive(serde::Serialize, Clone, serde::Deserialize, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(Arbitrary, Debug))]
+pub struct Bar;
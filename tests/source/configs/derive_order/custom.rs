@@ -0,0 +1,7 @@
+// rustfmt-derive_order: ["Debug","Clone","Copy","PartialEq","Eq","Hash"]
+
+#[derive(Hash, Eq, PartialEq, Copy, Clone, Debug)]
+pub struct Foo;
+
+#[derive(serde::Serialize, std::fmt::Debug, Default, Clone, Clone)]
+pub struct Bar;
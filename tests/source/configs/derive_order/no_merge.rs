@@ -0,0 +1,6 @@
+// rustfmt-derive_order: Alphabetical
+// rustfmt-merge_derives: false
+
+#[derive(PartialEq, Debug)]
+#[derive(Debug, Clone, Clone)]
+pub struct Foo;
@@ -0,0 +1,5 @@
+// rustfmt-derive_order: Preserve
+
+#[derive(PartialEq, Debug, Debug)]
+#[derive(Clone)]
+pub struct Foo;
@@ -0,0 +1,24 @@
+// rustfmt-format_code_in_doc_comments: true
+// rustfmt-doc_code_block_rust_tags: ["no_compile", "title=*"]
+
+/// ```rust,no_compile
+/// let   x=1;
+/// ```
+///
+/// ```rust title=example.rs
+/// let   y=2;
+/// ```
+///
+/// ```rust,unknown
+/// let   z=3;
+/// ```
+fn foo() {}
+
+/// ```edition2018
+/// async   fn f() { x.await  }
+/// ```
+///
+/// ```edition2015
+/// let   async=  1;
+/// ```
+fn bar() {}
@@ -0,0 +1,12 @@
+// rustfmt-format_code_in_doc_comments: true
+// rustfmt-edition: 2015
+// The code blocks of another edition are parsed with that edition.
+
+/// ```edition2018
+/// async   fn fetch(client:&Client)->Response { client.get( ).await  }
+/// ```
+///
+/// ```
+/// let   async=  1;
+/// ```
+fn fetch() {}
@@ -0,0 +1,15 @@
+// rustfmt-format_code_in_doc_comments: true
+// rustfmt-doc_code_block_skip_tags: ["no_run", "ignore-*"]
+
+/// ```
+/// let   x=1;
+/// ```
+///
+/// ```no_run
+/// let   y=2;
+/// ```
+///
+/// ```rust,ignore-wasm32
+/// let   z=3;
+/// ```
+fn foo() {}
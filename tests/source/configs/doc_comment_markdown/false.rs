@@ -0,0 +1,43 @@
+// rustfmt-doc_comment_markdown: false
+// Reflow doc comments as Markdown
+
+//! Crate documentation that is
+//! split over
+//! several short lines.
+//!
+//! Overview
+//! ========
+//!
+//! + First item
+//! + Second item, which is quite long and must be wrapped because it does not fit on a single line
+//!     * nested item
+//!
+//! 1) first step
+//! 1) second step
+
+/// Frobnicates the widget. See [the frobnication guide](https://example.com/guides/frobnication) and `Widget::frobnicate_with_care()` for details.
+///
+/// ### Examples ###
+///
+/// - Call it:
+///
+///   ```
+///   let   x = frobnicate();
+///   ```
+/// - Don't call it twice.
+///
+/// > Quoted text that goes on and on until it has to be wrapped onto the next line.
+///
+/// | Column | Other column with a very long header that must not be wrapped at all |
+/// |--------|------------------------------------------------------------------------|
+///
+/// First line\
+/// second line
+fn frobnicate() {}
+
+struct Widget;
+
+impl Widget {
+    /// Frobnicates the widget with care, which takes a little longer than frobnicating it without.
+    fn frobnicate_with_care(&self) {}
+}
@@ -0,0 +1,9 @@
+// rustfmt-inline_format_args: true
+// rustfmt-edition: 2018
+// Before the 2021 edition, `panic!` doesn't format a lone format string.
+
+fn main() {
+    panic!("{}", reason);
+    panic!("{}: {}", code, reason);
+    panic!("{}: {}", code.0, reason);
+}
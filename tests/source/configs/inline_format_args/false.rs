@@ -0,0 +1,7 @@
+// rustfmt-inline_format_args: false
+// Inline format args
+
+fn main() {
+    let s = format!("{} {:?} {:>8.3}", a, b, c);
+    println!("{}, {}!", greeting.trim(), name);
+}
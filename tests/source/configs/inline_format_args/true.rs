@@ -0,0 +1,24 @@
+// rustfmt-inline_format_args: true
+// rustfmt-edition: 2021
+// Inline format args
+
+fn main() {
+    let s = format!("{} {:?} {:>8.3}", a, b, c);
+    println!("{}, {}!", greeting.trim(), name);
+    println!("{}, {}!", name, greeting.trim());
+    println!(r#"{} said "{}""#, who, what);
+    assert_eq!(left, right, "{} != {}", left, right);
+    write!(f, "{{{}}}", inner)?;
+    eprintln!("{0} {}", a, b);
+    eprintln!("{:1$}", a, width);
+    eprintln!("{} {name}", a, name = b);
+    eprintln!("{}", self);
+    eprintln!("{}", r#type);
+    panic!("{}", reason);
+}
+
+macro_rules! show {
+    ($x:expr) => {
+        println!("{}", $x)
+    };
+}
@@ -0,0 +1,7 @@
+// rustfmt-macro_shapes: {"vec": "Verbatim", "println": "Call"}
+// Configured shapes override the built-in ones
+
+fn main() {
+    let v = vec!(1,2,3);
+    println!("{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}{}", 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15);
+}
@@ -0,0 +1,17 @@
+// rustfmt-macro_shapes: {"ensure": "Format(1)", "my_vec": "Array", "sql_params": "Call", "my_items": "Items", "my_stmts": "Statements", "html": "Verbatim"}
+// Macro shapes
+
+fn main() {
+    ensure!(some_condition_that_is_long, "the condition does not hold for {} and {}", first_value, second_value);
+    let v = my_vec!(1,2,3);
+    sql_params!{ user_id, name };
+    sql_params!{ user_id, name }
+    let after = 1;
+    let doc = html! { <div>   </div> };
+    my_stmts! {
+        let x=1;
+        call(x)
+    }
+}
+
+my_items! { fn a( ) { } struct B{x:u8} }
@@ -0,0 +1,14 @@
+// rustfmt-normalize_doc_comments: false
+// Normalize doc comments
+
+/**
+ * Frobnicates the [`crate::Widget`](crate::Widget).
+ *
+ *
+ * Returns nothing.
+ */
+fn frobnicate() {}
+
+///Does nothing.
+///
+fn nothing() {}
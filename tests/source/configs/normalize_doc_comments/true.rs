@@ -0,0 +1,37 @@
+// rustfmt-normalize_doc_comments: true
+// Normalize doc comments
+
+//!Crate documentation.
+//!
+//!
+
+/**
+ * Frobnicates the [`crate::Widget`](crate::Widget).
+ *
+ *
+ * See [the guide](crate::guide) and [`Widget::new`](Widget::new).
+ */
+fn frobnicate() {}
+
+///    Indented by more than one space:
+///      - relatively indented item
+///
+///
+fn nothing() {}
+
+///Collapses blank lines outside of code blocks only.
+///
+///
+/// ```
+/// let a = 1;
+///
+///
+/// let b = [`a`](a);
+/// ```
+///
+fn code() {}
+
+struct Foo {
+    /** The bar. */
+    bar: u32,
+}
@@ -0,0 +1,12 @@
+// rustfmt-format_strings: true
+// rustfmt-string_split_style: Concat
+
+#[must_use = "the returned guard releases the lock when it is dropped, so it has to be kept alive for as long as the lock is needed"]
+fn lock() -> Guard {
+    todo!()
+}
+
+fn main() {
+    bail!("the answer to life, the universe and everything is {}, which was computed after a very long time", answer);
+    my_macro!("the answer to life, the universe and everything is {answer:>8}, which was computed after a very long time");
+}
@@ -0,0 +1,11 @@
+// rustfmt-format_strings: true
+// rustfmt-string_split_style: Concat
+
+fn main() {
+    let lorem = "ipsum dolor sit amet consectetur adipiscing elit lorem ipsum dolor sit amet consectetur adipiscing";
+    let escapes = "a tab\t, a quote \", a unicode escape \u{1F600} and an ascii escape \x41 in a long string that goes on";
+    let raw = r#"a raw string with "quotes" and a C:\path\to\a\file which is too long to fit on a single line"#;
+    let short = "fits on one line";
+    println!("the answer to life, the universe and everything is {:>8}, which was computed after a very long time", answer);
+    println!("the answer to life, the universe and everything is {answer:>8}, which was computed after a very long time");
+}
@@ -0,0 +1,8 @@
+// rustfmt-format_strings: true
+// rustfmt-string_split_style: Mixed
+
+fn main() {
+    let lorem = "ipsum dolor sit amet consectetur adipiscing elit lorem ipsum dolor sit amet consectetur adipiscing";
+    let raw = r#"a raw string with "quotes" and a C:\path\to\a\file which is too long to fit on a single line"#;
+    println!("the answer to life, the universe and everything is {:>8}, which was computed after a very long time", answer);
+}
@@ -0,0 +1,26 @@
+// rustfmt-format_macro_matchers: true
+// Macros 2.0 definitions get the same treatment as `macro_rules!`.
+
+pub   macro add {
+    ( $ x : expr ) => { $x+1 },
+    ( $ x : expr , $ y : expr ) => (
+        $x+$y
+    )
+}
+
+pub(crate) macro make_fn( $ name : ident ) {
+    fn $name() -> u32 { 42 }
+}
+
+/// A single arm in braces.
+#[allow(unused_macros)]
+pub(in crate::macros) macro unit {
+    () => { () },
+}
+
+macro empty {}
+
+macro unseparated {
+    ( $ x : expr ) => { $x }
+    () => { }
+}
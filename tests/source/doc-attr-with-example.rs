@@ -0,0 +1,41 @@
+// rustfmt-format_code_in_doc_comments: true
+
+#![doc = "```\nlet   x=1;\n```"]
+//! Inner doc comment.
+//!
+//! ```
+//! # use   std::io;
+//! let   y=2;
+//! ```
+
+#[doc = " Escaped example:\n ```\n let   s=\"x\";\n ```"]
+fn escaped() {}
+
+#[doc = "
+ Multi-line example:
+ ```
+ # fn   hidden() {  }
+ fn   bar() {  }
+ ```
+"]
+fn bar() {}
+
+#[doc = r#"
+```rust
+let   greeting="hello";
+```
+"#]
+fn raw() {}
+
+#[doc = "```text\nlet   untouched=1;\n```"]
+fn text() {}
+
+#[doc = "Caf\u{e9} \x41:\n```\nlet   x=1;\n```"]
+fn unicode_escapes() {}
+
+#[doc = "Continued \
+         example:\n\
+         ```\n\
+         let   x=1;\n\
+         ```"]
+fn continued() {}
@@ -0,0 +1,42 @@
+// rustfmt-style_edition: 2027
+// Format the contents of the repetitions of `macro_rules!` transcribers.
+
+macro_rules! impl_zero {
+    ($($t:ty),*) => {
+        $(
+            impl Zero for $t { fn zero()->Self{ 0 as $t } }
+        )*
+    };
+}
+
+macro_rules! my_vec {
+    ($($x:expr),*) => {
+        <[_]>::into_vec(Box::new([ $( $x ),* ]))
+    };
+}
+
+macro_rules! collect {
+    ($($x:expr),* $(,)?) => {{
+        let mut v=Vec::new();
+        $( v.push( $x ); )*
+        v
+    }};
+}
+
+macro_rules! sum {
+    ($($x:expr),+) => { 0 $( + $x )+ };
+}
+
+// The whole macro is left as written if the contents of a repetition can't be formatted...
+macro_rules! decl {
+    ($($name:ident: $ty:ty),*) => {
+        fn decl(){ $( $name : $ty; )* }
+    };
+}
+
+// ...or if they no longer fit once put back in place of their placeholder.
+macro_rules! long_list {
+    ($($x:expr),*) => {
+        some_function_with_a_long_name(first_argument, second_argument, [ $( transform_the_value($x) ),* ])
+    };
+}
@@ -0,0 +1,44 @@
+// Regions between `// rustfmt::off` and `// rustfmt::on` are copied as they are.
+
+// rustfmt::off
+const   IDENTITY : [i32;9] = [1, 0, 0,
+                              0, 1, 0,
+                              0, 0, 1];
+// rustfmt::on
+
+fn   formatted( ) { }
+
+fn statements() {
+    let  x =  1 ;
+      // rustfmt::off
+    let matrix = [1, 0,
+                  0, 1];
+    let  y =  2 ;
+    // rustfmt::on
+    let  z =  3 ;
+}
+
+fn trailing_region() {
+    foo( );
+        // rustfmt::off
+    // nothing   here
+    // rustfmt::on
+}
+
+fn within_expression() {
+    let   x = [
+        // rustfmt::off
+        1,0,
+        0,1,
+        // rustfmt::on
+    ];
+    bar( );
+}
+
+impl Foo {
+    // rustfmt::off
+    fn   kept ( ) { }
+    // rustfmt::on
+
+    fn   formatted ( ) { }
+}
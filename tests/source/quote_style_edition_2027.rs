@@ -0,0 +1,16 @@
+// rustfmt-style_edition: 2027
+// Format the Rust code in `quote!` and `parse_quote!` with `#` interpolations.
+
+fn expand(input: &Input) -> TokenStream {
+    let name = &input.ident;
+    let fields = input.fields.iter().map(|f| &f.ident);
+    let ty: Type = parse_quote!( Vec < #name > );
+    let sum = quote!(#a+#b);
+    let unparseable = quote! { #vis   struct #name; };
+    quote! {
+        #[automatically_derived]
+        impl   #name {
+            pub fn   field_names()->Vec<&'static str>{ vec![#(stringify!(#fields)),*] }
+        }
+    }
+}
@@ -0,0 +1,5 @@
+mod skipped;
+
+fn   main( )   {
+    println!("Hello, world!")  ;
+}
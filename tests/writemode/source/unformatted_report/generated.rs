@@ -0,0 +1,3 @@
+// @generated
+
+fn   left_as_is( )   {}
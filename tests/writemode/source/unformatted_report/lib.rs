@@ -0,0 +1,11 @@
+mod generated;
+mod skipped;
+
+#[rustfmt::skip]
+fn skipped_item() {
+    let   x  =  1;
+}
+
+fn main() {
+    foo!(a b c);
+}
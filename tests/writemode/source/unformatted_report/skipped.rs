@@ -0,0 +1,3 @@
+#![rustfmt::skip]
+
+fn   left_as_is( )   {}